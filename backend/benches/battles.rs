@@ -0,0 +1,83 @@
+//! Battles per second fought one at a time on a single thread, with pet stats, and as a batch.
+//! * `one worker` is the batch before it was split across workers. `pooled` is split across every simulation worker.
+//! ```bash
+//! cargo bench --bench battles
+//! ```
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use backend::battle::{
+    attribution::BattleLog,
+    batch::run_batch_split,
+    pool::num_workers,
+    response::{build_teams, fight_teams},
+    team::Teams,
+};
+
+const RUNS: u64 = 500;
+const SEED: u64 = 42;
+
+fn report(name: &str, runs: u64, elapsed: Duration) {
+    let battles_per_sec = runs as f64 / elapsed.as_secs_f64();
+    println!(
+        "{name:<12} {runs:>6} battles in {elapsed:>10.2?} ({battles_per_sec:>10.1} battles/sec)"
+    );
+}
+
+fn sequential(teams: &Teams) -> Duration {
+    let start = Instant::now();
+    for run in 0..RUNS {
+        let (mut team, mut enemy_team) =
+            build_teams(teams.clone(), SEED + run).expect("Invalid test teams.");
+        let _ = black_box(fight_teams(&mut team, &mut enemy_team, |_, _, _| {}));
+    }
+    start.elapsed()
+}
+
+/// Same as [`sequential`] but also works out pet stats like a batch does.
+fn attributed(teams: &Teams) -> Duration {
+    let start = Instant::now();
+    for run in 0..RUNS {
+        let (mut team, mut enemy_team) =
+            build_teams(teams.clone(), SEED + run).expect("Invalid test teams.");
+        let mut log = BattleLog::new(&team, &mut enemy_team);
+        log.record_turn(0, &team, &enemy_team);
+        let _ = black_box(fight_teams(
+            &mut team,
+            &mut enemy_team,
+            |turn, team, enemy_team| log.record_turn(turn, team, enemy_team),
+        ));
+        black_box(log.finish(&team, &mut enemy_team));
+    }
+    start.elapsed()
+}
+
+fn batch(runtime: &tokio::runtime::Runtime, teams: &Teams, workers: usize) -> Duration {
+    let start = Instant::now();
+    runtime
+        .block_on(run_batch_split(teams.clone(), SEED, RUNS, workers))
+        .expect("Invalid test teams.");
+    start.elapsed()
+}
+
+fn main() {
+    let teams: Teams = serde_json::from_str(include_str!("../tests/test_teams.json")).unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    // Warm up the database connection and thread pool.
+    batch(&runtime, &teams, num_workers());
+
+    println!("Workers: {}", num_workers());
+    report("sequential", RUNS, sequential(&teams));
+    report("attributed", RUNS, attributed(&teams));
+    let baseline = batch(&runtime, &teams, 1);
+    report("one worker", RUNS, baseline);
+    let pooled = batch(&runtime, &teams, num_workers());
+    report("pooled", RUNS, pooled);
+    println!(
+        "Speedup: {:.2}x",
+        baseline.as_secs_f64() / pooled.as_secs_f64()
+    );
+}
@@ -0,0 +1,59 @@
+//! Embeds the built frontend when the `embed-frontend` feature is enabled.
+//! * The frontend is read from `SAPTESTER_FRONTEND_DIST`, `../frontend/dist` by default.
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const FRONTEND_DIST_VAR: &str = "SAPTESTER_FRONTEND_DIST";
+const DEFAULT_FRONTEND_DIST: &str = "../frontend/dist";
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-env-changed={FRONTEND_DIST_VAR}");
+    if env::var_os("CARGO_FEATURE_EMBED_FRONTEND").is_none() {
+        return Ok(());
+    }
+
+    let dist_dir = env::var(FRONTEND_DIST_VAR).unwrap_or_else(|_| DEFAULT_FRONTEND_DIST.to_owned());
+    let dist_dir = fs::canonicalize(&dist_dir)
+        .map_err(|err| format!("Frontend not built at {dist_dir}: {err}"))?;
+    println!("cargo:rerun-if-changed={}", dist_dir.display());
+
+    let mut files = vec![];
+    collect_files(&dist_dir, &mut files)?;
+    files.sort();
+
+    let mut entries = String::new();
+    for file in files {
+        let Some(file_path) = file.strip_prefix(&dist_dir)?.to_str() else {
+            continue;
+        };
+        // Paths are matched against url paths so always use forward slashes.
+        let file_path = file_path.replace('\\', "/");
+        entries.push_str(&format!(
+            "    ({file_path:?}, include_bytes!({:?})),\n",
+            file.display().to_string()
+        ));
+    }
+
+    let out_file = PathBuf::from(env::var("OUT_DIR")?).join("frontend_files.rs");
+    fs::write(
+        out_file,
+        format!("pub static FRONTEND_FILES: &[(&str, &[u8])] = &[\n{entries}];\n"),
+    )?;
+    Ok(())
+}
@@ -1,21 +1,130 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::{
+    convert::DEFAULT_TEAM_NAME,
+    logging::{parse_log_filter, LogFormat, LogOutput, LogRotation},
+};
 
 // https://github.com/dxps/fullstack-rust-axum-dioxus-rwa/blob/main/backend/src/bin/server.rs
-#[derive(Parser, Debug)]
+/// Arguments override values from the config file and environment variables.
+#[derive(Parser, Debug, Default)]
 #[clap(
     name = "server",
     about = "The server side of Fullstack Rust RealWorld App project."
 )]
 pub struct Args {
-    /// The HTTP listening address.
-    #[clap(short = 'a', long = "addr", default_value = "0.0.0.0")]
-    pub addr: String,
+    /// Convert a team and exit instead of serving.
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// The TOML config file.
+    #[clap(short = 'c', long = "config")]
+    pub config: Option<PathBuf>,
+
+    /// Print the merged config as TOML and exit.
+    #[clap(long = "print-config")]
+    pub print_config: bool,
+
+    /// The HTTP listening address. [default: 0.0.0.0]
+    #[clap(short = 'a', long = "addr")]
+    pub addr: Option<String>,
+
+    /// The HTTP listening port. [default: 3030]
+    #[clap(short = 'p', long = "port")]
+    pub port: Option<u16>,
+
+    /// The logging level. Accepts filter directives. ex. `info,backend=debug` [default: info]
+    #[clap(short = 'l', long = "log", value_parser = parse_log_filter)]
+    pub log_level: Option<String>,
+
+    /// The log format. [default: text]
+    #[clap(long = "log-format", value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Where logs are written. [default: file]
+    #[clap(long = "log-output", value_enum)]
+    pub log_output: Option<LogOutput>,
+
+    /// The log file. Rotated files are suffixed with their date. [default: backend.log]
+    #[clap(long = "log-file")]
+    pub log_file: Option<PathBuf>,
+
+    /// How often the log file is rotated. [default: daily]
+    #[clap(long = "log-rotation", value_enum)]
+    pub log_rotation: Option<LogRotation>,
+
+    /// The directory cached images are stored in. [default: assets]
+    #[clap(long = "assets")]
+    pub assets_dir: Option<PathBuf>,
+
+    /// Directory with the built frontend to serve.
+    #[clap(long = "frontend")]
+    pub frontend_dir: Option<PathBuf>,
 
-    /// The HTTP listening port.
-    #[clap(short = 'p', long = "port", default_value = "3030")]
-    pub port: u16,
+    /// Database reads allowed per client per minute. [default: 120]
+    #[clap(long = "db-rate")]
+    pub db_rate: Option<u32>,
+
+    /// Database reads a client can make at once. [default: 60]
+    #[clap(long = "db-burst")]
+    pub db_burst: Option<u32>,
+
+    /// Battle simulations allowed per client per minute. [default: 30]
+    #[clap(long = "battle-rate")]
+    pub battle_rate: Option<u32>,
+
+    /// Battle simulations a client can make at once. [default: 10]
+    #[clap(long = "battle-burst")]
+    pub battle_burst: Option<u32>,
+
+    /// Threads battles are simulated on. One per core if 0. [default: 0]
+    #[clap(long = "battle-workers")]
+    pub battle_workers: Option<usize>,
+
+    /// The maximum request body size in bytes. [default: 65536]
+    #[clap(long = "max-body-size")]
+    pub max_body_size: Option<usize>,
+
+    /// The request timeout in seconds. [default: 30]
+    #[clap(short = 't', long = "timeout")]
+    pub timeout: Option<u64>,
+
+    /// Origins allowed to make cross-origin requests. Allows any origin if not given.
+    #[clap(long = "allow-origin")]
+    pub allow_origin: Vec<String>,
+
+    /// Get client addresses from proxy headers. Only use behind a proxy that sets them.
+    #[clap(long = "trust-proxy")]
+    pub trust_proxy: bool,
+
+    /// Seconds in-flight requests are given to finish on shutdown. [default: 30]
+    #[clap(long = "shutdown-timeout")]
+    pub shutdown_timeout: Option<u64>,
+
+    /// PEM certificate chain to serve HTTPS with. Requires `--tls-key`.
+    #[clap(long = "tls-cert", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key to serve HTTPS with. Requires `--tls-cert`.
+    #[clap(long = "tls-key", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+}
 
-    /// The logging level.
-    #[clap(short = 'l', long = "log", default_value = "info")]
-    pub log_level: String,
+/// Team conversions. The converted team is printed as JSON.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Convert a sapai team, `Team.state`, to a team accepted by `/battle`.
+    FromSapai {
+        /// JSON file with the team. Read from stdin if not given.
+        input: Option<PathBuf>,
+        /// Name of the converted team.
+        #[clap(long = "name", default_value = DEFAULT_TEAM_NAME)]
+        name: String,
+    },
+    /// Convert a team accepted by `/battle` to a sapai team.
+    ToSapai {
+        /// JSON file with the team. Read from stdin if not given.
+        input: Option<PathBuf>,
+    },
 }
@@ -0,0 +1,227 @@
+use indexmap::IndexMap;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use saptest::{
+    db::record::{FoodRecord, PetRecord},
+    Entity, SAPQuery, SAPDB,
+};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use super::{ASSET_ROUTE, STATIC_ASSETS};
+use crate::{config::config, db::cache::db_version};
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+/// Number of downloads started. Keeps the temporary file of each download unique.
+static NUM_DOWNLOADS: AtomicU64 = AtomicU64::new(0);
+
+/// Source urls of every servable image for a single database version. See [`asset_sources`].
+static ASSET_SOURCES: Lazy<Mutex<VersionedSources>> = Lazy::new(Mutex::default);
+
+#[derive(Default)]
+struct VersionedSources {
+    version: Option<u64>,
+    sources: Arc<IndexMap<String, String>>,
+}
+
+/// Source urls of every servable image keyed by their local file name.
+/// * Rebuilt when the database version changes so new pets and foods are served.
+fn asset_sources() -> Arc<IndexMap<String, String>> {
+    let version = db_version().hash;
+    let mut cached = ASSET_SOURCES.lock().unwrap();
+    if cached.version != Some(version) {
+        let static_assets = STATIC_ASSETS
+            .into_iter()
+            .map(|(file_name, url)| (file_name.to_owned(), url.to_owned()));
+        let record_assets = record_img_urls()
+            .into_iter()
+            .map(|url| (asset_file_name(&url), url));
+        *cached = VersionedSources {
+            version: Some(version),
+            sources: Arc::new(static_assets.chain(record_assets).collect()),
+        };
+    }
+    Arc::clone(&cached.sources)
+}
+
+fn asset_dir() -> &'static Path {
+    &config().assets.dir
+}
+
+fn record_img_urls() -> Vec<String> {
+    let query_all = |entity: Entity| {
+        let mut query = SAPQuery::from_iter(std::iter::empty::<(String, Vec<String>)>());
+        query.set_table(entity);
+        SAPDB.execute_query(query).unwrap_or_default()
+    };
+    let pet_urls = query_all(Entity::Pet)
+        .into_iter()
+        .filter_map(|rec| PetRecord::try_from(rec).ok())
+        .map(|pet| pet.img_url);
+    let food_urls = query_all(Entity::Food)
+        .into_iter()
+        .filter_map(|rec| FoodRecord::try_from(rec).ok())
+        .map(|food| food.img_url);
+
+    pet_urls.chain(food_urls).unique().collect_vec()
+}
+
+/// Image extension of a url. Wiki urls can have path segments after the file name.
+/// * ex. `.../Ant.png/revision/latest?cb=1` is a `png`.
+fn url_extension(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/')
+        .filter_map(|segment| segment.rsplit_once('.'))
+        .map(|(_, ext)| ext)
+        .find(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or("png")
+}
+
+/// Stable 64-bit FNV-1a hash so file names stay the same across builds.
+fn url_hash(url: &str) -> u64 {
+    url.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Local file name of an image url.
+pub fn asset_file_name(url: &str) -> String {
+    format!(
+        "{:016x}.{}",
+        url_hash(url),
+        url_extension(url).to_lowercase()
+    )
+}
+
+/// Path a local copy of an image url is served from.
+pub fn asset_path(url: &str) -> String {
+    format!("{ASSET_ROUTE}/{}", asset_file_name(url))
+}
+
+/// Mime type of a cached image from its file name.
+pub fn content_type(file_name: &str) -> &'static str {
+    match file_name.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("wasm") => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Temporary file a download is written to before being moved to `path`.
+/// * Unique per download so concurrent downloads of the same image don't share a file.
+fn download_tmp_path(path: &Path) -> PathBuf {
+    let download_id = NUM_DOWNLOADS.fetch_add(1, Ordering::Relaxed);
+    path.with_extension(format!("{}.{download_id}.part", std::process::id()))
+}
+
+async fn download_asset(url: &str, path: &Path) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let bytes = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?
+        .to_vec();
+
+    tokio::fs::create_dir_all(asset_dir()).await?;
+    // Write to a temporary file first so partial downloads are never served.
+    // Renaming is atomic so concurrent downloads of the same image each replace the file whole.
+    let tmp_path = download_tmp_path(path);
+    if let Err(err) = tokio::fs::write(&tmp_path, &bytes).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(err.into());
+    }
+    tokio::fs::rename(&tmp_path, path).await?;
+
+    Ok(bytes)
+}
+
+/// Load a cached image, downloading it from its source if it isn't cached yet.
+/// * Returns `None` if the file name isn't a known image.
+pub async fn load_asset(file_name: &str) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+    let Some(url) = asset_sources().get(file_name).cloned() else {
+        return Ok(None);
+    };
+    let path = asset_dir().join(file_name);
+
+    if let Ok(bytes) = tokio::fs::read(&path).await {
+        return Ok(Some(bytes));
+    }
+    log::info!("Caching {url} as {file_name}.");
+    download_asset(&url, &path).await.map(Some)
+}
+
+/// Download every image that isn't cached yet. Failures are logged and retried on request.
+pub async fn cache_all_assets() {
+    let sources = asset_sources();
+    for (file_name, url) in sources.iter() {
+        let path = asset_dir().join(file_name);
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            continue;
+        }
+        if let Err(err) = download_asset(url, &path).await {
+            log::warn!("Unable to cache {url}: {err}")
+        }
+    }
+    log::info!("Cached {} assets.", sources.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_file_name() {
+        let wiki_url =
+            "https://static.wikia.nocookie.net/superautopets/images/a/aa/Ant.png/revision/latest?cb=20211231";
+        let file_name = asset_file_name(wiki_url);
+
+        assert!(file_name.ends_with(".png"));
+        // Same url always gives the same name.
+        assert_eq!(file_name, asset_file_name(wiki_url));
+        assert_ne!(file_name, asset_file_name("https://example.com/Ant.png"));
+        assert_eq!(asset_path(wiki_url), format!("{ASSET_ROUTE}/{file_name}"));
+    }
+
+    #[test]
+    fn test_download_tmp_path() {
+        let path = Path::new("assets/0123456789abcdef.png");
+        let (first, second) = (download_tmp_path(path), download_tmp_path(path));
+
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), path.parent());
+        assert!(first.to_string_lossy().ends_with(".part"));
+    }
+
+    #[test]
+    fn test_url_extension() {
+        assert_eq!(url_extension("https://example.com/icon.svg.png"), "png");
+        assert_eq!(url_extension("https://example.com/header.jpg?cb=1"), "jpg");
+        assert_eq!(url_extension("https://example.com/no_ext"), "png");
+    }
+
+    #[test]
+    fn test_record_assets_known() {
+        let pet_url = record_img_urls().into_iter().next().unwrap();
+        let sources = asset_sources();
+        assert!(sources.contains_key(&asset_file_name(&pet_url)));
+        assert!(sources.contains_key("attack_icon.png"));
+        // Same sources until the database changes.
+        assert!(Arc::ptr_eq(&sources, &asset_sources()));
+    }
+}
@@ -0,0 +1,27 @@
+pub mod cache;
+pub mod response;
+
+/// Route local copies of images are served from.
+const ASSET_ROUTE: &str = "/assets/img";
+/// Cached images never change as their file names are derived from their source url.
+const ASSET_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Images used by the frontend that aren't part of `SAPDB`. Stored under a fixed file name.
+const STATIC_ASSETS: [(&str, &str); 4] = [
+    (
+        "attack_icon.png",
+        "https://static.wikia.nocookie.net/superautopets/images/a/aa/Attack_Icon.png",
+    ),
+    (
+        "health_icon.png",
+        "https://static.wikia.nocookie.net/superautopets/images/4/44/Health_Icon.png",
+    ),
+    (
+        "empty_slot_icon.png",
+        "https://upload.wikimedia.org/wikipedia/commons/thumb/c/c1/Empty_set_symbol.svg/200px-Empty_set_symbol.svg.png",
+    ),
+    (
+        "sap_header.jpg",
+        "https://static.wikia.nocookie.net/superautopets/images/5/5d/Super_Auto_Pets_Header.jpg",
+    ),
+];
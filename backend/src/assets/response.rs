@@ -0,0 +1,56 @@
+use axum::{
+    extract::Path,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        StatusCode,
+    },
+    response::IntoResponse,
+};
+
+use super::{
+    cache::{content_type, load_asset},
+    ASSET_CACHE_CONTROL,
+};
+
+pub async fn get_asset(Path(file_name): Path<String>) -> impl IntoResponse {
+    match load_asset(&file_name).await {
+        Ok(Some(bytes)) => Ok((
+            [
+                (CONTENT_TYPE, content_type(&file_name)),
+                (CACHE_CONTROL, ASSET_CACHE_CONTROL),
+            ],
+            bytes,
+        )),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(err) => {
+            log::error!("Unable to load asset {file_name}: {err}");
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_get_unknown_asset() {
+        let app = app();
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/assets/img/not_an_asset.png")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,483 @@
+//! Outcome probabilities estimated by probing the branches of a battle.
+//!
+//! `saptest` draws random targets and chance-based foods from each team's seed but can't list the choices in a turn.
+//! Instead, each turn is fought from the same state with [`BRANCH_PROBES`] seeds. Probe seeds are drawn from the
+//! state and turn so every branch is probed with different seeds. Probes that end in the same state are one branch,
+//! weighted by the share of probes that reached it. Branches from different paths that reach the same state are
+//! merged. Probabilities are only estimates: a choice no probe made is missed and shares are rounded to multiples of
+//! `1 / BRANCH_PROBES`. They're exact when a turn has no random choices.
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use saptest::{teams::team::TeamFightOutcome, Team, TeamCombat};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use super::{
+    batch::{run_batch, BatchTally},
+    pool::run_simulation,
+    response::{build_teams, random_seed},
+    snapshot::TurnSnapshot,
+    team::Teams,
+};
+use crate::config::config;
+
+/// Seeds each turn is fought with to find its branches.
+pub const BRANCH_PROBES: u64 = 16;
+/// Most likely paths returned.
+pub const MAX_PATHS: usize = 5;
+/// Turns fought per explored branch before falling back to sampling.
+const FOUGHT_TURNS_PER_BRANCH: usize = 2000;
+
+#[derive(Deserialize)]
+pub struct AnalysisRequest {
+    #[serde(flatten)]
+    pub teams: Teams,
+    /// Branches probed before falling back to sampling. At most `max_analysis_branches`.
+    pub max_branches: Option<usize>,
+    /// Battles fought when sampling. At most `max_batch_runs`.
+    pub runs: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisMode {
+    /// Every branch found by probing was explored. See the [module docs](self).
+    #[default]
+    Probed,
+    /// Too many branches. Outcomes were sampled with a batch of seeds.
+    MonteCarlo,
+}
+
+/// Chance of each outcome from the friend team's side.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeProbabilities {
+    pub win: f64,
+    pub loss: f64,
+    pub draw: f64,
+    /// Stopped at the turn limit.
+    pub unfinished: f64,
+}
+
+impl OutcomeProbabilities {
+    fn add(&mut self, outcome: &TeamFightOutcome, probability: f64) {
+        match outcome {
+            TeamFightOutcome::Win => self.win += probability,
+            TeamFightOutcome::Loss => self.loss += probability,
+            TeamFightOutcome::Draw => self.draw += probability,
+            TeamFightOutcome::None => self.unfinished += probability,
+        }
+    }
+}
+
+impl From<&BatchTally> for OutcomeProbabilities {
+    fn from(tally: &BatchTally) -> Self {
+        let share = |count: u64| match tally.runs() {
+            0 => 0.0,
+            runs => count as f64 / runs as f64,
+        };
+        OutcomeProbabilities {
+            win: share(tally.wins),
+            loss: share(tally.losses),
+            draw: share(tally.draws),
+            unfinished: share(tally.unfinished),
+        }
+    }
+}
+
+/// A single sequence of branches through a battle.
+/// * Branches merged from several paths keep the seeds and turns of the most likely one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomePath {
+    pub probability: f64,
+    pub outcome: TeamFightOutcome,
+    /// Seed each turn was fought with. Replays the path.
+    pub seeds: Vec<u64>,
+    /// Teams before the battle and after every turn.
+    pub turns: Vec<TurnSnapshot>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnalysisResponse {
+    pub status: Option<String>,
+    pub seed: u64,
+    pub mode: AnalysisMode,
+    /// Distinct paths found. 0 when sampling.
+    pub branches: usize,
+    /// Battles fought when sampling. 0 if every branch was explored.
+    pub runs: u64,
+    pub probabilities: OutcomeProbabilities,
+    /// The most likely paths. Empty when sampling.
+    pub paths: Vec<OutcomePath>,
+}
+
+/// A battle state being explored.
+/// * Teams are kept serialized as `saptest` pets are shared between clones of a team.
+struct Branch {
+    path: OutcomePath,
+    friend_team: String,
+    enemy_team: String,
+    ended: bool,
+    /// Hash of the teams and if the battle ended. Branches in the same state have the same hash.
+    state_hash: u64,
+}
+
+impl Branch {
+    fn new(path: OutcomePath, friend_team: String, enemy_team: String, ended: bool) -> Self {
+        let mut hasher = DefaultHasher::new();
+        (&friend_team, &enemy_team, ended).hash(&mut hasher);
+        Branch {
+            path,
+            friend_team,
+            enemy_team,
+            ended,
+            state_hash: hasher.finish(),
+        }
+    }
+
+    fn teams(&self) -> Result<(Team, Team), String> {
+        let parse = |team: &str| serde_json::from_str::<Team>(team).map_err(|err| err.to_string());
+        Ok((parse(&self.friend_team)?, parse(&self.enemy_team)?))
+    }
+
+    fn is_same_state(&self, other: &Branch) -> bool {
+        self.state_hash == other.state_hash
+            && self.ended == other.ended
+            && self.friend_team == other.friend_team
+            && self.enemy_team == other.enemy_team
+    }
+
+    /// Seed of a probe of the next turn. Differs for each state, turn, and probe.
+    fn probe_seed(&self, seed: u64, turn: usize, probe: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (seed, self.state_hash, turn, probe).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Branches indexed by their state.
+#[derive(Default)]
+struct Branches {
+    branches: Vec<Branch>,
+    by_state: HashMap<u64, Vec<usize>>,
+}
+
+impl Branches {
+    fn len(&self) -> usize {
+        self.branches.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.branches.is_empty()
+    }
+
+    /// Branch in the same state as `branch`.
+    fn find_mut(&mut self, branch: &Branch) -> Option<&mut Branch> {
+        let idx = self
+            .by_state
+            .get(&branch.state_hash)?
+            .iter()
+            .copied()
+            .find(|idx| self.branches[*idx].is_same_state(branch))?;
+        Some(&mut self.branches[idx])
+    }
+
+    fn push(&mut self, branch: Branch) {
+        self.by_state
+            .entry(branch.state_hash)
+            .or_default()
+            .push(self.branches.len());
+        self.branches.push(branch);
+    }
+
+    /// Add `branch`, merging it with a branch in the same state.
+    /// * Merged probabilities are summed. The path of the more likely branch is kept.
+    fn merge(&mut self, mut branch: Branch) {
+        let Some(existing) = self.find_mut(&branch) else {
+            self.push(branch);
+            return;
+        };
+        let probability = existing.path.probability + branch.path.probability;
+        if branch.path.probability > existing.path.probability {
+            std::mem::swap(&mut existing.path, &mut branch.path);
+        }
+        existing.path.probability = probability;
+    }
+}
+
+/// Serialize a team after a turn. Seeds are reset so the same state reached with different seeds compares equal.
+fn team_state(team: &mut Team, seed: u64) -> String {
+    team.set_seed(Some(seed));
+    serde_json::to_string(&*team).unwrap_or_default()
+}
+
+/// Results of fighting the next turn of `branch` with each probe seed.
+/// * A result's probability is the branch's probability times the share of probes that reached it.
+/// * Returns an error message if a turn can't be fought.
+fn next_turns(seed: u64, branch: &Branch) -> Result<Branches, String> {
+    let turn = branch.path.seeds.len() + 1;
+    let probe_probability = branch.path.probability / BRANCH_PROBES as f64;
+    let mut children = Branches::default();
+    for probe in 0..BRANCH_PROBES {
+        let turn_seed = branch.probe_seed(seed, turn, probe);
+        let (mut team, mut enemy_team) = branch.teams()?;
+        team.set_seed(Some(turn_seed));
+        enemy_team.set_seed(Some(turn_seed));
+        let outcome = team
+            .fight(&mut enemy_team)
+            .map_err(|err| format!("Turn {turn} failed: {err}"))?;
+        let ended = !matches!(outcome, TeamFightOutcome::None) || turn >= config().battle.max_turns;
+        let snapshot = TurnSnapshot::new(turn, &team, &enemy_team);
+        let path = OutcomePath {
+            probability: probe_probability,
+            outcome,
+            seeds: vec![],
+            turns: vec![],
+        };
+        let friend_team = team_state(&mut team, seed);
+        let enemy_team = team_state(&mut enemy_team, seed);
+        let mut child = Branch::new(path, friend_team, enemy_team, ended);
+        // Only copy the path for new results.
+        if let Some(existing) = children.find_mut(&child) {
+            existing.path.probability += probe_probability;
+            continue;
+        }
+        child.path.seeds = [&branch.path.seeds[..], &[turn_seed]].concat();
+        child.path.turns = branch.path.turns.clone();
+        child.path.turns.push(snapshot);
+        children.push(child);
+    }
+    Ok(children)
+}
+
+/// Explore the branches of a battle turn by turn. Branches that reach the same state are merged.
+/// * Returns [`None`] if there are more than `max_branches` paths or the battle is too long to probe.
+/// * Returns an error message if either team is invalid.
+pub fn explore(
+    teams: &Teams,
+    seed: u64,
+    max_branches: usize,
+) -> Result<Option<Vec<OutcomePath>>, String> {
+    let (mut team, mut enemy_team) = build_teams(teams.clone(), seed)?;
+    let path = OutcomePath {
+        probability: 1.0,
+        outcome: TeamFightOutcome::None,
+        seeds: vec![],
+        turns: vec![TurnSnapshot::new(0, &team, &enemy_team)],
+    };
+    let friend_team = team_state(&mut team, seed);
+    let enemy_team = team_state(&mut enemy_team, seed);
+    let mut open = Branches::default();
+    open.push(Branch::new(path, friend_team, enemy_team, false));
+    let mut finished = Branches::default();
+    let mut fought_turns = 0;
+
+    while !open.is_empty() {
+        fought_turns += open.len() * BRANCH_PROBES as usize;
+        if fought_turns > max_branches.max(1) * FOUGHT_TURNS_PER_BRANCH {
+            return Ok(None);
+        }
+        let mut next = Branches::default();
+        for branch in open.branches.iter() {
+            for child in next_turns(seed, branch)?.branches {
+                if child.ended {
+                    finished.merge(child)
+                } else {
+                    next.merge(child)
+                }
+            }
+        }
+        if next.len() + finished.len() > max_branches {
+            return Ok(None);
+        }
+        open = next;
+    }
+    Ok(Some(
+        finished
+            .branches
+            .into_iter()
+            .map(|branch| branch.path)
+            .collect(),
+    ))
+}
+
+pub async fn post_analyze(Json(request): Json<AnalysisRequest>) -> impl IntoResponse {
+    let battle_config = &config().battle;
+    let max_runs = battle_config.max_batch_runs as u64;
+    let mut resp = AnalysisResponse {
+        seed: request.teams.seed.unwrap_or_else(random_seed),
+        ..Default::default()
+    };
+    let runs = request.runs.unwrap_or(max_runs);
+    if !(1..=max_runs).contains(&runs) {
+        resp.status = Some(format!("Runs must be between 1 and {max_runs}."));
+        return (StatusCode::BAD_REQUEST, Json(resp));
+    }
+    let max_branches = request
+        .max_branches
+        .unwrap_or(battle_config.max_analysis_branches)
+        .min(battle_config.max_analysis_branches);
+
+    let (teams, seed) = (request.teams.clone(), resp.seed);
+    let explored = run_simulation(move || explore(&teams, seed, max_branches)).await;
+
+    match explored {
+        Ok(Some(mut paths)) => {
+            for path in paths.iter() {
+                resp.probabilities.add(&path.outcome, path.probability);
+            }
+            paths.sort_by(|path, other| other.probability.total_cmp(&path.probability));
+            resp.mode = AnalysisMode::Probed;
+            resp.branches = paths.len();
+            paths.truncate(MAX_PATHS);
+            resp.paths = paths;
+        }
+        Ok(None) => match run_batch(request.teams, resp.seed, runs).await {
+            Ok(tally) => {
+                resp.mode = AnalysisMode::MonteCarlo;
+                resp.runs = tally.runs();
+                resp.probabilities = OutcomeProbabilities::from(&tally);
+            }
+            Err(err_msg) => {
+                resp.status = Some(err_msg);
+                return (StatusCode::BAD_REQUEST, Json(resp));
+            }
+        },
+        Err(err_msg) => {
+            resp.status = Some(err_msg);
+            return (StatusCode::BAD_REQUEST, Json(resp));
+        }
+    }
+    resp.status = Some(StatusCode::ACCEPTED.to_string());
+    (StatusCode::ACCEPTED, Json(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::post_json;
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_probabilities_from_tally() {
+        let tally = BatchTally {
+            wins: 3,
+            losses: 1,
+            draws: 0,
+            unfinished: 0,
+            total_turns: 12,
+            ..Default::default()
+        };
+        let probabilities = OutcomeProbabilities::from(&tally);
+
+        assert_eq!(probabilities.win, 0.75);
+        assert_eq!(probabilities.loss, 0.25);
+        assert_eq!(
+            OutcomeProbabilities::from(&BatchTally::default()),
+            OutcomeProbabilities::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_analyze() {
+        let mut request: Value =
+            serde_json::from_str(include_str!("../../tests/test_teams.json")).unwrap();
+        request["seed"] = json!(42);
+
+        let (status, resp) = post_json("/battle/analyze", request.clone()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let resp: AnalysisResponse = serde_json::from_value(resp).unwrap();
+        let probabilities = resp.probabilities;
+        let total =
+            probabilities.win + probabilities.loss + probabilities.draw + probabilities.unfinished;
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(resp.paths.len() <= MAX_PATHS);
+        assert!(resp.branches >= resp.paths.len());
+
+        // No branches can be explored so outcomes are sampled.
+        request["max_branches"] = json!(0);
+        request["runs"] = json!(10);
+        let (status, resp) = post_json("/battle/analyze", request).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let resp: AnalysisResponse = serde_json::from_value(resp).unwrap();
+        assert_eq!(resp.mode, AnalysisMode::MonteCarlo);
+        assert_eq!(resp.runs, 10);
+        assert!(resp.paths.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_post_analyze_deterministic() {
+        let team = |name: &str, attack: usize, health: usize| {
+            json!({
+                "name": name,
+                "pets": [{"name": "Dog", "attack": attack, "health": health, "level": 1}]
+            })
+        };
+        // Teams without random effects have a single branch.
+        for (enemy_attack, enemy_health, outcome) in
+            [(1, 1, "Win"), (50, 50, "Loss"), (10, 10, "Draw")]
+        {
+            let request = json!({
+                "friend_team": team("Friend", 10, 10),
+                "enemy_team": team("Enemy", enemy_attack, enemy_health),
+                "seed": 42,
+            });
+            let (status, resp) = post_json("/battle/analyze", request).await;
+            assert_eq!(status, StatusCode::ACCEPTED);
+            let resp: AnalysisResponse = serde_json::from_value(resp).unwrap();
+
+            assert_eq!(resp.mode, AnalysisMode::Probed);
+            assert_eq!(resp.branches, 1);
+            assert_eq!(resp.paths[0].probability, 1.0);
+            assert_eq!(json!(resp.paths[0].outcome), json!(outcome));
+            let probabilities = resp.probabilities;
+            let expected = match outcome {
+                "Win" => probabilities.win,
+                "Loss" => probabilities.loss,
+                _ => probabilities.draw,
+            };
+            assert_eq!(expected, 1.0);
+        }
+    }
+
+    fn branch(probability: f64, friend_team: &str, seed: u64) -> Branch {
+        let path = OutcomePath {
+            probability,
+            outcome: TeamFightOutcome::None,
+            seeds: vec![seed],
+            turns: vec![],
+        };
+        Branch::new(path, friend_team.to_owned(), String::new(), false)
+    }
+
+    #[test]
+    fn test_merge_branch() {
+        let mut branches = Branches::default();
+        branches.merge(branch(0.25, "1", 1));
+        branches.merge(branch(0.5, "1", 2));
+        branches.merge(branch(0.25, "2", 3));
+
+        // Same states from different paths are merged and keep the more likely path.
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches.branches[0].path.probability, 0.75);
+        assert_eq!(branches.branches[0].path.seeds, vec![2]);
+    }
+
+    #[test]
+    fn test_probe_seeds() {
+        let (first, second) = (branch(1.0, "1", 0), branch(1.0, "2", 0));
+        let seeds = |branch: &Branch, turn: usize| {
+            (0..BRANCH_PROBES)
+                .map(|probe| branch.probe_seed(42, turn, probe))
+                .collect::<Vec<_>>()
+        };
+
+        // Each branch and turn is probed with its own seeds.
+        assert_ne!(seeds(&first, 1), seeds(&second, 1));
+        assert_ne!(seeds(&first, 1), seeds(&first, 2));
+        assert_eq!(seeds(&first, 1), seeds(&first, 1));
+    }
+}
@@ -0,0 +1,619 @@
+//! Per-pet performance derived from the effect graph `saptest` records for each team.
+//! * Every attack and effect is an edge from the pet that caused it to the pet it affected.
+//! * A kill is credited to the last enemy that damaged a pet before it fainted.
+use saptest::{
+    effects::{
+        actions::{Action, StatChangeType},
+        state::Status,
+    },
+    Pet, Team, TeamViewer,
+};
+use serde::{Deserialize, Serialize};
+
+/// Performance of a pet in a single battle.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetStats {
+    pub id: Option<String>,
+    pub name: String,
+    pub damage_dealt: usize,
+    pub damage_taken: usize,
+    pub kills: usize,
+    /// Times the pet's effects triggered. Attacks aren't counted.
+    pub effect_triggers: usize,
+    /// Attack and health given to other pets on the team.
+    pub stats_granted: usize,
+    pub summons: usize,
+    pub fainted_turn: Option<usize>,
+}
+
+/// Performance of a pet summed over a batch of battles.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetTotals {
+    pub id: Option<String>,
+    pub name: String,
+    /// Battles the pet was in.
+    pub battles: u64,
+    pub damage_dealt: u64,
+    pub damage_taken: u64,
+    pub kills: u64,
+    pub effect_triggers: u64,
+    pub stats_granted: u64,
+    pub summons: u64,
+    pub faints: u64,
+    /// Sum of the turns the pet fainted on.
+    pub fainted_turns: u64,
+}
+
+/// Mean performance of a pet over the battles it was in.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PetAggregate {
+    pub id: Option<String>,
+    pub name: String,
+    pub battles: u64,
+    pub damage_dealt: f64,
+    pub damage_taken: f64,
+    pub kills: f64,
+    pub effect_triggers: f64,
+    pub stats_granted: f64,
+    pub summons: f64,
+    /// Fraction of battles the pet fainted in.
+    pub faint_rate: f64,
+    /// Mean turn the pet fainted on. [`None`] if it never fainted.
+    pub mean_fainted_turn: Option<f64>,
+}
+
+/// Pet statistics of both teams. Pets on a team before the battle come first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BattleStats<T = PetStats> {
+    pub friend_team: Vec<T>,
+    pub enemy_team: Vec<T>,
+}
+
+impl PetStats {
+    fn new(pet: &Pet) -> Self {
+        PetStats {
+            id: pet.id.clone(),
+            name: pet.name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Pet known only by its id in an effect graph. ex. `Zombie Cricket_0`
+    fn from_key(key: &str) -> Self {
+        let name = key.rsplit_once('_').map_or(key, |(name, _)| name);
+        PetStats {
+            id: Some(key.to_owned()),
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl PetTotals {
+    fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+
+    fn add(&mut self, stats: &PetStats) {
+        self.battles += 1;
+        self.damage_dealt += stats.damage_dealt as u64;
+        self.damage_taken += stats.damage_taken as u64;
+        self.kills += stats.kills as u64;
+        self.effect_triggers += stats.effect_triggers as u64;
+        self.stats_granted += stats.stats_granted as u64;
+        self.summons += stats.summons as u64;
+        if let Some(turn) = stats.fainted_turn {
+            self.faints += 1;
+            self.fainted_turns += turn as u64;
+        }
+    }
+
+    fn merge(&mut self, other: &PetTotals) {
+        self.battles += other.battles;
+        self.damage_dealt += other.damage_dealt;
+        self.damage_taken += other.damage_taken;
+        self.kills += other.kills;
+        self.effect_triggers += other.effect_triggers;
+        self.stats_granted += other.stats_granted;
+        self.summons += other.summons;
+        self.faints += other.faints;
+        self.fainted_turns += other.fainted_turns;
+    }
+}
+
+impl From<&PetTotals> for PetAggregate {
+    fn from(totals: &PetTotals) -> Self {
+        let mean = |total: u64| match totals.battles {
+            0 => 0.0,
+            battles => total as f64 / battles as f64,
+        };
+        PetAggregate {
+            id: totals.id.clone(),
+            name: totals.name.clone(),
+            battles: totals.battles,
+            damage_dealt: mean(totals.damage_dealt),
+            damage_taken: mean(totals.damage_taken),
+            kills: mean(totals.kills),
+            effect_triggers: mean(totals.effect_triggers),
+            stats_granted: mean(totals.stats_granted),
+            summons: mean(totals.summons),
+            faint_rate: mean(totals.faints),
+            mean_fainted_turn: (totals.faints > 0)
+                .then(|| totals.fainted_turns as f64 / totals.faints as f64),
+        }
+    }
+}
+
+fn add_totals(totals: &mut Vec<PetTotals>, pet: &PetTotals) {
+    match totals.iter_mut().find(|total| total.key() == pet.key()) {
+        Some(total) => total.merge(pet),
+        None => totals.push(pet.clone()),
+    }
+}
+
+impl BattleStats<PetTotals> {
+    /// Add the stats of a single battle.
+    pub fn add(&mut self, stats: &BattleStats) {
+        let to_totals = |pet: &PetStats| {
+            let mut totals = PetTotals {
+                id: pet.id.clone(),
+                name: pet.name.clone(),
+                ..Default::default()
+            };
+            totals.add(pet);
+            totals
+        };
+        for pet in stats.friend_team.iter().map(to_totals) {
+            add_totals(&mut self.friend_team, &pet);
+        }
+        for pet in stats.enemy_team.iter().map(to_totals) {
+            add_totals(&mut self.enemy_team, &pet);
+        }
+    }
+
+    pub fn merge(&mut self, other: BattleStats<PetTotals>) {
+        for pet in other.friend_team.iter() {
+            add_totals(&mut self.friend_team, pet);
+        }
+        for pet in other.enemy_team.iter() {
+            add_totals(&mut self.enemy_team, pet);
+        }
+    }
+
+    pub fn aggregates(&self) -> BattleStats<PetAggregate> {
+        BattleStats {
+            friend_team: self.friend_team.iter().map(PetAggregate::from).collect(),
+            enemy_team: self.enemy_team.iter().map(PetAggregate::from).collect(),
+        }
+    }
+}
+
+fn stats_entry<'a>(
+    stats: &'a mut Vec<PetStats>,
+    key: &str,
+    new: impl FnOnce() -> PetStats,
+) -> &'a mut PetStats {
+    match stats.iter().position(|stats| stats.key() == key) {
+        Some(idx) => &mut stats[idx],
+        None => {
+            stats.push(new());
+            stats.last_mut().expect("Pet stats just added.")
+        }
+    }
+}
+
+fn pet_entry<'a>(stats: &'a mut Vec<PetStats>, pet: &Pet) -> &'a mut PetStats {
+    let key = pet.id.clone().unwrap_or_else(|| pet.name.to_string());
+    stats_entry(stats, &key, || PetStats::new(pet))
+}
+
+fn key_entry<'a>(stats: &'a mut Vec<PetStats>, key: &str) -> &'a mut PetStats {
+    stats_entry(stats, key, || PetStats::from_key(key))
+}
+
+/// Pet in the effect graph of a team. Labeled `<pet id> - <team name>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EffectNode<'a> {
+    key: &'a str,
+    team: &'a str,
+}
+
+impl<'a> EffectNode<'a> {
+    /// Pet ids never contain ` - ` so the team name is everything after the first one.
+    fn parse(label: &'a str) -> Option<Self> {
+        let (key, team) = label.split_once(" - ")?;
+        Some(EffectNode { key, team })
+    }
+}
+
+/// What an attack or effect did to its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EffectKind {
+    Damage(usize),
+    /// Attack and health given.
+    Stats(usize),
+    Summon,
+    Other,
+}
+
+impl From<&Action> for EffectKind {
+    fn from(action: &Action) -> Self {
+        match action {
+            Action::Remove(StatChangeType::StaticValue(stats)) => {
+                EffectKind::Damage(stats.health.unsigned_abs())
+            }
+            Action::Add(StatChangeType::StaticValue(stats)) => {
+                EffectKind::Stats(stats.attack.unsigned_abs() + stats.health.unsigned_abs())
+            }
+            Action::Summon(..) => EffectKind::Summon,
+            _ => EffectKind::Other,
+        }
+    }
+}
+
+/// Attack or effect in the effect graph of a team.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EffectEdge<'a> {
+    source: EffectNode<'a>,
+    target: EffectNode<'a>,
+    /// What triggered the effect. ex. [`Status::Attack`]
+    status: &'a Status,
+    kind: EffectKind,
+}
+
+/// Attacks and effects of a team in the order they happened.
+fn effect_edges(team: &Team) -> impl Iterator<Item = EffectEdge<'_>> {
+    let graph = &team.history.effect_graph;
+    graph.raw_edges().iter().filter_map(move |edge| {
+        let (status, action, ..) = &edge.weight;
+        Some(EffectEdge {
+            source: EffectNode::parse(&graph[edge.source()])?,
+            target: EffectNode::parse(&graph[edge.target()])?,
+            status,
+            kind: EffectKind::from(action),
+        })
+    })
+}
+
+/// Credit the attacks and effects of the pets on team `team_name` from the edges of its effect graph.
+/// * Only edges caused by the team's pets are read. The other team's pets are credited from their own graph.
+fn credit_effects<'a>(
+    team_name: &str,
+    edges: impl IntoIterator<Item = EffectEdge<'a>>,
+    stats: &mut Vec<PetStats>,
+    enemy_stats: &mut Vec<PetStats>,
+) {
+    // Last pet of the team to damage each enemy.
+    let mut last_hits: Vec<(&str, &str)> = vec![];
+    let mut prev_trigger = None;
+    for edge in edges
+        .into_iter()
+        .filter(|edge| edge.source.team == team_name)
+    {
+        let source = edge.source.key;
+        let trigger = (source, edge.status);
+        if *edge.status != Status::Attack && prev_trigger != Some(trigger) {
+            key_entry(stats, source).effect_triggers += 1;
+        }
+        prev_trigger = Some(trigger);
+
+        let is_enemy_target = edge.target.team != team_name;
+        let target_stats = |stats, enemy_stats| if is_enemy_target { enemy_stats } else { stats };
+        match edge.kind {
+            EffectKind::Damage(damage) => {
+                key_entry(stats, source).damage_dealt += damage;
+                key_entry(
+                    target_stats(&mut *stats, &mut *enemy_stats),
+                    edge.target.key,
+                )
+                .damage_taken += damage;
+                if is_enemy_target && damage > 0 {
+                    last_hits.retain(|(target, _)| *target != edge.target.key);
+                    last_hits.push((edge.target.key, source));
+                }
+            }
+            EffectKind::Stats(granted) if edge.target.key != source => {
+                key_entry(stats, source).stats_granted += granted;
+            }
+            EffectKind::Summon => {
+                key_entry(stats, source).summons += 1;
+                // Summoned pets are listed even if they never act.
+                key_entry(
+                    target_stats(&mut *stats, &mut *enemy_stats),
+                    edge.target.key,
+                );
+            }
+            EffectKind::Stats(_) | EffectKind::Other => {}
+        }
+    }
+
+    for (target, source) in last_hits {
+        let is_fainted = enemy_stats
+            .iter()
+            .any(|pet| pet.key() == target && pet.fainted_turn.is_some());
+        if is_fainted {
+            key_entry(stats, source).kills += 1;
+        }
+    }
+}
+
+/// Added to the name of an enemy team with the same name as its opponent while they fight.
+const SAME_NAME_SUFFIX: &str = " (Enemy)";
+
+/// Pets of both teams and when they fainted. Recorded while a battle is fought.
+/// * Cheap enough to record every turn of a batch. Attacks and effects are only read once the battle ends.
+#[derive(Debug, Default)]
+pub struct BattleLog {
+    stats: BattleStats,
+    /// Fainted pets already recorded on each team.
+    friends_fainted: usize,
+    enemies_fainted: usize,
+    /// Name of the enemy team before it was renamed. See [`BattleLog::new`].
+    enemy_name: Option<String>,
+}
+
+fn record_faints(stats: &mut Vec<PetStats>, recorded: &mut usize, turn: usize, team: &Team) {
+    for pet in team.fainted.iter().flatten().skip(*recorded) {
+        pet_entry(stats, &pet.borrow())
+            .fainted_turn
+            .get_or_insert(turn);
+        *recorded += 1;
+    }
+}
+
+impl BattleLog {
+    /// Start a log with the pets on both teams before the battle.
+    /// * Pets that already fainted, like in a resumed battle, aren't listed.
+    /// * Pets in effect graphs are labeled with their team's name. If both teams have the same name, the enemy team
+    ///   is renamed until [`BattleLog::finish`] so their pets can be told apart.
+    pub fn new(team: &Team, enemy_team: &mut Team) -> Self {
+        let enemy_name = (team.get_name() == enemy_team.get_name()).then(|| {
+            let name = enemy_team.get_name().to_owned();
+            let _ = enemy_team.set_name(&format!("{name}{SAME_NAME_SUFFIX}"));
+            name
+        });
+        let mut log = BattleLog {
+            enemy_name,
+            friends_fainted: team.fainted.iter().flatten().count(),
+            enemies_fainted: enemy_team.fainted.iter().flatten().count(),
+            ..Default::default()
+        };
+        for pet in team.all() {
+            pet_entry(&mut log.stats.friend_team, &pet.borrow());
+        }
+        for pet in enemy_team.all() {
+            pet_entry(&mut log.stats.enemy_team, &pet.borrow());
+        }
+        log
+    }
+
+    /// Record pets that fainted during `turn`.
+    pub fn record_turn(&mut self, turn: usize, team: &Team, enemy_team: &Team) {
+        record_faints(
+            &mut self.stats.friend_team,
+            &mut self.friends_fainted,
+            turn,
+            team,
+        );
+        record_faints(
+            &mut self.stats.enemy_team,
+            &mut self.enemies_fainted,
+            turn,
+            enemy_team,
+        );
+    }
+
+    /// Per-pet statistics of the finished battle. Restores the enemy team's name.
+    pub fn finish(mut self, team: &Team, enemy_team: &mut Team) -> BattleStats {
+        for pet in team.all() {
+            pet_entry(&mut self.stats.friend_team, &pet.borrow());
+        }
+        for pet in enemy_team.all() {
+            pet_entry(&mut self.stats.enemy_team, &pet.borrow());
+        }
+        let stats = &mut self.stats;
+        credit_effects(
+            team.get_name(),
+            effect_edges(team),
+            &mut stats.friend_team,
+            &mut stats.enemy_team,
+        );
+        credit_effects(
+            enemy_team.get_name(),
+            effect_edges(enemy_team),
+            &mut stats.enemy_team,
+            &mut stats.friend_team,
+        );
+        if let Some(name) = self.enemy_name.take() {
+            let _ = enemy_team.set_name(&name);
+        }
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use saptest::Statistics;
+
+    use super::*;
+
+    type EdgeLabel = (&'static str, &'static str, Status, EffectKind);
+
+    fn friend_edges() -> Vec<EdgeLabel> {
+        vec![
+            (
+                "Mosquito_0 - Friend",
+                "Cricket_0 - Enemy",
+                Status::StartOfBattle,
+                EffectKind::Damage(1),
+            ),
+            (
+                "Mosquito_0 - Friend",
+                "Fish_0 - Enemy",
+                Status::StartOfBattle,
+                EffectKind::Damage(1),
+            ),
+            (
+                "Ant_0 - Friend",
+                "Cricket_0 - Enemy",
+                Status::Attack,
+                EffectKind::Damage(2),
+            ),
+            (
+                "Ant_0 - Friend",
+                "Dog_0 - Friend",
+                Status::Faint,
+                EffectKind::Stats(3),
+            ),
+            (
+                "Dog_0 - Friend",
+                "Dog_0 - Friend",
+                Status::Hurt,
+                EffectKind::Stats(2),
+            ),
+        ]
+    }
+
+    fn enemy_edges() -> Vec<EdgeLabel> {
+        vec![
+            (
+                "Cricket_0 - Enemy",
+                "Ant_0 - Friend",
+                Status::Attack,
+                EffectKind::Damage(2),
+            ),
+            (
+                "Cricket_0 - Enemy",
+                "Zombie Cricket_0 - Enemy",
+                Status::Faint,
+                EffectKind::Summon,
+            ),
+        ]
+    }
+
+    fn edges(labels: &[EdgeLabel]) -> Vec<EffectEdge<'_>> {
+        labels
+            .iter()
+            .map(|(source, target, status, kind)| EffectEdge {
+                source: EffectNode::parse(source).unwrap(),
+                target: EffectNode::parse(target).unwrap(),
+                status,
+                kind: *kind,
+            })
+            .collect()
+    }
+
+    fn pet(key: &str, fainted_turn: Option<usize>) -> PetStats {
+        PetStats {
+            fainted_turn,
+            ..PetStats::from_key(key)
+        }
+    }
+
+    fn battle_stats() -> BattleStats {
+        let mut stats = BattleStats {
+            friend_team: vec![
+                pet("Mosquito_0", None),
+                pet("Ant_0", Some(1)),
+                pet("Dog_0", None),
+            ],
+            enemy_team: vec![pet("Cricket_0", Some(1)), pet("Fish_0", Some(1))],
+        };
+        let (friend_edges, enemy_edges) = (friend_edges(), enemy_edges());
+        credit_effects(
+            "Friend",
+            edges(&friend_edges),
+            &mut stats.friend_team,
+            &mut stats.enemy_team,
+        );
+        credit_effects(
+            "Enemy",
+            edges(&enemy_edges),
+            &mut stats.enemy_team,
+            &mut stats.friend_team,
+        );
+        stats
+    }
+
+    #[test]
+    fn test_effect_edge() {
+        // Team names can contain the separator.
+        assert_eq!(
+            EffectNode::parse("Zombie Cricket_0 - The - Team"),
+            Some(EffectNode {
+                key: "Zombie Cricket_0",
+                team: "The - Team"
+            })
+        );
+        let damage = Action::Remove(StatChangeType::StaticValue(Statistics {
+            attack: 0,
+            health: -2,
+        }));
+        assert_eq!(EffectKind::from(&damage), EffectKind::Damage(2));
+        let buff = Action::Add(StatChangeType::StaticValue(Statistics {
+            attack: 2,
+            health: 1,
+        }));
+        assert_eq!(EffectKind::from(&buff), EffectKind::Stats(3));
+    }
+
+    #[test]
+    fn test_same_team_names() {
+        let mut team = Team::new(&[], 5).unwrap();
+        let _ = team.set_name("Team");
+        let mut enemy_team = team.clone();
+
+        let log = BattleLog::new(&team, &mut enemy_team);
+        // Renamed while fighting so effect graphs can tell the teams apart.
+        assert_ne!(team.get_name(), enemy_team.get_name());
+        log.finish(&team, &mut enemy_team);
+        assert_eq!(enemy_team.get_name(), "Team");
+    }
+
+    #[test]
+    fn test_battle_stats() {
+        let stats = battle_stats();
+
+        // Ability damage and kills count. Each target of an effect isn't another trigger.
+        let mosquito = &stats.friend_team[0];
+        assert_eq!((mosquito.damage_dealt, mosquito.kills), (2, 1));
+        assert_eq!(mosquito.effect_triggers, 1);
+
+        let ant = &stats.friend_team[1];
+        assert_eq!((ant.damage_dealt, ant.damage_taken, ant.kills), (2, 2, 1));
+        assert_eq!((ant.effect_triggers, ant.stats_granted), (1, 3));
+
+        // Stats a pet gives itself aren't granted.
+        let dog = &stats.friend_team[2];
+        assert_eq!((dog.effect_triggers, dog.stats_granted), (1, 0));
+
+        let cricket = &stats.enemy_team[0];
+        assert_eq!((cricket.damage_dealt, cricket.damage_taken), (2, 3));
+        assert_eq!((cricket.effect_triggers, cricket.summons), (1, 1));
+        // Summoned pets are listed after the starting pets.
+        assert_eq!(stats.enemy_team[2].name, "Zombie Cricket");
+    }
+
+    #[test]
+    fn test_totals() {
+        let stats = battle_stats();
+        let mut totals = BattleStats::<PetTotals>::default();
+        totals.add(&stats);
+
+        let mut other = BattleStats::<PetTotals>::default();
+        other.add(&stats);
+        totals.merge(other);
+
+        let aggregates = totals.aggregates();
+        let ant = &aggregates.friend_team[1];
+        assert_eq!(ant.battles, 2);
+        assert_eq!(ant.damage_dealt, 2.0);
+        assert_eq!(ant.faint_rate, 1.0);
+        assert_eq!(ant.mean_fainted_turn, Some(1.0));
+        assert_eq!(aggregates.friend_team[0].mean_fainted_turn, None);
+    }
+}
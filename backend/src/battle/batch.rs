@@ -0,0 +1,247 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use saptest::teams::team::TeamFightOutcome;
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, panic::resume_unwind};
+use tokio::task::JoinSet;
+use tracing::Instrument;
+
+use super::{
+    attribution::{BattleLog, BattleStats, PetAggregate, PetTotals},
+    pool::{num_workers, run_simulation},
+    response::{build_teams, fight_teams, random_seed},
+    team::Teams,
+};
+use crate::{config::config, health::metrics::record_battle};
+
+#[derive(Deserialize)]
+pub struct BatchRequest {
+    #[serde(flatten)]
+    pub teams: Teams,
+    /// Battles to fight. Run `i` is fought with seed `seed + i`.
+    pub runs: u64,
+}
+
+/// Outcomes of a batch of battles from the friend team's side.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchTally {
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    /// Battles stopped at the turn limit.
+    pub unfinished: u64,
+    pub total_turns: u64,
+    /// Pet performance summed over all runs.
+    #[serde(skip)]
+    pub pet_totals: BattleStats<PetTotals>,
+}
+
+impl BatchTally {
+    fn record(&mut self, outcome: Option<&TeamFightOutcome>, num_turns: usize) {
+        match outcome {
+            Some(TeamFightOutcome::Win) => self.wins += 1,
+            Some(TeamFightOutcome::Loss) => self.losses += 1,
+            Some(TeamFightOutcome::Draw) => self.draws += 1,
+            Some(TeamFightOutcome::None) | None => self.unfinished += 1,
+        }
+        self.total_turns += num_turns as u64;
+    }
+
+    fn merge(&mut self, other: BatchTally) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+        self.unfinished += other.unfinished;
+        self.total_turns += other.total_turns;
+        self.pet_totals.merge(other.pet_totals);
+    }
+
+    pub fn runs(&self) -> u64 {
+        self.wins + self.losses + self.draws + self.unfinished
+    }
+
+    pub fn mean_turns(&self) -> f64 {
+        match self.runs() {
+            0 => 0.0,
+            runs => self.total_turns as f64 / runs as f64,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BatchResponse {
+    pub status: Option<String>,
+    pub seed: u64,
+    pub runs: u64,
+    #[serde(flatten)]
+    pub tally: BatchTally,
+    pub win_rate: f64,
+    pub mean_turns: f64,
+    /// Mean pet performance over the runs each pet was in.
+    pub pet_stats: BattleStats<PetAggregate>,
+}
+
+impl BatchResponse {
+    /// Response for a finished batch fought with `seed`.
+    pub fn from_tally(seed: u64, tally: BatchTally) -> Self {
+        let win_rate = match tally.runs() {
+            0 => 0.0,
+            runs => tally.wins as f64 / runs as f64,
+        };
+        Self {
+            status: Some(StatusCode::ACCEPTED.to_string()),
+            seed,
+            runs: tally.runs(),
+            win_rate,
+            mean_turns: tally.mean_turns(),
+            pet_stats: tally.pet_totals.aggregates(),
+            tally,
+        }
+    }
+}
+
+/// Fight the runs in `runs` one after another.
+fn fight_runs(teams: Teams, seed: u64, runs: Range<u64>) -> Result<BatchTally, String> {
+    let mut tally = BatchTally::default();
+    for run in runs {
+        let (mut team, mut enemy_team) = build_teams(teams.clone(), seed.wrapping_add(run))?;
+        let mut log = BattleLog::new(&team, &mut enemy_team);
+        // Turn 0 is recorded like in single battles.
+        log.record_turn(0, &team, &enemy_team);
+        let (outcome, num_turns) =
+            fight_teams(&mut team, &mut enemy_team, |turn, team, enemy_team| {
+                log.record_turn(turn, team, enemy_team)
+            });
+        record_battle(outcome.as_ref().ok());
+        tally.record(outcome.as_ref().ok(), num_turns);
+        tally.pet_totals.add(&log.finish(&team, &mut enemy_team));
+    }
+    Ok(tally)
+}
+
+/// Fight `runs` battles between the same teams. Runs are split evenly across the simulation workers.
+/// * Returns an error message if either team is invalid.
+pub async fn run_batch(teams: Teams, seed: u64, runs: u64) -> Result<BatchTally, String> {
+    run_batch_split(teams, seed, runs, num_workers()).await
+}
+
+/// Same as [`run_batch`] but runs are split into `workers` chunks. Used to compare against a single worker.
+pub async fn run_batch_split(
+    teams: Teams,
+    seed: u64,
+    runs: u64,
+    workers: usize,
+) -> Result<BatchTally, String> {
+    let workers = (workers as u64).clamp(1, runs.max(1));
+    let chunk_size = runs.div_ceil(workers).max(1);
+
+    let mut chunks = JoinSet::new();
+    for start in (0..runs).step_by(chunk_size as usize) {
+        let teams = teams.clone();
+        let end = (start + chunk_size).min(runs);
+        chunks.spawn(
+            run_simulation(move || fight_runs(teams, seed, start..end))
+                .instrument(tracing::Span::current()),
+        );
+    }
+
+    let mut tally = BatchTally::default();
+    while let Some(chunk) = chunks.join_next().await {
+        let chunk = chunk.unwrap_or_else(|err| resume_unwind(err.into_panic()))?;
+        tally.merge(chunk);
+    }
+    Ok(tally)
+}
+
+pub async fn post_batch(Json(request): Json<BatchRequest>) -> impl IntoResponse {
+    let max_runs = config().battle.max_batch_runs as u64;
+    let mut resp = BatchResponse {
+        runs: request.runs,
+        ..Default::default()
+    };
+    if !(1..=max_runs).contains(&request.runs) {
+        resp.status = Some(format!("Runs must be between 1 and {max_runs}."));
+        return (StatusCode::BAD_REQUEST, Json(resp));
+    }
+    resp.seed = request.teams.seed.unwrap_or_else(random_seed);
+
+    match run_batch(request.teams, resp.seed, request.runs).await {
+        Ok(tally) => (
+            StatusCode::ACCEPTED,
+            Json(BatchResponse::from_tally(resp.seed, tally)),
+        ),
+        Err(err_msg) => {
+            resp.status = Some(err_msg);
+            (StatusCode::BAD_REQUEST, Json(resp))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::post_json;
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_tally_merge() {
+        let mut tally = BatchTally::default();
+        tally.record(Some(&TeamFightOutcome::Win), 3);
+        tally.record(None, 251);
+
+        let mut other = BatchTally::default();
+        other.record(Some(&TeamFightOutcome::Loss), 4);
+        other.record(Some(&TeamFightOutcome::Draw), 2);
+        tally.merge(other);
+
+        assert_eq!(tally.runs(), 4);
+        assert_eq!(tally.unfinished, 1);
+        assert_eq!(tally.mean_turns(), 65.0);
+    }
+
+    #[tokio::test]
+    async fn test_post_batch() {
+        let mut request: Value =
+            serde_json::from_str(include_str!("../../tests/test_teams.json")).unwrap();
+        request["seed"] = json!(42);
+        request["runs"] = json!(20);
+
+        let (status, body) = post_json("/battle/batch", request.clone()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let tally: BatchTally = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(tally.runs(), 20);
+
+        // Same seed gives the same outcomes.
+        let (_, repeat_body) = post_json("/battle/batch", request).await;
+        assert_eq!(body, repeat_body);
+    }
+
+    #[test]
+    fn test_fight_runs_split() {
+        let teams: Teams =
+            serde_json::from_str(include_str!("../../tests/test_teams.json")).unwrap();
+        let tally = fight_runs(teams.clone(), 42, 0..12).unwrap();
+
+        // Runs split across workers give the same outcomes as fighting them together.
+        let mut split_tally = BatchTally::default();
+        for runs in [0..5, 5..6, 6..12] {
+            split_tally.merge(fight_runs(teams.clone(), 42, runs).unwrap());
+        }
+        assert_eq!(split_tally, tally);
+    }
+
+    #[tokio::test]
+    async fn test_post_batch_invalid_runs() {
+        let mut request: Value =
+            serde_json::from_str(include_str!("../../tests/test_teams.json")).unwrap();
+        request["runs"] = json!(0);
+
+        let (status, _) = post_json("/battle/batch", request.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        request["runs"] = json!(config().battle.max_batch_runs + 1);
+        let (status, _) = post_json("/battle/batch", request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
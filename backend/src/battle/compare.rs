@@ -0,0 +1,336 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use itertools::{EitherOrBoth, Itertools};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    pool::run_simulation,
+    response::{battle, random_seed, BattleResponse},
+    snapshot::{PetSnapshot, TeamSnapshot, TurnSnapshot},
+    store::get_battle,
+    team::Teams,
+};
+
+/// A battle to compare. Either a new battle request or the id of a previous `/battle` request.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum BattleSource {
+    Stored { id: u64 },
+    Teams(Teams),
+}
+
+#[derive(Deserialize)]
+pub struct CompareRequest {
+    pub first: BattleSource,
+    pub second: BattleSource,
+    /// Seed both battles are fought with.
+    /// Defaults to the seed of either battle, stored or given inline, or a random seed.
+    /// Battles with different seeds need a seed here.
+    pub seed: Option<u64>,
+}
+
+/// Both battles aligned at a single turn. A battle that already ended has no snapshot.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TurnComparison {
+    pub turn: usize,
+    pub diverged: bool,
+    pub first: Option<TurnSnapshot>,
+    pub second: Option<TurnSnapshot>,
+}
+
+/// Pets that fainted in only one of the battles.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaintedDiff {
+    pub first_only: Vec<String>,
+    pub second_only: Vec<String>,
+}
+
+/// Final state of a pet that differs between battles. Missing if the pet did not survive.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatDiff {
+    pub pet: String,
+    pub first: Option<PetSnapshot>,
+    pub second: Option<PetSnapshot>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamDiff {
+    pub fainted: FaintedDiff,
+    pub stats: Vec<StatDiff>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CompareResponse {
+    pub status: Option<String>,
+    pub seed: u64,
+    pub outcome_changed: bool,
+    pub divergent_turn: Option<usize>,
+    pub friend_team: TeamDiff,
+    pub enemy_team: TeamDiff,
+    pub turns: Vec<TurnComparison>,
+    pub first: BattleResponse,
+    pub second: BattleResponse,
+}
+
+fn fainted_diff(first: &TeamSnapshot, second: &TeamSnapshot) -> FaintedDiff {
+    let first_fainted = first.fainted.iter().map(PetSnapshot::key).collect_vec();
+    let second_fainted = second.fainted.iter().map(PetSnapshot::key).collect_vec();
+
+    FaintedDiff {
+        first_only: first_fainted
+            .iter()
+            .filter(|pet| !second_fainted.contains(pet))
+            .map(|pet| pet.to_string())
+            .collect_vec(),
+        second_only: second_fainted
+            .iter()
+            .filter(|pet| !first_fainted.contains(pet))
+            .map(|pet| pet.to_string())
+            .collect_vec(),
+    }
+}
+
+fn stat_diffs(first: &TeamSnapshot, second: &TeamSnapshot) -> Vec<StatDiff> {
+    first
+        .pets
+        .iter()
+        .chain(second.pets.iter())
+        .map(PetSnapshot::key)
+        .unique()
+        .filter_map(|key| {
+            let first_pet = first.pets.iter().find(|pet| pet.key() == key);
+            let second_pet = second.pets.iter().find(|pet| pet.key() == key);
+            (first_pet != second_pet).then(|| StatDiff {
+                pet: key.to_owned(),
+                first: first_pet.cloned(),
+                second: second_pet.cloned(),
+            })
+        })
+        .collect_vec()
+}
+
+fn team_diff(first: &TeamSnapshot, second: &TeamSnapshot) -> TeamDiff {
+    TeamDiff {
+        fainted: fainted_diff(first, second),
+        stats: stat_diffs(first, second),
+    }
+}
+
+/// Align the turns of two battles. Turns are divergent if the state of either team differs.
+pub fn align_turns(first: Vec<TurnSnapshot>, second: Vec<TurnSnapshot>) -> Vec<TurnComparison> {
+    first
+        .into_iter()
+        .zip_longest(second)
+        .enumerate()
+        .map(|(turn, turns)| {
+            let (first, second) = match turns {
+                EitherOrBoth::Both(first, second) => (Some(first), Some(second)),
+                EitherOrBoth::Left(first) => (Some(first), None),
+                EitherOrBoth::Right(second) => (None, Some(second)),
+            };
+            let diverged = match (&first, &second) {
+                (Some(first), Some(second)) => {
+                    first.friend_team != second.friend_team || first.enemy_team != second.enemy_team
+                }
+                _ => true,
+            };
+            TurnComparison {
+                turn,
+                diverged,
+                first,
+                second,
+            }
+        })
+        .collect_vec()
+}
+
+fn resolve_source(source: BattleSource) -> Result<(Teams, Option<u64>), String> {
+    match source {
+        BattleSource::Stored { id } => get_battle(id)
+            .map(|stored| (stored.teams, Some(stored.seed)))
+            .ok_or_else(|| format!("No stored battle with id {id}.")),
+        BattleSource::Teams(teams) => {
+            let seed = teams.seed;
+            Ok((teams, seed))
+        }
+    }
+}
+
+/// Seed both battles are fought with. See [`CompareRequest::seed`].
+fn resolve_seed(
+    seed: Option<u64>,
+    first_seed: Option<u64>,
+    second_seed: Option<u64>,
+) -> Result<u64, String> {
+    match (seed, first_seed, second_seed) {
+        (Some(seed), _, _) => Ok(seed),
+        (None, Some(first_seed), Some(second_seed)) if first_seed != second_seed => Err(format!(
+            "Battles have different seeds, {first_seed} and {second_seed}. Give a seed to compare them."
+        )),
+        (None, first_seed, second_seed) => {
+            Ok(first_seed.or(second_seed).unwrap_or_else(random_seed))
+        }
+    }
+}
+
+fn run_with_snapshots(
+    teams: Teams,
+    seed: u64,
+) -> Result<(BattleResponse, Vec<TurnSnapshot>), String> {
+    let mut turns = vec![];
+    battle(teams, seed, |turn, team, enemy_team| {
+        turns.push(TurnSnapshot::new(turn, team, enemy_team))
+    })
+    .map(|resp| (resp, turns))
+}
+
+pub async fn post_compare(Json(request): Json<CompareRequest>) -> impl IntoResponse {
+    let mut resp = CompareResponse::default();
+
+    let (first, second) = match (
+        resolve_source(request.first),
+        resolve_source(request.second),
+    ) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(err_msg), _) | (_, Err(err_msg)) => {
+            resp.status = Some(err_msg);
+            return (StatusCode::NOT_FOUND, Json(resp));
+        }
+    };
+    resp.seed = match resolve_seed(request.seed, first.1, second.1) {
+        Ok(seed) => seed,
+        Err(err_msg) => {
+            resp.status = Some(err_msg);
+            return (StatusCode::BAD_REQUEST, Json(resp));
+        }
+    };
+
+    let seed = resp.seed;
+    let (first_res, second_res) = tokio::join!(
+        run_simulation(move || run_with_snapshots(first.0, seed)),
+        run_simulation(move || run_with_snapshots(second.0, seed))
+    );
+    let (first_resp, first_turns) = match first_res {
+        Ok(res) => res,
+        Err(err_msg) => {
+            resp.status = Some(format!("First Battle: {err_msg}"));
+            return (StatusCode::BAD_REQUEST, Json(resp));
+        }
+    };
+    let (second_resp, second_turns) = match second_res {
+        Ok(res) => res,
+        Err(err_msg) => {
+            resp.status = Some(format!("Second Battle: {err_msg}"));
+            return (StatusCode::BAD_REQUEST, Json(resp));
+        }
+    };
+
+    if let (Some(first_final), Some(second_final)) = (first_turns.last(), second_turns.last()) {
+        resp.friend_team = team_diff(&first_final.friend_team, &second_final.friend_team);
+        resp.enemy_team = team_diff(&first_final.enemy_team, &second_final.enemy_team);
+    }
+    resp.turns = align_turns(first_turns, second_turns);
+    resp.divergent_turn = resp
+        .turns
+        .iter()
+        .find_map(|turn| turn.diverged.then_some(turn.turn));
+    resp.outcome_changed = first_resp.outcome != second_resp.outcome;
+    resp.first = first_resp;
+    resp.second = second_resp;
+    resp.status = Some(StatusCode::ACCEPTED.to_string());
+
+    (StatusCode::ACCEPTED, Json(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::post_json;
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn pet(key: &str, attack: isize, health: isize) -> PetSnapshot {
+        PetSnapshot {
+            id: Some(key.to_owned()),
+            name: key.to_owned(),
+            attack,
+            health,
+            item: None,
+        }
+    }
+
+    fn turn(turn: usize, friends: Vec<PetSnapshot>, fainted: Vec<PetSnapshot>) -> TurnSnapshot {
+        TurnSnapshot {
+            turn,
+            friend_team: TeamSnapshot {
+                pets: friends,
+                fainted,
+            },
+            enemy_team: TeamSnapshot {
+                pets: vec![],
+                fainted: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_align_turns() {
+        let first = vec![
+            turn(0, vec![pet("Ant_0", 2, 1)], vec![]),
+            turn(1, vec![pet("Ant_0", 2, 1)], vec![]),
+        ];
+        let second = vec![
+            turn(0, vec![pet("Ant_0", 2, 1)], vec![]),
+            turn(1, vec![], vec![pet("Ant_0", 2, 0)]),
+            turn(2, vec![], vec![pet("Ant_0", 2, 0)]),
+        ];
+        let turns = align_turns(first, second);
+
+        assert_eq!(turns.len(), 3);
+        assert!(!turns[0].diverged && turns[1].diverged && turns[2].diverged);
+        // First battle ended before the second.
+        assert!(turns[2].first.is_none() && turns[2].second.is_some());
+    }
+
+    #[test]
+    fn test_team_diff() {
+        let first = turn(1, vec![pet("Ant_0", 2, 1)], vec![pet("Dog_0", 3, 0)]);
+        let second = turn(1, vec![pet("Ant_0", 3, 1)], vec![]);
+        let diff = team_diff(&first.friend_team, &second.friend_team);
+
+        assert_eq!(diff.fainted.first_only, vec!["Dog_0".to_owned()]);
+        assert!(diff.fainted.second_only.is_empty());
+        assert_eq!(
+            diff.stats,
+            vec![StatDiff {
+                pet: "Ant_0".to_owned(),
+                first: Some(pet("Ant_0", 2, 1)),
+                second: Some(pet("Ant_0", 3, 1))
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resolve_seed() {
+        assert_eq!(resolve_seed(Some(1), Some(2), Some(3)), Ok(1));
+        assert_eq!(resolve_seed(None, None, Some(3)), Ok(3));
+        assert_eq!(resolve_seed(None, Some(2), Some(2)), Ok(2));
+        // Conflicting seeds need an explicit seed.
+        assert!(resolve_seed(None, Some(2), Some(3)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compare_same_battle() {
+        let teams: Value =
+            serde_json::from_str(include_str!("../../tests/test_teams.json")).unwrap();
+        let request = json!({ "first": teams, "second": teams, "seed": 42 });
+
+        let (status, body) = post_json("/battle/compare", request).await;
+
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        // Same teams and seed never diverge.
+        assert_eq!(body.get("divergent_turn"), Some(&Value::Null));
+        assert_eq!(body.get("outcome_changed"), Some(&Value::Bool(false)));
+    }
+}
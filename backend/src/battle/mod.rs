@@ -1,5 +1,10 @@
+pub mod analysis;
+pub mod attribution;
+pub mod batch;
+pub mod compare;
+pub mod pool;
 pub mod response;
+pub mod resume;
+pub mod snapshot;
+pub mod store;
 pub mod team;
-
-const TEAM_SIZE: usize = 5;
-const ALLOWED_NUM_TURNS: usize = 250;
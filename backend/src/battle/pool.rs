@@ -0,0 +1,58 @@
+use once_cell::sync::Lazy;
+use std::{num::NonZeroUsize, panic::resume_unwind, thread::available_parallelism};
+use tokio::sync::Semaphore;
+
+use crate::config::config;
+
+/// Limits how many battles are simulated at once.
+static SIMULATION_PERMITS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(num_workers()));
+
+/// Threads battles are simulated on. One per core if not configured.
+pub fn num_workers() -> usize {
+    match config().battle.workers {
+        0 => available_parallelism().map_or(1, NonZeroUsize::get),
+        workers => workers,
+    }
+}
+
+/// Run a CPU-bound simulation on the blocking thread pool so it doesn't stall the async runtime.
+/// * Waits until one of the [`num_workers`] slots is free.
+/// * Panics in `simulation` are resumed in the caller.
+pub async fn run_simulation<T, F>(simulation: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let _permit = SIMULATION_PERMITS
+        .acquire()
+        .await
+        .expect("Simulation pool closed.");
+    let span = tracing::Span::current();
+
+    match tokio::task::spawn_blocking(move || span.in_scope(simulation)).await {
+        Ok(res) => res,
+        Err(err) if err.is_panic() => resume_unwind(err.into_panic()),
+        Err(err) => panic!("Simulation cancelled: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_simulation() {
+        let thread = std::thread::current().id();
+        let sim_thread = run_simulation(|| std::thread::current().id()).await;
+
+        // Simulations don't run on the calling thread.
+        assert_ne!(thread, sim_thread);
+        assert!(num_workers() > 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Invalid battle")]
+    async fn test_run_simulation_panic() {
+        run_simulation(|| panic!("Invalid battle")).await
+    }
+}
@@ -3,73 +3,195 @@ use saptest::{
     create_battle_digraph, error::SAPTestError, teams::team::TeamFightOutcome, Team, TeamCombat,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
 
-use super::{team::Teams, ALLOWED_NUM_TURNS};
+use super::{
+    attribution::{BattleLog, BattleStats},
+    pool::run_simulation,
+    store::store_battle,
+    team::{build_team, Teams},
+};
+use crate::{
+    config::config,
+    health::metrics::{record_battle, record_turn_limit, record_validation_failure},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BattleResponse {
-    status: Option<String>,
-    outcome: TeamFightOutcome,
-    friend_team: Option<Team>,
-    enemy_team: Option<Team>,
-    num_turns: usize,
-    digraph: Option<String>,
+    pub id: Option<u64>,
+    pub status: Option<String>,
+    pub outcome: TeamFightOutcome,
+    pub friend_team: Option<Value>,
+    pub enemy_team: Option<Value>,
+    pub num_turns: usize,
+    pub seed: Option<u64>,
+    pub digraph: Option<String>,
+    /// Performance of each pet. See [`BattleLog`].
+    pub pet_stats: Option<BattleStats>,
+    /// State of both teams before the battle and after every turn. Only kept if requested.
+    pub turns: Option<Vec<TurnState>>,
+}
+
+/// Full state of both teams at the end of a turn. Can be used to resume the battle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnState {
+    pub turn: usize,
+    pub friend_team: Value,
+    pub enemy_team: Value,
+}
+
+/// How already built teams are fought.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FightOptions {
+    /// Turn the teams are at. Turns fought are numbered from it.
+    pub first_turn: usize,
+    /// Keep the state of both teams after every turn.
+    pub replay: bool,
 }
 
 impl Default for BattleResponse {
     fn default() -> Self {
         Self {
+            id: Default::default(),
             status: Default::default(),
             outcome: TeamFightOutcome::None,
             friend_team: Default::default(),
             enemy_team: Default::default(),
             num_turns: Default::default(),
+            seed: Default::default(),
             digraph: Default::default(),
+            pet_stats: Default::default(),
+            turns: Default::default(),
         }
     }
 }
 
-pub async fn post_battle(Json(teams): Json<Teams>) -> impl IntoResponse {
-    let mut resp = BattleResponse::default();
-    let friend_team: Result<Team, SAPTestError> = teams.friend_team.try_into();
-    let enemy_team: Result<Team, SAPTestError> = teams.enemy_team.try_into();
-
-    let Ok(mut team) = friend_team else {
-        let err_msg = format!("Invalid Friend Team: {:?}", friend_team.unwrap_err());
-        resp.status = Some(err_msg);
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(resp)
-        )
-    };
-    let Ok(mut enemy_team) = enemy_team else {
-        let err_msg = format!("Invalid Enemy Team: {:?}", enemy_team.unwrap_err());
-        resp.status = Some(err_msg);
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(resp)
-        )
-    };
+/// Seed for battles that were not given one.
+pub fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
 
+/// Fight `team` against `enemy_team` until the battle is decided or the turn limit is reached.
+/// * `on_turn` is called with the turn number and both teams after every turn.
+pub fn fight_teams<F>(
+    team: &mut Team,
+    enemy_team: &mut Team,
+    mut on_turn: F,
+) -> (Result<TeamFightOutcome, SAPTestError>, usize)
+where
+    F: FnMut(usize, &Team, &Team),
+{
+    let max_turns = config().battle.max_turns;
     let mut num_turns = 0;
     let mut outcome = Ok(TeamFightOutcome::None);
     while let Ok(TeamFightOutcome::None) = outcome {
-        if num_turns > ALLOWED_NUM_TURNS {
+        if num_turns > max_turns {
+            record_turn_limit();
             outcome = Err(SAPTestError::InvalidTeamAction {
                 subject: "Battle Duration".to_owned(),
                 reason: format!("Reached maximum turn limit, {num_turns}"),
             });
             break;
         }
-        outcome = team.fight(&mut enemy_team);
+        outcome = team.fight(enemy_team);
         num_turns += 1;
+        on_turn(num_turns, team, enemy_team);
     }
+    (outcome, num_turns)
+}
+
+/// Build both teams with the given seed.
+/// * Returns an error message if either team is invalid.
+pub fn build_teams(teams: Teams, seed: u64) -> Result<(Team, Team), String> {
+    let (friend_team, enemy_team) = tracing::info_span!("team_conversion").in_scope(|| {
+        let friend_team = build_team(teams.friend_team);
+        let enemy_team = build_team(teams.enemy_team);
+        (friend_team, enemy_team)
+    });
+
+    let Ok(mut team) = friend_team else {
+        record_validation_failure();
+        return Err(format!(
+            "Invalid Friend Team: {:?}",
+            friend_team.unwrap_err()
+        ));
+    };
+    let Ok(mut enemy_team) = enemy_team else {
+        record_validation_failure();
+        return Err(format!("Invalid Enemy Team: {:?}", enemy_team.unwrap_err()));
+    };
+    team.set_seed(Some(seed));
+    enemy_team.set_seed(Some(seed));
+    Ok((team, enemy_team))
+}
+
+/// Build both teams with the given seed and fight them.
+/// * `on_turn` is called with the turn number and both teams before the battle (turn 0) and after every turn.
+/// * Returns an error message if either team is invalid.
+pub fn battle<F>(teams: Teams, seed: u64, on_turn: F) -> Result<BattleResponse, String>
+where
+    F: FnMut(usize, &Team, &Team),
+{
+    let options = FightOptions {
+        first_turn: 0,
+        replay: teams.replay,
+    };
+    let (team, enemy_team) = build_teams(teams, seed)?;
+    Ok(fight_to_response(team, enemy_team, seed, options, on_turn))
+}
+
+/// Fight built teams until the battle is decided or the turn limit is reached.
+/// * `on_turn` is called with the turn number and both teams before fighting and after every turn.
+pub fn fight_to_response<F>(
+    mut team: Team,
+    mut enemy_team: Team,
+    seed: u64,
+    options: FightOptions,
+    mut on_turn: F,
+) -> BattleResponse
+where
+    F: FnMut(usize, &Team, &Team),
+{
+    let mut log = BattleLog::new(&team, &mut enemy_team);
+    let mut states = vec![];
+    let mut on_turn = |turn: usize, team: &Team, enemy_team: &Team| {
+        let turn = options.first_turn + turn;
+        log.record_turn(turn, team, enemy_team);
+        if options.replay {
+            states.push(TurnState {
+                turn,
+                friend_team: serde_json::to_value(team).unwrap_or_default(),
+                enemy_team: serde_json::to_value(enemy_team).unwrap_or_default(),
+            });
+        }
+        on_turn(turn, team, enemy_team)
+    };
+
+    let fight_span = tracing::info_span!("fight", seed, num_turns = tracing::field::Empty);
+    let (outcome, num_turns) = fight_span.in_scope(|| {
+        on_turn(0, &team, &enemy_team);
+        fight_teams(&mut team, &mut enemy_team, &mut on_turn)
+    });
+    fight_span.record("num_turns", num_turns);
+    record_battle(outcome.as_ref().ok());
 
     let digraph = create_battle_digraph(&team, false);
-    resp.friend_team = Some(team);
-    resp.enemy_team = Some(enemy_team);
+    // Restores the enemy team's name so it's finished before the teams are kept.
+    let pet_stats = log.finish(&team, &mut enemy_team);
+    let mut resp = BattleResponse {
+        seed: Some(seed),
+        ..Default::default()
+    };
+    resp.friend_team = serde_json::to_value(&team).ok();
+    resp.enemy_team = serde_json::to_value(&enemy_team).ok();
     resp.digraph = Some(digraph);
-    resp.num_turns = num_turns;
+    resp.num_turns = options.first_turn + num_turns;
+    resp.pet_stats = Some(pet_stats);
+    resp.turns = options.replay.then_some(states);
 
     if let Ok(battle_outcome) = outcome {
         resp.outcome = battle_outcome;
@@ -78,5 +200,117 @@ pub async fn post_battle(Json(teams): Json<Teams>) -> impl IntoResponse {
         resp.status = Some(outcome.unwrap_err().to_string());
     }
 
-    (StatusCode::ACCEPTED, Json(resp))
+    resp
+}
+
+pub async fn post_battle(Json(teams): Json<Teams>) -> impl IntoResponse {
+    let seed = teams.seed.unwrap_or_else(random_seed);
+
+    let battle_teams = teams.clone();
+
+    match run_simulation(move || battle(battle_teams, seed, |_, _, _| {})).await {
+        Ok(mut resp) => {
+            // Keep request so it can be replayed in comparisons.
+            resp.id = Some(store_battle(teams, seed));
+            (StatusCode::ACCEPTED, Json(resp))
+        }
+        Err(err_msg) => {
+            let resp = BattleResponse {
+                status: Some(err_msg),
+                ..Default::default()
+            };
+            (StatusCode::BAD_REQUEST, Json(resp))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::battle::team::{SimplePet, SimpleTeam};
+
+    fn team(name: &str, pets: &[(&str, Option<&str>)]) -> SimpleTeam {
+        SimpleTeam {
+            name: name.to_owned(),
+            pets: pets
+                .iter()
+                .map(|(pet, item)| {
+                    Some(SimplePet {
+                        name: (*pet).to_owned(),
+                        item: item.map(str::to_owned),
+                        ..Default::default()
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Attack and health of each pet alive after a battle, front first.
+    fn alive_stats(team: &Value) -> Vec<(usize, usize)> {
+        team["friends"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|pet| !pet.is_null())
+            .map(|pet| {
+                let stat = |name: &str| pet["stats"][name].as_u64().unwrap_or_default() as usize;
+                (stat("attack"), stat("health"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sim_parity() {
+        // Only pets and foods modeled by sim without random targets.
+        let teams = Teams {
+            friend_team: team(
+                "Friend",
+                &[
+                    ("Cricket", None),
+                    ("Flamingo", None),
+                    ("Fish", Some("Garlic")),
+                ],
+            ),
+            enemy_team: team(
+                "Enemy",
+                &[
+                    ("Hedgehog", None),
+                    ("Sheep", None),
+                    ("Beaver", Some("Meat Bone")),
+                ],
+            ),
+            seed: None,
+            replay: false,
+        };
+        let sim_teams = sim::Teams {
+            friend_team: teams.friend_team.clone(),
+            enemy_team: teams.enemy_team.clone(),
+            seed: None,
+        };
+
+        let resp = battle(teams, 0, |_, _, _| {}).unwrap();
+        let result = sim::fight(&sim_teams, 0).unwrap();
+
+        assert!(result.unmodeled.is_empty());
+        assert_eq!(
+            format!("{:?}", resp.outcome),
+            format!("{:?}", result.outcome)
+        );
+        let sim_stats = |team: &sim::TeamState| {
+            team.friends
+                .iter()
+                .map(|pet| (pet.attack, pet.health))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            alive_stats(resp.friend_team.as_ref().unwrap()),
+            sim_stats(&result.friend_team)
+        );
+        assert_eq!(
+            alive_stats(resp.enemy_team.as_ref().unwrap()),
+            sim_stats(&result.enemy_team)
+        );
+    }
 }
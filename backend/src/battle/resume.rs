@@ -0,0 +1,252 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use saptest::{
+    pets::pet::{MAX_PET_STATS, MIN_PET_STATS},
+    Food, FoodName, Team,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::{
+    pool::run_simulation,
+    response::{fight_to_response, random_seed, BattleResponse, FightOptions},
+    team::rebuild_team,
+};
+use crate::{config::config, health::metrics::record_validation_failure};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeamSide {
+    Friend,
+    Enemy,
+}
+
+/// A change to a pet before the battle resumes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetEdit {
+    pub team: TeamSide,
+    /// Position of the pet. 0 is the front.
+    pub position: usize,
+    pub attack: Option<isize>,
+    pub health: Option<isize>,
+    /// Held food. An empty name removes the item.
+    pub item: Option<String>,
+    /// Position to move the pet to.
+    pub move_to: Option<usize>,
+}
+
+/// Teams in the middle of a battle. Teams are in the format of `friend_team` and `enemy_team` in a battle response.
+/// * Teams are only deserialized on the simulation workers as `saptest` teams can't be sent between threads.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResumeRequest {
+    pub friend_team: serde_json::Value,
+    pub enemy_team: serde_json::Value,
+    /// Seed for random effects after resuming.
+    /// * If not given, teams keep the random state they were serialized with so the rest of the original battle is
+    ///   fought again. Teams without a seed get a random one.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Turn the teams are from.
+    #[serde(default)]
+    pub turn: usize,
+    /// Applied in order before resuming.
+    #[serde(default)]
+    pub edits: Vec<PetEdit>,
+    #[serde(default)]
+    pub replay: bool,
+}
+
+/// Parse a team from a battle response. Pets are rebuilt from their definitions like a new battle's pets.
+fn parse_team(team: serde_json::Value, side: TeamSide) -> Result<Team, String> {
+    let team: Team =
+        serde_json::from_value(team).map_err(|err| format!("Invalid {side:?} Team: {err}"))?;
+    let num_pets = team.friends.iter().flatten().count();
+    if num_pets > config().battle.team_size {
+        return Err(format!(
+            "Invalid {side:?} Team: {num_pets} pets is more than {}.",
+            config().battle.team_size
+        ));
+    }
+    rebuild_team(&team).map_err(|err| format!("Invalid {side:?} Team: {err}"))
+}
+
+/// Apply an edit to a pet on `team`.
+/// * Health is at least 1 so edited pets don't resume fainted.
+pub fn apply_edit(team: &mut Team, edit: &PetEdit) -> Result<(), String> {
+    let Some(pet) = team.friends.get(edit.position).cloned().flatten() else {
+        return Err(format!(
+            "No {:?} pet at position {}.",
+            edit.team, edit.position
+        ));
+    };
+    {
+        let mut pet = pet.borrow_mut();
+        if let Some(attack) = edit.attack {
+            pet.stats.attack = attack.clamp(MIN_PET_STATS, MAX_PET_STATS);
+        }
+        if let Some(health) = edit.health {
+            pet.stats.health = health.clamp(MIN_PET_STATS.max(1), MAX_PET_STATS);
+        }
+        if let Some(item) = edit.item.as_deref() {
+            pet.item = if item.is_empty() {
+                None
+            } else {
+                Some(
+                    FoodName::from_str(item)
+                        .and_then(Food::try_from)
+                        .map_err(|err| format!("Invalid item {item}: {err}"))?,
+                )
+            };
+        }
+    }
+    if let Some(move_to) = edit.move_to {
+        let slot = team.friends.remove(edit.position);
+        team.friends.insert(move_to.min(team.friends.len()), slot);
+        for (pos, pet) in team.friends.iter().enumerate() {
+            if let Some(pet) = pet {
+                pet.borrow_mut().pos = Some(pos);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validation_failure(err: String) -> String {
+    record_validation_failure();
+    err
+}
+
+/// Continue a battle from the given teams after applying any edits.
+/// * Up to `max_turns` more turns are fought.
+/// * Random effects continue from the teams' random state unless the request has a seed. See [`ResumeRequest::seed`].
+/// * Returns an error message if either team or an edit is invalid.
+pub fn resume(request: ResumeRequest) -> Result<BattleResponse, String> {
+    let parsed = parse_team(request.friend_team, TeamSide::Friend).and_then(|team| {
+        parse_team(request.enemy_team, TeamSide::Enemy).map(|enemy_team| (team, enemy_team))
+    });
+    let (mut team, mut enemy_team) = parsed.map_err(validation_failure)?;
+
+    for edit in request.edits.iter() {
+        match edit.team {
+            TeamSide::Friend => apply_edit(&mut team, edit),
+            TeamSide::Enemy => apply_edit(&mut enemy_team, edit),
+        }
+        .map_err(validation_failure)?;
+    }
+    // Teams keep their random state unless a new seed is given.
+    for team in [&mut team, &mut enemy_team] {
+        if request.seed.is_some() || team.seed.is_none() {
+            team.set_seed(Some(request.seed.unwrap_or_else(random_seed)));
+        }
+    }
+    let seed = team.seed.unwrap_or_default();
+
+    let options = FightOptions {
+        first_turn: request.turn,
+        replay: request.replay,
+    };
+    Ok(fight_to_response(
+        team,
+        enemy_team,
+        seed,
+        options,
+        |_, _, _| {},
+    ))
+}
+
+pub async fn post_resume(Json(request): Json<ResumeRequest>) -> impl IntoResponse {
+    let seed = request.seed;
+
+    match run_simulation(move || resume(request)).await {
+        Ok(resp) => (StatusCode::ACCEPTED, Json(resp)),
+        Err(err_msg) => {
+            let resp = BattleResponse {
+                status: Some(err_msg),
+                seed,
+                ..Default::default()
+            };
+            (StatusCode::BAD_REQUEST, Json(resp))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::post_json;
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_post_resume() {
+        let mut request: Value =
+            serde_json::from_str(include_str!("../../tests/test_teams.json")).unwrap();
+        request["seed"] = json!(42);
+        request["replay"] = json!(true);
+        let (status, battle) = post_json("/battle", request).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let turns = battle["turns"].as_array().unwrap();
+        assert_eq!(
+            turns.len(),
+            battle["num_turns"].as_u64().unwrap() as usize + 1
+        );
+
+        // Resuming from the start with the same seed fights the same battle.
+        let resume_request = json!({
+            "friend_team": turns[0]["friend_team"],
+            "enemy_team": turns[0]["enemy_team"],
+            "seed": 42,
+        });
+        let (status, resumed) = post_json("/battle/resume", resume_request.clone()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(resumed["outcome"], battle["outcome"]);
+        assert_eq!(resumed["num_turns"], battle["num_turns"]);
+        assert!(resumed["turns"].is_null());
+
+        // Resuming mid-battle without a seed continues the original battle.
+        let (status, continued) = post_json(
+            "/battle/resume",
+            json!({
+                "friend_team": turns[1]["friend_team"],
+                "enemy_team": turns[1]["enemy_team"],
+                "turn": 1,
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(continued["outcome"], battle["outcome"]);
+        assert_eq!(continued["num_turns"], battle["num_turns"]);
+        assert_eq!(
+            continued["friend_team"]["friends"],
+            battle["friend_team"]["friends"]
+        );
+        assert_eq!(
+            continued["enemy_team"]["friends"],
+            battle["enemy_team"]["friends"]
+        );
+
+        // Edits apply mid-battle.
+        let (status, edited) = post_json(
+            "/battle/resume",
+            json!({
+                "friend_team": turns[1]["friend_team"],
+                "enemy_team": turns[1]["enemy_team"],
+                "turn": 1,
+                "edits": [{"team": "Friend", "position": 0, "attack": 50, "health": 50, "item": null, "move_to": null}],
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(edited["outcome"], json!("Win"));
+
+        let mut edited_request = resume_request;
+        edited_request["turn"] = json!(0);
+        edited_request["edits"] = json!([
+            {"team": "Friend", "position": 0, "attack": 50, "health": 50, "item": "", "move_to": null}
+        ]);
+        let (status, edited) = post_json("/battle/resume", edited_request.clone()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(edited["outcome"], json!("Win"));
+
+        edited_request["edits"][0]["position"] = json!(10);
+        let (status, _) = post_json("/battle/resume", edited_request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
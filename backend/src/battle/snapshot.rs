@@ -0,0 +1,75 @@
+use itertools::Itertools;
+use saptest::{Pet, Team, TeamViewer};
+use serde::{Deserialize, Serialize};
+
+/// Pet state at the end of a turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetSnapshot {
+    pub id: Option<String>,
+    pub name: String,
+    pub attack: isize,
+    pub health: isize,
+    pub item: Option<String>,
+}
+
+impl PetSnapshot {
+    /// Identifier used to match the same pet across turns and battles.
+    pub fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl From<&Pet> for PetSnapshot {
+    fn from(pet: &Pet) -> Self {
+        PetSnapshot {
+            id: pet.id.clone(),
+            name: pet.name.to_string(),
+            attack: pet.stats.attack,
+            health: pet.stats.health,
+            item: pet.item.as_ref().map(|food| food.name.to_string()),
+        }
+    }
+}
+
+/// Alive and fainted pets of a team at the end of a turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamSnapshot {
+    pub pets: Vec<PetSnapshot>,
+    pub fainted: Vec<PetSnapshot>,
+}
+
+impl From<&Team> for TeamSnapshot {
+    fn from(team: &Team) -> Self {
+        TeamSnapshot {
+            pets: team
+                .all()
+                .iter()
+                .map(|pet| PetSnapshot::from(&*pet.borrow()))
+                .collect_vec(),
+            fainted: team
+                .fainted
+                .iter()
+                .flatten()
+                .map(|pet| PetSnapshot::from(&*pet.borrow()))
+                .collect_vec(),
+        }
+    }
+}
+
+/// State of both teams at the end of a turn. Turn 0 is the state before the battle starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnSnapshot {
+    pub turn: usize,
+    pub friend_team: TeamSnapshot,
+    pub enemy_team: TeamSnapshot,
+}
+
+impl TurnSnapshot {
+    pub fn new(turn: usize, team: &Team, enemy_team: &Team) -> Self {
+        TurnSnapshot {
+            turn,
+            friend_team: team.into(),
+            enemy_team: enemy_team.into(),
+        }
+    }
+}
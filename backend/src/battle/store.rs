@@ -0,0 +1,35 @@
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use super::team::Teams;
+use crate::config::config;
+
+static NEXT_BATTLE_ID: AtomicU64 = AtomicU64::new(1);
+static BATTLES: Lazy<Mutex<IndexMap<u64, StoredBattle>>> = Lazy::new(Default::default);
+
+/// A battle request and the seed it was fought with.
+#[derive(Clone)]
+pub struct StoredBattle {
+    pub teams: Teams,
+    pub seed: u64,
+}
+
+/// Store a battle request and return its id.
+pub fn store_battle(teams: Teams, seed: u64) -> u64 {
+    let id = NEXT_BATTLE_ID.fetch_add(1, Ordering::Relaxed);
+    let mut battles = BATTLES.lock().unwrap();
+    // Oldest battles are evicted first.
+    if battles.len() >= config().cache.stored_battles {
+        battles.shift_remove_index(0);
+    }
+    battles.insert(id, StoredBattle { teams, seed });
+    id
+}
+
+pub fn get_battle(id: u64) -> Option<StoredBattle> {
+    BATTLES.lock().unwrap().get(&id).cloned()
+}
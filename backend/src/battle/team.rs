@@ -1,86 +1,103 @@
-use itertools::Itertools;
 use saptest::{
+    effects::actions::{Action, StatChangeType},
     error::SAPTestError,
     pets::pet::{MAX_PET_LEVEL, MAX_PET_STATS, MIN_PET_LEVEL, MIN_PET_STATS},
-    Food, FoodName, Pet, PetName, Team,
+    Food, Pet, Statistics, Team,
 };
 use serde::Deserialize;
-use std::str::FromStr;
+use sim::convert::{self, temp_stats_effect};
 
-use super::TEAM_SIZE;
+pub use sim::{
+    convert::build_pet,
+    team::{SimplePet, SimpleTeam, EMPTY_SLOT_NAME},
+};
+
+use crate::config::config;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct Teams {
     pub friend_team: SimpleTeam,
     pub enemy_team: SimpleTeam,
+    /// Seed for random effects. A random seed is used if not given.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Keep the full state of both teams after every turn so the battle can be resumed from any turn.
+    #[serde(default)]
+    pub replay: bool,
 }
 
-#[derive(Deserialize)]
-pub struct SimpleTeam {
-    name: String,
-    pets: Vec<Option<SimplePet>>,
-}
-
-#[derive(Deserialize, Default, Clone)]
-pub struct SimplePet {
-    pub name: String,
-    pub attack: Option<usize>,
-    pub health: Option<usize>,
-    pub level: Option<usize>,
-    pub item: Option<String>,
+/// Build a `saptest` team of the configured team size. Empty slots are kept.
+/// * [`Team::try_from`] always builds a team of the default size.
+pub fn build_team(simple_team: SimpleTeam) -> Result<Team, SAPTestError> {
+    convert::build_team(simple_team, config().battle.team_size)
 }
 
-impl TryFrom<SimplePet> for Pet {
-    type Error = SAPTestError;
-
-    fn try_from(simple_pet: SimplePet) -> Result<Self, Self::Error> {
-        let item = simple_pet
-            .item
-            .and_then(|item_name| FoodName::from_str(&item_name).ok())
-            .and_then(|item_name| Food::try_from(item_name).ok());
-
-        let pet_lvl = simple_pet
-            .level
-            .map_or(1, |lvl| lvl.clamp(MIN_PET_LEVEL, MAX_PET_LEVEL));
-
-        PetName::from_str(&simple_pet.name)
-            .and_then(|pet_name| Pet::new(pet_name, None, pet_lvl))
-            .map(|mut pet| {
-                //  Assign item.
-                pet.item = item;
-                // Assign stats if given.
-                if let Some(Ok(attack)) = simple_pet.attack.map(TryInto::<isize>::try_into) {
-                    pet.stats.attack = attack.clamp(MIN_PET_STATS, MAX_PET_STATS)
-                }
-                if let Some(Ok(health)) = simple_pet.health.map(TryInto::<isize>::try_into) {
-                    pet.stats.health = health.clamp(MIN_PET_STATS, MAX_PET_STATS)
-                }
-                pet
-            })
+/// Rebuild a pet from a battle in progress the way [`build_pet`] builds one.
+/// * Effects come from the pet's definition at its clamped level. Uses left are kept, up to the definition's.
+/// * Stats are clamped and the item is rebuilt from its name.
+/// * Temporary stats not yet added at the start of battle are kept.
+/// * The pet's seed is kept so random effects continue where they left off.
+pub fn rebuild_pet(pet: &Pet) -> Result<Pet, SAPTestError> {
+    let pet_lvl = pet.get_level().clamp(MIN_PET_LEVEL, MAX_PET_LEVEL);
+    let mut rebuilt = Pet::new(pet.name.clone(), None, pet_lvl)?;
+    rebuilt.id = pet.id.clone();
+    rebuilt.seed = pet.seed;
+    rebuilt.stats.attack = pet.stats.attack.clamp(MIN_PET_STATS, MAX_PET_STATS);
+    rebuilt.stats.health = pet.stats.health.clamp(MIN_PET_STATS, MAX_PET_STATS);
+    rebuilt.item = pet
+        .item
+        .as_ref()
+        .map(|food| Food::try_from(food.name.clone()))
+        .transpose()?;
+    for (effect, effect_left) in rebuilt.effect.iter_mut().zip(pet.effect.iter()) {
+        if let (Some(uses), Some(uses_left)) = (effect.uses, effect_left.uses) {
+            effect.uses = Some(uses.min(uses_left));
+        }
+    }
+    let temp_stats = pet
+        .effect
+        .iter()
+        .skip(rebuilt.effect.len())
+        .filter(|effect| effect.temp && effect.uses != Some(0))
+        .find_map(|effect| match &effect.action {
+            Action::Add(StatChangeType::StaticValue(stats)) => Some(Statistics {
+                attack: stats.attack.clamp(MIN_PET_STATS, MAX_PET_STATS),
+                health: stats.health.clamp(MIN_PET_STATS, MAX_PET_STATS),
+            }),
+            _ => None,
+        });
+    if let Some(temp_stats) = temp_stats {
+        rebuilt.effect.push(temp_stats_effect(temp_stats))
     }
+    Ok(rebuilt)
 }
 
-impl TryFrom<SimpleTeam> for Team {
-    type Error = SAPTestError;
-
-    fn try_from(simple_team: SimpleTeam) -> Result<Self, Self::Error> {
-        let pets = simple_team
-            .pets
-            .into_iter()
-            // If pet in slot, generate pet. Otherwise, treat as empty slot.
-            .map(|slot| slot.and_then(|pet| pet.try_into().ok()))
-            .collect_vec();
-
-        Team::new(&pets, TEAM_SIZE).map(|mut team| {
-            let _ = team.set_name(&simple_team.name);
-            team
+/// Rebuild a team from a battle in progress with [`rebuild_pet`]. Fainted pets are kept as they are.
+/// * Pending triggers and the team's seed are kept.
+pub fn rebuild_team(team: &Team) -> Result<Team, SAPTestError> {
+    let team_size = config().battle.team_size;
+    let pets = team
+        .friends
+        .iter()
+        .take(team_size)
+        .map(|slot| {
+            slot.as_ref()
+                .map(|pet| rebuild_pet(&pet.borrow()))
+                .transpose()
         })
-    }
+        .collect::<Result<Vec<Option<Pet>>, SAPTestError>>()?;
+
+    let mut rebuilt = Team::new(&pets, team_size)?;
+    let _ = rebuilt.set_name(&team.name);
+    rebuilt.fainted = team.fainted.clone();
+    rebuilt.triggers = team.triggers.clone();
+    rebuilt.seed = team.seed;
+    Ok(rebuilt)
 }
 
 #[cfg(test)]
 mod tests {
-    use saptest::TeamViewer;
+    use saptest::{PetName, TeamViewer};
 
     use super::*;
 
@@ -91,17 +108,39 @@ mod tests {
             ..Default::default()
         };
 
-        let ant: Pet = def_ant.try_into().unwrap();
+        let ant = build_pet(def_ant).unwrap();
 
         assert!(
             ant.name == PetName::Ant
                 && ant.get_level() == 1
                 && ant.stats.attack == 2
                 && ant.stats.health == 1
-                && ant.item == None
+                && ant.item.is_none()
         )
     }
 
+    #[test]
+    fn test_try_from() {
+        let simple_pet = SimplePet {
+            name: "Ant".to_owned(),
+            level: Some(2),
+            item: Some("Honey".to_owned()),
+            ..Default::default()
+        };
+        let simple_team = SimpleTeam {
+            name: "Friend".to_owned(),
+            pets: vec![Some(simple_pet.clone()), None],
+        };
+
+        let pet = Pet::try_from(simple_pet.clone()).unwrap();
+        let team = Team::try_from(simple_team.clone()).unwrap();
+
+        // Conversions are the same as building.
+        assert_eq!(pet, build_pet(simple_pet).unwrap());
+        assert_eq!(team.get_name(), build_team(simple_team).unwrap().get_name());
+        assert_eq!(team.all().len(), 1);
+    }
+
     #[test]
     fn test_deserialize_pet() {
         let pet_only_name = r#"{"name": "Ant"}"#;
@@ -136,7 +175,7 @@ mod tests {
             name: "The Super Auto Pets".to_owned(),
             pets: simple_pets,
         };
-        let team: Team = simple_team.try_into().unwrap();
+        let team = build_team(simple_team).unwrap();
 
         assert!(
             // Name set.
@@ -146,4 +185,81 @@ mod tests {
             team.nth(1).is_none()
         )
     }
+
+    #[test]
+    fn test_build_team_invalid_names() {
+        let team = |pet: SimplePet| SimpleTeam {
+            name: "The Super Auto Pets".to_owned(),
+            pets: vec![Some(pet)],
+        };
+        let unknown_pet = SimplePet {
+            name: "Antt".to_owned(),
+            ..Default::default()
+        };
+        let unknown_item = SimplePet {
+            name: "Ant".to_owned(),
+            item: Some("Honeyy".to_owned()),
+            ..Default::default()
+        };
+        let empty_slot = SimplePet {
+            name: EMPTY_SLOT_NAME.to_owned(),
+            ..Default::default()
+        };
+
+        // Unknown names aren't dropped silently.
+        assert!(build_team(team(unknown_pet)).is_err());
+        assert!(build_team(team(unknown_item)).is_err());
+        assert!(build_team(team(empty_slot)).is_ok_and(|team| team.all().is_empty()));
+    }
+
+    #[test]
+    fn test_build_temp_stats() {
+        let buffed_ant = SimplePet {
+            name: "Ant".to_owned(),
+            attack: Some(3),
+            temp_attack: Some(2),
+            temp_health: Some(50),
+            ..Default::default()
+        };
+        let ant = build_pet(buffed_ant).unwrap();
+
+        // Given or base stats are kept.
+        assert_eq!(ant.stats.attack, 3);
+        assert_eq!(ant.stats.health, 1);
+        // Temporary stats are added at the start of battle.
+        let temp_effect = ant.effect.last().unwrap();
+        assert_eq!(
+            temp_effect.action,
+            Action::Add(StatChangeType::StaticValue(Statistics {
+                attack: 2,
+                health: MAX_PET_STATS
+            }))
+        );
+        assert_eq!(temp_effect.uses, Some(1));
+        assert!(temp_effect.temp);
+    }
+
+    #[test]
+    fn test_rebuild_pet() {
+        let mut cricket = build_pet(SimplePet {
+            name: "Cricket".to_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+        let def_effect = cricket.effect.clone();
+        cricket.seed = Some(7);
+        cricket.stats.attack = MAX_PET_STATS + 10;
+        cricket.stats.health = MIN_PET_STATS - 10;
+        cricket.effect.push(def_effect[0].clone());
+        for effect in cricket.effect.iter_mut() {
+            effect.uses = Some(100);
+        }
+
+        let rebuilt = rebuild_pet(&cricket).unwrap();
+        assert_eq!(rebuilt.stats.attack, MAX_PET_STATS);
+        assert_eq!(rebuilt.stats.health, MIN_PET_STATS);
+        assert_eq!(rebuilt.seed, Some(7));
+        // Effects can't be added and uses can't go above the definition's.
+        assert_eq!(rebuilt.effect, def_effect);
+    }
 }
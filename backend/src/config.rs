@@ -0,0 +1,509 @@
+use once_cell::sync::OnceCell;
+use saptest::{Entity, SAPQuery, SAPDB};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, path::PathBuf, time::Duration};
+use toml::{Table, Value};
+
+use crate::{
+    args::Args,
+    limits::{Limits, Quota},
+    logging::{parse_log_filter, LogFormat, LogOutput, LogRotation},
+};
+
+/// Prefix of environment variables overriding config values.
+/// * Sections and keys are separated by `__`. ex. `SAPTESTER_SERVER__PORT=8080`
+const ENV_PREFIX: &str = "SAPTESTER_";
+const ENV_SEPARATOR: &str = "__";
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// The HTTP listening address.
+    pub addr: String,
+    /// The HTTP listening port.
+    pub port: u16,
+    /// Origins allowed to make cross-origin requests. Any origin if empty.
+    pub allowed_origins: Vec<String>,
+    /// Get client addresses from the `Fly-Client-IP` and `X-Forwarded-For` headers.
+    pub trust_proxy: bool,
+    /// Seconds in-flight requests are given to finish on shutdown.
+    pub shutdown_timeout_secs: u64,
+    /// Serve HTTPS with these certificates. HTTP if not set.
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            addr: String::from("0.0.0.0"),
+            port: 3030,
+            allowed_origins: vec![],
+            trust_proxy: false,
+            shutdown_timeout_secs: 30,
+            tls: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM encoded certificate chain.
+    pub cert: PathBuf,
+    /// PEM encoded private key.
+    pub key: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BattleConfig {
+    /// Turns fought before a battle is stopped.
+    pub max_turns: usize,
+    /// Pets per team.
+    pub team_size: usize,
+    /// Threads battles are simulated on. One per core if 0.
+    pub workers: usize,
+    /// Battles a single batch request can run.
+    pub max_batch_runs: usize,
+    /// Branches an analysis request explores before sampling outcomes instead.
+    pub max_analysis_branches: usize,
+}
+
+impl Default for BattleConfig {
+    fn default() -> Self {
+        Self {
+            max_turns: 250,
+            team_size: 5,
+            workers: 0,
+            max_batch_runs: 1000,
+            max_analysis_branches: 256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Battle requests kept for later comparison.
+    pub stored_battles: usize,
+    /// Database query results kept.
+    pub queries: usize,
+    /// How often the database is checked for changes in seconds.
+    pub db_version_ttl_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            stored_battles: 256,
+            queries: 512,
+            db_version_ttl_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Directory with the `saptest` config, `.saptest.toml`, and database.
+    /// Defaults to the working directory. See [`Config::load_database`].
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetConfig {
+    /// The directory cached images are stored in.
+    pub dir: PathBuf,
+    /// Download images not cached yet on startup.
+    pub prefetch: bool,
+}
+
+impl Default for AssetConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("assets"),
+            prefetch: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontendConfig {
+    /// Directory with the built frontend. Embedded files are served if not set.
+    pub dir: Option<PathBuf>,
+    /// Backend url the frontend sends requests to. The frontend's origin if empty.
+    pub api_url: String,
+}
+
+/// Route groups to serve.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RouteConfig {
+    pub db: bool,
+    pub battle: bool,
+    pub convert: bool,
+    pub assets: bool,
+    pub health: bool,
+    /// Only served if a frontend directory is set or the frontend is embedded.
+    pub frontend: bool,
+}
+
+impl Default for RouteConfig {
+    fn default() -> Self {
+        Self {
+            db: true,
+            battle: true,
+            convert: true,
+            assets: true,
+            health: true,
+            frontend: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// The logging level. Accepts filter directives. ex. `info,backend=debug`
+    pub level: String,
+    pub format: LogFormat,
+    pub output: LogOutput,
+    /// The log file. Rotated files are suffixed with their date.
+    pub file: PathBuf,
+    pub rotation: LogRotation,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: String::from("info"),
+            format: LogFormat::Text,
+            output: LogOutput::File,
+            file: PathBuf::from("backend.log"),
+            rotation: LogRotation::Daily,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub limits: Limits,
+    pub battle: BattleConfig,
+    pub cache: CacheConfig,
+    pub database: DatabaseConfig,
+    pub assets: AssetConfig,
+    pub frontend: FrontendConfig,
+    pub routes: RouteConfig,
+    pub logging: LogConfig,
+}
+
+/// Merge the tables of `other` into `base`. Values in `other` take precedence.
+fn merge_tables(base: &mut Table, other: Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(other_table)) => {
+                merge_tables(base_table, other_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Parse an environment variable as a TOML value. Unquoted strings are allowed.
+fn parse_env_value(raw: &str) -> Value {
+    format!("value = {raw}")
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_owned()))
+}
+
+/// Add the path of every section and value in `table` to `keys`. ex. `["server", "port"]`
+fn add_key_paths(keys: &mut Vec<Vec<String>>, prefix: &[String], table: &Table) {
+    for (key, value) in table {
+        let mut path = prefix.to_vec();
+        path.push(key.clone());
+        if let Value::Table(section) = value {
+            add_key_paths(keys, &path, section);
+        }
+        keys.push(path);
+    }
+}
+
+/// Path of every section and value of the config.
+fn config_key_paths() -> Vec<Vec<String>> {
+    // Optional values are only serialized if set.
+    let mut config = Config::default();
+    config.server.tls = Some(TlsConfig {
+        cert: PathBuf::new(),
+        key: PathBuf::new(),
+    });
+    config.database.dir = Some(PathBuf::new());
+    config.frontend.dir = Some(PathBuf::new());
+
+    let mut keys = vec![];
+    if let Ok(table) = Table::try_from(config) {
+        add_key_paths(&mut keys, &[], &table);
+    }
+    keys
+}
+
+/// Set config values from `SAPTESTER_<SECTION>__<KEY>` environment variables.
+/// * Variables not matching a config key are skipped and returned.
+fn apply_env_vars<I>(config: &mut Table, vars: I) -> Vec<String>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let known_keys = config_key_paths();
+    let mut unknown_vars = vec![];
+    for (var, raw) in vars {
+        let Some(path) = var.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let keys: Vec<String> = path
+            .to_lowercase()
+            .split(ENV_SEPARATOR)
+            .map(str::to_owned)
+            .collect();
+        if !known_keys.contains(&keys) {
+            unknown_vars.push(var);
+            continue;
+        }
+        let Some((last_key, sections)) = keys.split_last() else {
+            continue;
+        };
+
+        let mut var_table = Table::from_iter([(last_key.clone(), parse_env_value(&raw))]);
+        for section in sections.iter().rev() {
+            var_table = Table::from_iter([(section.clone(), Value::Table(var_table))]);
+        }
+        // Invalid values are still errors when the config is loaded.
+        // Environment variables replace non-table values with a section.
+        merge_tables(config, var_table);
+    }
+    unknown_vars
+}
+
+impl Config {
+    /// Merge the defaults, config file, environment variables, and arguments in that order.
+    pub fn load(args: &Args) -> Result<Config, Box<dyn Error>> {
+        let mut config = Table::try_from(Config::default())?;
+        if let Some(path) = &args.config {
+            let file_config = std::fs::read_to_string(path)
+                .map_err(|err| format!("Unable to read {}: {err}", path.display()))?
+                .parse::<Table>()?;
+            merge_tables(&mut config, file_config);
+        }
+        for var in apply_env_vars(&mut config, std::env::vars()) {
+            // Logging isn't set up until the config is loaded.
+            eprintln!("Ignoring unknown config variable {var}.");
+        }
+
+        let mut config: Config = Value::Table(config).try_into()?;
+        config.apply_args(args);
+        parse_log_filter(&config.logging.level)?;
+
+        Ok(config)
+    }
+
+    fn apply_args(&mut self, args: &Args) {
+        let quota = |quota: &mut Quota, per_minute: Option<u32>, burst: Option<u32>| {
+            quota.per_minute = per_minute.unwrap_or(quota.per_minute);
+            quota.burst = burst.unwrap_or(quota.burst);
+        };
+
+        if let Some(addr) = &args.addr {
+            self.server.addr = addr.clone()
+        }
+        self.server.port = args.port.unwrap_or(self.server.port);
+        if !args.allow_origin.is_empty() {
+            self.server.allowed_origins = args.allow_origin.clone()
+        }
+        self.server.trust_proxy |= args.trust_proxy;
+        self.server.shutdown_timeout_secs = args
+            .shutdown_timeout
+            .unwrap_or(self.server.shutdown_timeout_secs);
+        if let (Some(cert), Some(key)) = (&args.tls_cert, &args.tls_key) {
+            self.server.tls = Some(TlsConfig {
+                cert: cert.clone(),
+                key: key.clone(),
+            })
+        }
+
+        quota(&mut self.limits.db, args.db_rate, args.db_burst);
+        quota(&mut self.limits.battle, args.battle_rate, args.battle_burst);
+        self.limits.max_body_size = args.max_body_size.unwrap_or(self.limits.max_body_size);
+        self.limits.timeout_secs = args.timeout.unwrap_or(self.limits.timeout_secs);
+
+        self.battle.workers = args.battle_workers.unwrap_or(self.battle.workers);
+
+        if let Some(assets_dir) = &args.assets_dir {
+            self.assets.dir = assets_dir.clone()
+        }
+
+        if let Some(frontend_dir) = &args.frontend_dir {
+            self.frontend.dir = Some(frontend_dir.clone())
+        }
+
+        if let Some(log_level) = &args.log_level {
+            self.logging.level = log_level.clone()
+        }
+        self.logging.format = args.log_format.unwrap_or(self.logging.format);
+        self.logging.output = args.log_output.unwrap_or(self.logging.output);
+        if let Some(log_file) = &args.log_file {
+            self.logging.file = log_file.clone()
+        }
+        self.logging.rotation = args.log_rotation.unwrap_or(self.logging.rotation);
+    }
+
+    /// Load the `saptest` config and database from the database directory.
+    /// * `saptest` finds `.saptest.toml` and opens its database from the working directory the first time it's used.
+    ///   The directory is only entered until then so other relative paths are unaffected.
+    /// * The working directory is shared by every thread. Must be called before any other threads are started.
+    pub fn load_database(&self) -> Result<(), Box<dyn Error>> {
+        let Some(db_dir) = &self.database.dir else {
+            return Ok(());
+        };
+        let db_dir = db_dir
+            .canonicalize()
+            .map_err(|err| format!("Unable to find {}: {err}", db_dir.display()))?;
+        let cwd = std::env::current_dir()?;
+        std::env::set_current_dir(&db_dir)
+            .map_err(|err| format!("Unable to enter {}: {err}", db_dir.display()))?;
+        let mut query = SAPQuery::from_iter([("lvl".to_owned(), vec!["1".to_owned()])]);
+        query.set_table(Entity::Pet);
+        let loaded = SAPDB.execute_query(query);
+        std::env::set_current_dir(cwd)?;
+
+        loaded.map(|_| ()).map_err(|err| err.into())
+    }
+
+    /// The merged configuration as TOML.
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(self)
+    }
+}
+
+/// Set the server config. Can only be set once.
+pub fn set_config(config: Config) {
+    if CONFIG.set(config).is_err() {
+        log::warn!("Config already set.")
+    }
+}
+
+/// Server config. Defaults are used if never set.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_round_trip() {
+        let config_toml = Config::default().to_toml().unwrap();
+        let config: Config = toml::from_str(&config_toml).unwrap();
+
+        assert_eq!(config.to_toml().unwrap(), config_toml);
+    }
+
+    #[test]
+    fn test_config_key_paths() {
+        let keys = config_key_paths();
+        let path = |path: &[&str]| path.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+
+        assert!(keys.contains(&path(&["server"])));
+        assert!(keys.contains(&path(&["limits", "battle", "burst"])));
+        // Optional values are known even though they're unset by default.
+        assert!(keys.contains(&path(&["server", "tls", "cert"])));
+        assert!(keys.contains(&path(&["database", "dir"])));
+        assert!(!keys.contains(&path(&["server", "port", "number"])));
+    }
+
+    #[test]
+    fn test_merge_file_and_env() {
+        let mut config = Table::try_from(Config::default()).unwrap();
+        let file_config: Table = r#"
+            [server]
+            port = 8080
+
+            [battle]
+            max_turns = 100
+        "#
+        .parse()
+        .unwrap();
+        merge_tables(&mut config, file_config);
+        let unknown_vars = apply_env_vars(
+            &mut config,
+            [
+                ("SAPTESTER_SERVER__PORT", "9090"),
+                (
+                    "SAPTESTER_SERVER__ALLOWED_ORIGINS",
+                    r#"["https://example.com"]"#,
+                ),
+                ("SAPTESTER_ASSETS__DIR", "/tmp/assets"),
+                ("SAPTESTER_SERVER__TLS__CERT", "cert.pem"),
+                ("SAPTESTER_SERVER__TLS__KEY", "key.pem"),
+                ("SAPTESTER_SERVER__PROT", "8080"),
+                ("SAPTESTER_UNKNOWN", "1"),
+                ("OTHER_VAR", "1"),
+            ]
+            .map(|(var, value)| (var.to_owned(), value.to_owned())),
+        );
+        let config: Config = Value::Table(config).try_into().unwrap();
+
+        // Unknown keys are skipped instead of failing.
+        assert_eq!(
+            unknown_vars,
+            vec!["SAPTESTER_SERVER__PROT", "SAPTESTER_UNKNOWN"]
+        );
+        assert_eq!(
+            config.server.tls.map(|tls| tls.cert),
+            Some(PathBuf::from("cert.pem"))
+        );
+
+        // Environment variables override the file.
+        assert_eq!(config.server.port, 9090);
+        assert_eq!(config.server.allowed_origins, vec!["https://example.com"]);
+        assert_eq!(config.assets.dir, PathBuf::from("/tmp/assets"));
+        // File overrides defaults. Other values are kept.
+        assert_eq!(config.battle.max_turns, 100);
+        assert_eq!(config.battle.team_size, 5);
+    }
+
+    #[test]
+    fn test_tls_args() {
+        use clap::Parser;
+
+        let args = Args::parse_from(["backend", "--tls-cert", "cert.pem", "--tls-key", "key.pem"]);
+        let mut config = Config::default();
+        config.apply_args(&args);
+        let tls = config.server.tls.unwrap();
+        assert_eq!(tls.cert, PathBuf::from("cert.pem"));
+        assert_eq!(tls.key, PathBuf::from("key.pem"));
+
+        // Certificate and key are required together.
+        assert!(Args::try_parse_from(["backend", "--tls-cert", "cert.pem"]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let config: Result<Config, _> = toml::from_str("[server]\nprot = 8080");
+        assert!(config.is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::{error::Error, io::Read, path::Path};
+
+use crate::{args::Command, battle::team::SimpleTeam};
+
+use self::sapai::{from_sapai_team, to_sapai_team, SapaiTeam};
+
+pub mod response;
+pub mod sapai;
+
+/// Name of teams converted from formats without team names.
+pub const DEFAULT_TEAM_NAME: &str = "Team";
+
+/// Format of a team to convert from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamFormat {
+    /// sapai's `Team.state`.
+    Sapai,
+    /// A `SimpleTeam` as accepted by `/battle`.
+    Saptester,
+}
+
+/// Convert a team to the other format.
+/// * `name` is only used for formats without team names.
+pub fn convert_team(from: TeamFormat, team: Value, name: Option<&str>) -> Result<Value, String> {
+    let converted = match from {
+        TeamFormat::Sapai => {
+            let team: SapaiTeam =
+                serde_json::from_value(team).map_err(|err| format!("Invalid sapai team: {err}"))?;
+            let team = from_sapai_team(team, name.unwrap_or(DEFAULT_TEAM_NAME))?;
+            serde_json::to_value(team)
+        }
+        TeamFormat::Saptester => {
+            let team: SimpleTeam =
+                serde_json::from_value(team).map_err(|err| format!("Invalid team: {err}"))?;
+            serde_json::to_value(to_sapai_team(&team)?)
+        }
+    };
+    converted.map_err(|err| err.to_string())
+}
+
+fn read_input(input: Option<&Path>) -> std::io::Result<String> {
+    match input {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+/// Run a conversion subcommand and return the converted team as pretty JSON.
+pub fn run_command(command: &Command) -> Result<String, Box<dyn Error>> {
+    let (from, input, name) = match command {
+        Command::FromSapai { input, name } => (TeamFormat::Sapai, input, Some(name.as_str())),
+        Command::ToSapai { input } => (TeamFormat::Saptester, input, None),
+    };
+    let team: Value = serde_json::from_str(&read_input(input.as_deref())?)?;
+    let converted = convert_team(from, team, name)?;
+    Ok(serde_json::to_string_pretty(&converted)?)
+}
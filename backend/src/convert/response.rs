@@ -0,0 +1,83 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{convert_team, TeamFormat};
+
+#[derive(Deserialize)]
+pub struct ConvertRequest {
+    pub from: TeamFormat,
+    pub team: Value,
+    /// Name of the converted team if the source format has none.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConvertResponse {
+    pub status: Option<String>,
+    pub team: Option<Value>,
+}
+
+pub async fn post_convert_sapai(Json(request): Json<ConvertRequest>) -> impl IntoResponse {
+    match convert_team(request.from, request.team, request.name.as_deref()) {
+        Ok(team) => (
+            StatusCode::OK,
+            Json(ConvertResponse {
+                status: Some(StatusCode::OK.to_string()),
+                team: Some(team),
+            }),
+        ),
+        Err(err_msg) => (
+            StatusCode::BAD_REQUEST,
+            Json(ConvertResponse {
+                status: Some(err_msg),
+                team: None,
+            }),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::post_json;
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_convert_both_directions() {
+        let team = json!({
+            "name": "Friend",
+            "pets": [{"name": "Dog", "attack": 3, "health": 4, "level": 1, "item": "Honey"}, null]
+        });
+        let (status, body) =
+            post_json("/convert/sapai", json!({"from": "saptester", "team": team})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["team"]["team"][0]["pet"]["name"], "pet-dog");
+        assert_eq!(body["team"]["team"][0]["pet"]["status"], "status-honey-bee");
+
+        let sapai_team = body["team"].clone();
+        let (status, body) = post_json(
+            "/convert/sapai",
+            json!({"from": "sapai", "team": sapai_team, "name": "Friend"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["team"]["pets"][0], team["pets"][0]);
+    }
+
+    #[tokio::test]
+    async fn test_convert_unknown_status() {
+        let sapai_team = json!({
+            "type": "Team",
+            "team": [{"type": "TeamSlot", "pet": {"name": "pet-dog", "status": "status-unknown"}}]
+        });
+        let (status, body) = post_json(
+            "/convert/sapai",
+            json!({"from": "sapai", "team": sapai_team}),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["team"].is_null());
+    }
+}
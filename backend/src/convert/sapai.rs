@@ -0,0 +1,307 @@
+use itertools::Itertools;
+use saptest::PetName;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    battle::team::{build_pet, SimplePet, SimpleTeam, EMPTY_SLOT_NAME},
+    config::config,
+};
+
+/// Prefix of sapai pet names. ex. `pet-zombie-cricket`
+const SAPAI_PET_PREFIX: &str = "pet-";
+/// Name of an empty sapai slot.
+const SAPAI_EMPTY_PET: &str = "pet-none";
+/// Status of a sapai pet without a held food.
+const SAPAI_EMPTY_STATUS: &str = "none";
+
+/// sapai statuses and the foods that give them.
+const SAPAI_STATUS_FOODS: [(&str, &str); 10] = [
+    ("status-honey-bee", "Honey"),
+    ("status-bone-attack", "Meat Bone"),
+    ("status-garlic-armor", "Garlic"),
+    ("status-splash-attack", "Chili"),
+    ("status-melon-armor", "Melon"),
+    ("status-extra-life", "Mushroom"),
+    ("status-steak-attack", "Steak"),
+    ("status-poison-attack", "Peanut"),
+    ("status-coconut-shield", "Coconut"),
+    ("status-weak", "Weak"),
+];
+
+/// A pet in the format of sapai's `Pet.state`.
+/// * Unknown fields are ignored.
+/// * `experience` is dropped on import as teams have no experience. Exported pets have none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SapaiPet {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub name: String,
+    pub attack: usize,
+    pub health: usize,
+    pub level: usize,
+    pub experience: usize,
+    pub status: String,
+}
+
+impl Default for SapaiPet {
+    fn default() -> Self {
+        Self {
+            kind: String::from("Pet"),
+            name: SAPAI_EMPTY_PET.to_owned(),
+            attack: 0,
+            health: 0,
+            level: 1,
+            experience: 0,
+            status: SAPAI_EMPTY_STATUS.to_owned(),
+        }
+    }
+}
+
+/// A slot in the format of sapai's `TeamSlot.state`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SapaiSlot {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub pet: SapaiPet,
+}
+
+impl Default for SapaiSlot {
+    fn default() -> Self {
+        Self {
+            kind: String::from("TeamSlot"),
+            pet: SapaiPet::default(),
+        }
+    }
+}
+
+/// A team in the format of sapai's `Team.state`. The first slot is the front of the team.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SapaiTeam {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub team: Vec<SapaiSlot>,
+}
+
+impl Default for SapaiTeam {
+    fn default() -> Self {
+        Self {
+            kind: String::from("Team"),
+            team: vec![],
+        }
+    }
+}
+
+/// Convert a sapai pet name to a `saptest` pet name. ex. `pet-zombie-cricket` -> `Zombie Cricket`
+/// * Returns an error if the name isn't a `saptest` pet.
+pub fn from_sapai_pet_name(name: &str) -> Result<String, String> {
+    let Some(stripped_name) = name
+        .strip_prefix(SAPAI_PET_PREFIX)
+        .filter(|name| !name.is_empty())
+    else {
+        return Err(format!("Invalid sapai pet name: {name}"));
+    };
+    let pet_name = stripped_name
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .join(" ");
+    PetName::from_str(&pet_name).map_err(|_| format!("Unknown sapai pet: {name}"))?;
+    Ok(pet_name)
+}
+
+/// Convert a `saptest` pet name to a sapai pet name. ex. `Zombie Cricket` -> `pet-zombie-cricket`
+pub fn to_sapai_pet_name(name: &str) -> String {
+    format!(
+        "{SAPAI_PET_PREFIX}{}",
+        name.split_whitespace().join("-").to_lowercase()
+    )
+}
+
+/// Food given by a sapai status. [`None`] if there's no status.
+pub fn from_sapai_status(status: &str) -> Result<Option<String>, String> {
+    if status.is_empty() || status == SAPAI_EMPTY_STATUS {
+        return Ok(None);
+    }
+    SAPAI_STATUS_FOODS
+        .iter()
+        .find(|(sapai_status, _)| *sapai_status == status)
+        .map(|(_, food)| Some((*food).to_owned()))
+        .ok_or_else(|| format!("Unknown sapai status: {status}"))
+}
+
+/// sapai status given by a held food.
+pub fn to_sapai_status(food: Option<&str>) -> Result<String, String> {
+    let Some(food) = food else {
+        return Ok(SAPAI_EMPTY_STATUS.to_owned());
+    };
+    SAPAI_STATUS_FOODS
+        .iter()
+        .find(|(_, status_food)| status_food.eq_ignore_ascii_case(food))
+        .map(|(status, _)| (*status).to_owned())
+        .ok_or_else(|| format!("No sapai status for item: {food}"))
+}
+
+impl TryFrom<SapaiPet> for Option<SimplePet> {
+    type Error = String;
+
+    fn try_from(pet: SapaiPet) -> Result<Self, Self::Error> {
+        if pet.name == SAPAI_EMPTY_PET {
+            return Ok(None);
+        }
+        Ok(Some(SimplePet {
+            name: from_sapai_pet_name(&pet.name)?,
+            attack: Some(pet.attack),
+            health: Some(pet.health),
+            level: Some(pet.level),
+            item: from_sapai_status(&pet.status)?,
+            ..Default::default()
+        }))
+    }
+}
+
+impl TryFrom<Option<&SimplePet>> for SapaiPet {
+    type Error = String;
+
+    fn try_from(pet: Option<&SimplePet>) -> Result<Self, Self::Error> {
+        let Some(pet) = pet.filter(|pet| pet.name != EMPTY_SLOT_NAME) else {
+            return Ok(SapaiPet::default());
+        };
+        // sapai needs explicit stats. Unset stats are the pet's base stats at its level.
+        let (attack, health) = match (pet.attack, pet.health) {
+            (Some(attack), Some(health)) => (attack, health),
+            _ => {
+                let stats = build_pet(pet.clone())
+                    .map_err(|err| format!("Invalid pet {}: {err}", pet.name))?
+                    .stats;
+                (
+                    pet.attack.unwrap_or(stats.attack.max(0) as usize),
+                    pet.health.unwrap_or(stats.health.max(0) as usize),
+                )
+            }
+        };
+        Ok(SapaiPet {
+            name: to_sapai_pet_name(&pet.name),
+            attack,
+            health,
+            level: pet.level.unwrap_or(1),
+            status: to_sapai_status(pet.item.as_deref())?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Convert a sapai team. sapai teams have no name so `name` is used.
+pub fn from_sapai_team(team: SapaiTeam, name: &str) -> Result<SimpleTeam, String> {
+    let mut pets = team
+        .team
+        .into_iter()
+        .map(|slot| Option::<SimplePet>::try_from(slot.pet))
+        .collect::<Result<Vec<Option<SimplePet>>, String>>()?;
+    // Trailing empty slots are implied.
+    while pets.last().is_some_and(Option::is_none) {
+        pets.pop();
+    }
+    Ok(SimpleTeam {
+        name: name.to_owned(),
+        pets,
+    })
+}
+
+/// Convert a team to a sapai team. Empty slots are added up to the team size.
+/// * Temporary stats aren't converted as sapai teams outside of a battle have none.
+pub fn to_sapai_team(team: &SimpleTeam) -> Result<SapaiTeam, String> {
+    let team_size = config().battle.team_size.max(team.pets.len());
+    let slots = team
+        .slots(team_size)?
+        .into_iter()
+        .pad_using(team_size, |_| None)
+        .map(|pet| {
+            SapaiPet::try_from(pet).map(|pet| SapaiSlot {
+                pet,
+                ..Default::default()
+            })
+        })
+        .collect::<Result<Vec<SapaiSlot>, String>>()?;
+
+    Ok(SapaiTeam {
+        team: slots,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pet_names() {
+        assert_eq!(from_sapai_pet_name("pet-ant").unwrap(), "Ant");
+        assert_eq!(
+            from_sapai_pet_name("pet-zombie-cricket").unwrap(),
+            "Zombie Cricket"
+        );
+        assert!(from_sapai_pet_name("ant").is_err());
+        // Names are checked against known pets.
+        assert!(from_sapai_pet_name("pet-not-a-pet").is_err());
+        assert_eq!(to_sapai_pet_name("Zombie Cricket"), "pet-zombie-cricket");
+    }
+
+    #[test]
+    fn test_statuses() {
+        assert_eq!(
+            from_sapai_status("status-bone-attack").unwrap(),
+            Some("Meat Bone".to_owned())
+        );
+        assert_eq!(from_sapai_status("none").unwrap(), None);
+        assert!(from_sapai_status("status-unknown").is_err());
+        assert_eq!(
+            to_sapai_status(Some("meat bone")).unwrap(),
+            "status-bone-attack"
+        );
+        assert!(to_sapai_status(Some("Apple")).is_err());
+    }
+
+    #[test]
+    fn test_team_round_trip() {
+        let sapai_team: SapaiTeam = serde_json::from_str(
+            r#"{
+                "type": "Team",
+                "team": [
+                    {"type": "TeamSlot", "pet": {"type": "Pet", "name": "pet-dog", "attack": 3, "health": 4, "level": 2, "experience": 1, "status": "status-honey-bee", "seed_state": null}},
+                    {"type": "TeamSlot", "pet": {"type": "Pet", "name": "pet-none"}},
+                    {"type": "TeamSlot", "pet": {"type": "Pet", "name": "pet-ant", "attack": 2, "health": 1, "level": 1, "status": "none"}},
+                    {"type": "TeamSlot", "pet": {"type": "Pet", "name": "pet-none"}}
+                ],
+                "battle": false
+            }"#,
+        )
+        .unwrap();
+        let team = from_sapai_team(sapai_team, "Friend").unwrap();
+
+        // Order and gaps are kept. Trailing empty slots are dropped.
+        assert_eq!(team.pets.len(), 3);
+        assert!(team.pets[1].is_none());
+        let dog = team.pets[0].as_ref().unwrap();
+        assert_eq!(dog.name, "Dog");
+        assert_eq!(dog.item.as_deref(), Some("Honey"));
+        assert_eq!(
+            (dog.attack, dog.health, dog.level),
+            (Some(3), Some(4), Some(2))
+        );
+
+        let exported = to_sapai_team(&team).unwrap();
+        assert_eq!(exported.team.len(), config().battle.team_size);
+        assert_eq!(exported.team[0].pet.name, "pet-dog");
+        assert_eq!(exported.team[0].pet.status, "status-honey-bee");
+        assert_eq!(exported.team[1].pet, SapaiPet::default());
+        assert_eq!(from_sapai_team(exported, "Friend").unwrap(), team);
+    }
+}
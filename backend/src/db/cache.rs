@@ -0,0 +1,210 @@
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use saptest::{Entity, SAPQuery, SAPDB};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use crate::config::config;
+
+static DB_VERSION: Lazy<Mutex<DbVersion>> = Lazy::new(|| {
+    let (hash, records) = compute_db_version();
+    Mutex::new(DbVersion {
+        hash,
+        modified: SystemTime::now(),
+        records,
+    })
+});
+/// Database query results.
+pub static QUERY_CACHE: Lazy<ResultCache> = Lazy::new(|| ResultCache::new(config().cache.queries));
+/// Autocomplete results. Kept apart so typing doesn't evict query results.
+pub static AUTOCOMPLETE_CACHE: Lazy<ResultCache> =
+    Lazy::new(|| ResultCache::new(AUTOCOMPLETE_CACHE_SIZE));
+
+/// Autocomplete prefixes kept.
+const AUTOCOMPLETE_CACHE_SIZE: usize = 256;
+
+/// Version of the database contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbVersion {
+    /// Hash of all records.
+    pub hash: u64,
+    /// When this version was first seen.
+    pub modified: SystemTime,
+    /// Records counted when the version was last checked.
+    pub records: RecordCounts,
+}
+
+/// Number of records of each entity. [`None`] if the table couldn't be queried.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RecordCounts {
+    pub pets: Option<usize>,
+    pub foods: Option<usize>,
+}
+
+/// JSON results for a single database version keyed by normalized query.
+/// * At most `capacity` results are kept. Oldest results are evicted first.
+pub struct ResultCache {
+    capacity: usize,
+    results: Mutex<VersionedResults>,
+}
+
+#[derive(Default)]
+struct VersionedResults {
+    version: u64,
+    results: IndexMap<String, Arc<String>>,
+}
+
+fn hash_str(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash and count all records.
+fn compute_db_version() -> (u64, RecordCounts) {
+    let mut hasher = DefaultHasher::new();
+    let [pets, foods] = [Entity::Pet, Entity::Food].map(|entity| {
+        let mut query = SAPQuery::from_iter(std::iter::empty::<(String, Vec<String>)>());
+        query.set_table(entity);
+        let records = SAPDB.execute_query(query).ok();
+        format!("{:?}", records.as_deref().unwrap_or_default()).hash(&mut hasher);
+        records.map(|records| records.len())
+    });
+    (hasher.finish(), RecordCounts { pets, foods })
+}
+
+/// Current version of the database. Kept up to date by [`watch_db_version`].
+pub fn db_version() -> DbVersion {
+    *DB_VERSION.lock().unwrap()
+}
+
+/// Store a newly computed database hash and record counts. Returns `true` if the database changed.
+fn update_db_version(version: &mut DbVersion, hash: u64, records: RecordCounts) -> bool {
+    let is_changed = version.hash != hash;
+    if is_changed {
+        version.hash = hash;
+        version.modified = SystemTime::now();
+    }
+    version.records = records;
+    is_changed
+}
+
+/// Recheck the database version every `cache.db_version_ttl_secs` so requests never hash the database.
+pub async fn watch_db_version() {
+    let ttl = Duration::from_secs(config().cache.db_version_ttl_secs.max(1));
+    let mut interval = tokio::time::interval(ttl);
+    loop {
+        // The first tick completes immediately and sets the initial version.
+        interval.tick().await;
+        match tokio::task::spawn_blocking(compute_db_version).await {
+            Ok((hash, records)) => {
+                if update_db_version(&mut DB_VERSION.lock().unwrap(), hash, records) {
+                    log::info!("Database changed. Clearing cached queries.");
+                }
+            }
+            Err(err) => log::error!("Unable to check database version: {err}"),
+        }
+    }
+}
+
+/// Entity tag of a query result. Changes with the database version.
+pub fn etag(version: &DbVersion, key: &str) -> String {
+    format!("\"{:016x}-{:016x}\"", version.hash, hash_str(key))
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            results: Mutex::default(),
+        }
+    }
+
+    /// Get the JSON result of a query from the cache, running `query` on a miss.
+    /// * Cached results are cleared when the database version changes.
+    /// * `query` runs without holding the cache lock so other requests aren't blocked.
+    pub fn get_or_query<E, F>(&self, key: &str, query: F) -> Result<(DbVersion, Arc<String>), E>
+    where
+        F: FnOnce() -> Result<String, E>,
+    {
+        let version = db_version();
+        {
+            let mut cache = self.results.lock().unwrap();
+            if cache.version != version.hash {
+                cache.results.clear();
+                cache.version = version.hash;
+            }
+            if let Some(result) = cache.results.get(key) {
+                return Ok((version, result.clone()));
+            }
+        }
+
+        let result = Arc::new(query()?);
+
+        let mut cache = self.results.lock().unwrap();
+        // Don't store results of an older version if the database changed while querying.
+        if cache.version == version.hash {
+            if !cache.results.contains_key(key) && cache.results.len() >= self.capacity {
+                cache.results.shift_remove_index(0);
+            }
+            cache.results.insert(key.to_owned(), result.clone());
+        }
+        Ok((version, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_query() {
+        let key = "test_cached_query";
+        let cache = ResultCache::new(1);
+        let (version, result) = cache
+            .get_or_query::<(), _>(key, || Ok("[1]".to_owned()))
+            .unwrap();
+        // Query isn't rerun on hit.
+        let (_, cached_result) = cache
+            .get_or_query::<(), _>(key, || panic!("Query should be cached."))
+            .unwrap();
+
+        assert_eq!(result, cached_result);
+        assert_eq!(etag(&version, key), etag(&version, key));
+        assert_ne!(etag(&version, key), etag(&version, "other_key"));
+
+        // Oldest result is evicted once full.
+        cache
+            .get_or_query::<(), _>("other_key", || Ok("[2]".to_owned()))
+            .unwrap();
+        let (_, requeried_result) = cache
+            .get_or_query::<(), _>(key, || Ok("[3]".to_owned()))
+            .unwrap();
+        assert_eq!(requeried_result.as_str(), "[3]");
+    }
+
+    #[test]
+    fn test_update_db_version() {
+        let mut version = DbVersion {
+            hash: 1,
+            modified: SystemTime::UNIX_EPOCH,
+            records: RecordCounts::default(),
+        };
+        let records = RecordCounts {
+            pets: Some(1),
+            foods: None,
+        };
+        // Same hash keeps when it was modified. Counts are always updated.
+        assert!(!update_db_version(&mut version, 1, records));
+        assert_eq!(version.modified, SystemTime::UNIX_EPOCH);
+        assert_eq!(version.records, records);
+
+        assert!(update_db_version(&mut version, 2, records));
+        assert_eq!(version.hash, 2);
+        assert!(version.modified > SystemTime::UNIX_EPOCH);
+    }
+}
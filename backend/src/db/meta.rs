@@ -0,0 +1,106 @@
+use itertools::Itertools;
+use saptest::{
+    db::record::{FoodRecord, PetRecord, SAPRecord},
+    error::SAPTestError,
+    pets::pet::{MAX_PET_LEVEL, MAX_PET_STATS, MIN_PET_LEVEL, MIN_PET_STATS},
+    Entity, SAPQuery, SAPDB,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::config;
+
+/// Inclusive range of allowed values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds<T> {
+    pub min: T,
+    pub max: T,
+}
+
+/// Values accepted by the database filters and battle requests.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetaResponse {
+    /// Pet names. Doesn't include the empty slot, `Slot`.
+    pub pets: Vec<String>,
+    pub foods: Vec<String>,
+    /// Foods pets can hold.
+    pub items: Vec<String>,
+    pub packs: Vec<String>,
+    pub effect_triggers: Vec<String>,
+    pub tiers: Bounds<usize>,
+    pub levels: Bounds<usize>,
+    pub stats: Bounds<isize>,
+    pub team_size: usize,
+}
+
+fn all_records(entity: Entity) -> Result<Vec<SAPRecord>, SAPTestError> {
+    let mut query = SAPQuery::from_iter(std::iter::empty::<(String, Vec<String>)>());
+    query.set_table(entity);
+    SAPDB.execute_query(query)
+}
+
+/// Collect valid names, packs, triggers, and bounds from every pet and food record.
+pub fn db_meta() -> Result<MetaResponse, SAPTestError> {
+    let pets = all_records(Entity::Pet)?
+        .into_iter()
+        .filter_map(|rec| PetRecord::try_from(rec).ok())
+        .collect_vec();
+    let foods = all_records(Entity::Food)?
+        .into_iter()
+        .filter_map(|rec| FoodRecord::try_from(rec).ok())
+        .collect_vec();
+    let (min_tier, max_tier) = pets
+        .iter()
+        .map(|pet| pet.tier)
+        .chain(foods.iter().map(|food| food.tier))
+        .minmax()
+        .into_option()
+        .unwrap_or_default();
+
+    Ok(MetaResponse {
+        pets: pets
+            .iter()
+            .map(|pet| pet.name.to_string())
+            .sorted()
+            .dedup()
+            .collect_vec(),
+        foods: foods
+            .iter()
+            .map(|food| food.name.to_string())
+            .sorted()
+            .dedup()
+            .collect_vec(),
+        items: foods
+            .iter()
+            .filter(|food| food.holdable)
+            .map(|food| food.name.to_string())
+            .sorted()
+            .dedup()
+            .collect_vec(),
+        packs: pets
+            .iter()
+            .map(|pet| pet.pack.to_string())
+            .chain(foods.iter().map(|food| food.pack.to_string()))
+            .sorted()
+            .dedup()
+            .collect_vec(),
+        effect_triggers: pets
+            .iter()
+            .filter_map(|pet| pet.effect_trigger.clone())
+            .sorted()
+            .dedup()
+            .collect_vec(),
+        tiers: Bounds {
+            min: min_tier,
+            max: max_tier,
+        },
+        levels: Bounds {
+            min: MIN_PET_LEVEL,
+            max: MAX_PET_LEVEL,
+        },
+        stats: Bounds {
+            min: MIN_PET_STATS,
+            max: MAX_PET_STATS,
+        },
+        team_size: config().battle.team_size,
+    })
+}
@@ -1 +1,4 @@
+pub mod cache;
+pub mod meta;
+pub mod resource;
 pub mod response;
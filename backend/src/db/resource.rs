@@ -0,0 +1,315 @@
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use saptest::{
+    db::record::{FoodRecord, PetRecord, SAPRecord},
+    error::SAPTestError,
+    Entity, SAPQuery, SAPDB,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use super::{cache::db_version, meta::db_meta};
+use crate::assets::cache::asset_path;
+
+/// Names returned by [`autocomplete`] at most.
+pub const MAX_AUTOCOMPLETE_RESULTS: usize = 10;
+
+static AUTOCOMPLETE_NAMES: Lazy<Mutex<Option<AutocompleteNames>>> = Lazy::new(Default::default);
+
+/// Every pet and food name sorted by name and the database version they were read from.
+struct AutocompleteNames {
+    version: u64,
+    names: Arc<Vec<AutocompleteEntry>>,
+}
+
+/// Stats and effect of a pet at a single level.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetLevel {
+    pub lvl: usize,
+    /// Packs the pet has this level in.
+    pub packs: Vec<String>,
+    pub tier: usize,
+    pub cost: usize,
+    pub attack: usize,
+    pub health: usize,
+    pub effect_trigger: Option<String>,
+    pub effect: Option<String>,
+    pub effect_atk: usize,
+    pub effect_health: usize,
+    pub n_triggers: usize,
+    pub temp_effect: bool,
+}
+
+impl PetLevel {
+    /// Same level with the same stats and effect ignoring packs.
+    fn is_same_level(&self, other: &PetLevel) -> bool {
+        PetLevel {
+            packs: vec![],
+            ..self.clone()
+        } == PetLevel {
+            packs: vec![],
+            ..other.clone()
+        }
+    }
+}
+
+/// A pet with every level grouped under it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetResource {
+    pub name: String,
+    pub tier: usize,
+    pub cost: usize,
+    /// Packs the pet is in.
+    pub packs: Vec<String>,
+    pub img_url: String,
+    pub is_token: bool,
+    pub levels: Vec<PetLevel>,
+}
+
+/// A food and the packs it's in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoodResource {
+    pub name: String,
+    pub tier: usize,
+    pub cost: usize,
+    pub packs: Vec<String>,
+    pub img_url: String,
+    pub effect: String,
+    pub effect_atk: isize,
+    pub effect_health: isize,
+    pub holdable: bool,
+    pub single_use: bool,
+    pub end_of_battle: bool,
+    pub random: bool,
+    pub n_targets: usize,
+    pub turn_effect: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameKind {
+    Pet,
+    Food,
+}
+
+/// A pet or food name starting with the requested prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutocompleteEntry {
+    pub name: String,
+    pub kind: NameKind,
+}
+
+fn records_named<T>(entity: Entity, name: &str) -> Result<Vec<T>, SAPTestError>
+where
+    T: TryFrom<SAPRecord>,
+{
+    let mut query = SAPQuery::from_iter([("name".to_owned(), vec![name.to_owned()])]);
+    query.set_table(entity);
+    Ok(SAPDB
+        .execute_query(query)?
+        .into_iter()
+        .filter_map(|rec| T::try_from(rec).ok())
+        .collect_vec())
+}
+
+/// Group the records of a single pet by level. [`None`] if there are no records.
+/// * Levels that are the same in every pack are merged. Otherwise, each pack gets its own entry.
+/// * `tier` and `cost` are the lowest of any pack.
+pub fn group_pet_records(records: Vec<PetRecord>) -> Option<PetResource> {
+    let first = records.first()?;
+    let mut pet = PetResource {
+        name: first.name.to_string(),
+        tier: records.iter().map(|rec| rec.tier).min()?,
+        cost: records.iter().map(|rec| rec.cost).min()?,
+        packs: records
+            .iter()
+            .map(|rec| rec.pack.to_string())
+            .sorted()
+            .dedup()
+            .collect_vec(),
+        img_url: asset_path(&first.img_url),
+        is_token: first.is_token,
+        levels: vec![],
+    };
+    for rec in records.into_iter().sorted_by_key(|rec| rec.lvl) {
+        let level = PetLevel {
+            lvl: rec.lvl,
+            packs: vec![rec.pack.to_string()],
+            tier: rec.tier,
+            cost: rec.cost,
+            attack: rec.attack,
+            health: rec.health,
+            effect_trigger: rec.effect_trigger,
+            effect: rec.effect,
+            effect_atk: rec.effect_atk,
+            effect_health: rec.effect_health,
+            n_triggers: rec.n_triggers,
+            temp_effect: rec.temp_effect,
+        };
+        match pet
+            .levels
+            .iter_mut()
+            .find(|other| other.is_same_level(&level))
+        {
+            Some(other) => other.packs.extend(level.packs),
+            None => pet.levels.push(level),
+        }
+    }
+    for level in pet.levels.iter_mut() {
+        level.packs.sort();
+        level.packs.dedup();
+    }
+    Some(pet)
+}
+
+/// Merge the records of a single food in different packs. [`None`] if there are no records.
+pub fn group_food_records(records: Vec<FoodRecord>) -> Option<FoodResource> {
+    let packs = records
+        .iter()
+        .map(|rec| rec.pack.to_string())
+        .sorted()
+        .dedup()
+        .collect_vec();
+    let food = records.into_iter().next()?;
+    Some(FoodResource {
+        name: food.name.to_string(),
+        tier: food.tier,
+        cost: food.cost,
+        packs,
+        img_url: asset_path(&food.img_url),
+        effect: food.effect,
+        effect_atk: food.effect_atk,
+        effect_health: food.effect_health,
+        holdable: food.holdable,
+        single_use: food.single_use,
+        end_of_battle: food.end_of_battle,
+        random: food.random,
+        n_targets: food.n_targets,
+        turn_effect: food.turn_effect,
+    })
+}
+
+pub fn pet_resource(name: &str) -> Result<Option<PetResource>, SAPTestError> {
+    records_named(Entity::Pet, name).map(group_pet_records)
+}
+
+pub fn food_resource(name: &str) -> Result<Option<FoodResource>, SAPTestError> {
+    records_named(Entity::Food, name).map(group_food_records)
+}
+
+/// Every pet and food name sorted by name. Read once per database version.
+fn autocomplete_names() -> Result<Arc<Vec<AutocompleteEntry>>, SAPTestError> {
+    let version = db_version().hash;
+    if let Some(cached) = AUTOCOMPLETE_NAMES.lock().unwrap().as_ref() {
+        if cached.version == version {
+            return Ok(cached.names.clone());
+        }
+    }
+
+    // Read without holding the lock so other requests aren't blocked.
+    let meta = db_meta()?;
+    let names = meta
+        .pets
+        .into_iter()
+        .map(|name| AutocompleteEntry {
+            name,
+            kind: NameKind::Pet,
+        })
+        .chain(meta.foods.into_iter().map(|name| AutocompleteEntry {
+            name,
+            kind: NameKind::Food,
+        }))
+        .sorted_by(|entry, other| entry.name.cmp(&other.name))
+        .collect_vec();
+    let names = Arc::new(names);
+    *AUTOCOMPLETE_NAMES.lock().unwrap() = Some(AutocompleteNames {
+        version,
+        names: names.clone(),
+    });
+    Ok(names)
+}
+
+/// Pet and food names starting with `prefix` ignoring case. At most [`MAX_AUTOCOMPLETE_RESULTS`] are returned.
+pub fn autocomplete(prefix: &str) -> Result<Vec<AutocompleteEntry>, SAPTestError> {
+    let prefix = prefix.to_lowercase();
+    Ok(autocomplete_names()?
+        .iter()
+        .filter(|entry| entry.name.to_lowercase().starts_with(&prefix))
+        .take(MAX_AUTOCOMPLETE_RESULTS)
+        .cloned()
+        .collect_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use saptest::{Pack, PetName};
+
+    use super::*;
+
+    fn ant(lvl: usize, pack: Pack) -> PetRecord {
+        PetRecord {
+            name: PetName::Ant,
+            tier: 1,
+            attack: 2 * lvl,
+            health: lvl,
+            pack,
+            effect_trigger: Some("Faint".to_owned()),
+            effect: Some(format!("Give a random friend +{}/+{}.", 2 * lvl, lvl)),
+            effect_atk: 2 * lvl,
+            effect_health: lvl,
+            n_triggers: 1,
+            temp_effect: false,
+            lvl,
+            cost: 3,
+            img_url: String::new(),
+            is_token: false,
+        }
+    }
+
+    #[test]
+    fn test_group_pet_records() {
+        let records = vec![
+            ant(2, Pack::Turtle),
+            ant(1, Pack::Turtle),
+            ant(1, Pack::Star),
+            ant(3, Pack::Turtle),
+        ];
+        let pet = group_pet_records(records).unwrap();
+
+        assert_eq!(pet.name, "Ant");
+        assert_eq!(pet.packs, vec!["Star".to_owned(), "Turtle".to_owned()]);
+        // One entry per level in order.
+        assert_eq!(
+            pet.levels.iter().map(|level| level.lvl).collect_vec(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(pet.levels[2].attack, 6);
+        // Identical levels are merged across packs.
+        assert_eq!(pet.levels[0].packs, pet.packs);
+        assert!(group_pet_records(vec![]).is_none());
+    }
+
+    #[test]
+    fn test_group_pet_records_differing_packs() {
+        let mut star_ant = ant(1, Pack::Star);
+        star_ant.tier = 2;
+        star_ant.attack = 3;
+        let pet = group_pet_records(vec![ant(1, Pack::Turtle), star_ant]).unwrap();
+
+        // Tier isn't taken from an arbitrary pack.
+        assert_eq!(pet.tier, 1);
+        // Each pack keeps its own level.
+        assert_eq!(pet.levels.len(), 2);
+        let star_level = pet
+            .levels
+            .iter()
+            .find(|level| level.packs == vec!["Star".to_owned()])
+            .unwrap();
+        assert_eq!((star_level.tier, star_level.attack), (2, 3));
+        let turtle_level = pet
+            .levels
+            .iter()
+            .find(|level| level.packs == vec!["Turtle".to_owned()])
+            .unwrap();
+        assert_eq!((turtle_level.tier, turtle_level.attack), (1, 2));
+    }
+}
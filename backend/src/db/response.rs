@@ -1,10 +1,45 @@
-use axum::{extract::Query, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, Query},
+    headers::{ETag, IfModifiedSince, IfNoneMatch, LastModified},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json, TypedHeader,
+};
 use itertools::Itertools;
 use saptest::{
     db::record::{FoodRecord, PetRecord},
+    error::SAPTestError,
     Entity, SAPQuery, SAPDB,
 };
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    time::{Instant, UNIX_EPOCH},
+};
+
+use super::{
+    cache::{db_version, etag, ResultCache, AUTOCOMPLETE_CACHE, QUERY_CACHE},
+    meta::db_meta,
+    resource::{autocomplete, food_resource, pet_resource},
+};
+use crate::{assets::cache::asset_path, health::metrics::record_db_query};
+
+/// Version of the database contents. Changes whenever any record changes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionResponse {
+    /// Hash of all records in hex.
+    pub version: String,
+    /// When the records last changed in seconds since the Unix epoch.
+    pub updated: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutocompleteParams {
+    pub prefix: String,
+}
 
 #[derive(Debug)]
 struct APIQuery {
@@ -12,6 +47,19 @@ struct APIQuery {
     params: HashMap<String, String>,
 }
 
+impl APIQuery {
+    /// Key identifying the query regardless of parameter order.
+    fn cache_key(&self) -> String {
+        let params = self
+            .params
+            .iter()
+            .sorted()
+            .map(|(param, value)| format!("{param}={value}"))
+            .join("&");
+        format!("{:?}?{params}", self.qtype)
+    }
+}
+
 impl From<APIQuery> for SAPQuery {
     fn from(query: APIQuery) -> Self {
         let mut db_query = SAPQuery::from_iter(
@@ -25,62 +73,367 @@ impl From<APIQuery> for SAPQuery {
     }
 }
 
-pub async fn get_pet(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+/// Serialize a response body. `500 Internal Server Error` if it can't be serialized so an empty body is never cached.
+fn serialize_json<T: Serialize>(value: &T) -> Result<String, StatusCode> {
+    serde_json::to_string(value).map_err(|err| {
+        log::error!("Unable to serialize response: {err}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Respond with cached query results.
+/// * Responds with `304 Not Modified` if the client's copy is still valid.
+fn cached_response<F>(
+    query: APIQuery,
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    to_json: F,
+) -> Response
+where
+    F: FnOnce(SAPQuery) -> Result<String, StatusCode>,
+{
+    let key = query.cache_key();
+    let table = format!("{:?}", query.qtype);
+    let preconditions = (if_none_match, if_modified_since);
+    cached_json_response(
+        &QUERY_CACHE,
+        &key,
+        &table,
+        StatusCode::FOUND,
+        preconditions,
+        || to_json(query.into()),
+    )
+}
+
+/// Conditional request headers. See [`cached_json_response`].
+type Preconditions = (
+    Option<TypedHeader<IfNoneMatch>>,
+    Option<TypedHeader<IfModifiedSince>>,
+);
+
+/// Respond with the JSON stored under `key` in `cache`, running `to_json` on a miss.
+/// * Fresh results are sent with `status`. Errors from `to_json` are sent as is and not cached.
+fn cached_json_response<F>(
+    cache: &ResultCache,
+    key: &str,
+    table: &str,
+    status: StatusCode,
+    (if_none_match, if_modified_since): Preconditions,
+    to_json: F,
+) -> Response
+where
+    F: FnOnce() -> Result<String, StatusCode>,
+{
+    let (version, body) = match cache.get_or_query(key, || {
+        let _span = tracing::info_span!("db_query", table, query = key).entered();
+        let start = Instant::now();
+        let res = to_json();
+        record_db_query(table, start.elapsed());
+        res
+    }) {
+        Ok(res) => res,
+        Err(status) => return status.into_response(),
+    };
+
+    let etag: ETag = etag(&version, key).parse().unwrap();
+    let last_modified = LastModified::from(version.modified);
+    // If-None-Match takes precedence over If-Modified-Since.
+    let is_modified = match (if_none_match, if_modified_since) {
+        (Some(TypedHeader(if_none_match)), _) => if_none_match.precondition_passes(&etag),
+        (None, Some(TypedHeader(if_modified_since))) => {
+            if_modified_since.is_modified(version.modified)
+        }
+        (None, None) => true,
+    };
+    let headers = (
+        TypedHeader(etag),
+        TypedHeader(last_modified),
+        // Always revalidate so clients see database changes.
+        [(CACHE_CONTROL, "no-cache")],
+    );
+
+    if is_modified {
+        (
+            status,
+            headers,
+            [(CONTENT_TYPE, "application/json")],
+            body.to_string(),
+        )
+            .into_response()
+    } else {
+        (StatusCode::NOT_MODIFIED, headers).into_response()
+    }
+}
+
+pub async fn get_db_version() -> impl IntoResponse {
+    let version = db_version();
+    let updated = version
+        .modified
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+
+    (
+        TypedHeader(LastModified::from(version.modified)),
+        [(CACHE_CONTROL, "no-cache")],
+        Json(VersionResponse {
+            version: format!("{:016x}", version.hash),
+            updated,
+        }),
+    )
+}
+
+pub async fn get_db_meta(
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+) -> impl IntoResponse {
+    let preconditions = (if_none_match, if_modified_since);
+    cached_json_response(
+        &QUERY_CACHE,
+        "Meta",
+        "Meta",
+        StatusCode::OK,
+        preconditions,
+        || {
+            db_meta()
+                .map_err(|_| StatusCode::BAD_REQUEST)
+                .and_then(|meta| serialize_json(&meta))
+        },
+    )
+}
+
+/// Serialize a single pet or food. `404 Not Found` if there isn't one.
+fn resource_json<T: Serialize>(
+    resource: Result<Option<T>, SAPTestError>,
+) -> Result<String, StatusCode> {
+    match resource {
+        Ok(Some(resource)) => serialize_json(&resource),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+pub async fn get_pet_by_name(
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let key = format!("Pet/{name}");
+    let preconditions = (if_none_match, if_modified_since);
+    cached_json_response(
+        &QUERY_CACHE,
+        &key,
+        "Pet",
+        StatusCode::OK,
+        preconditions,
+        || resource_json(pet_resource(&name)),
+    )
+}
+
+pub async fn get_food_by_name(
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let key = format!("Food/{name}");
+    let preconditions = (if_none_match, if_modified_since);
+    cached_json_response(
+        &QUERY_CACHE,
+        &key,
+        "Food",
+        StatusCode::OK,
+        preconditions,
+        || resource_json(food_resource(&name)),
+    )
+}
+
+pub async fn get_autocomplete(
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    Query(params): Query<AutocompleteParams>,
+) -> impl IntoResponse {
+    let key = format!("Autocomplete?prefix={}", params.prefix.to_lowercase());
+    let preconditions = (if_none_match, if_modified_since);
+    cached_json_response(
+        &AUTOCOMPLETE_CACHE,
+        &key,
+        "Autocomplete",
+        StatusCode::OK,
+        preconditions,
+        || {
+            autocomplete(&params.prefix)
+                .map_err(|_| StatusCode::BAD_REQUEST)
+                .and_then(|names| serialize_json(&names))
+        },
+    )
+}
+
+pub async fn get_pet(
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
     let query = APIQuery {
         qtype: Entity::Pet,
         params,
-    }
-    .into();
-
-    SAPDB
-        .execute_query(query)
-        .map(|records| {
-            (
-                StatusCode::FOUND,
-                Json(
-                    records
-                        .into_iter()
-                        .filter_map(|rec| PetRecord::try_from(rec).ok())
-                        .collect_vec(),
-                ),
-            )
-        })
-        .map_err(|_| StatusCode::BAD_REQUEST)
+    };
+
+    cached_response(query, if_none_match, if_modified_since, |query| {
+        let records = SAPDB
+            .execute_query(query)
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let pets = records
+            .into_iter()
+            .filter_map(|rec| PetRecord::try_from(rec).ok())
+            // Serve local copy of image.
+            .map(|mut pet| {
+                pet.img_url = asset_path(&pet.img_url);
+                pet
+            })
+            .collect_vec();
+        serialize_json(&pets)
+    })
 }
 
-pub async fn get_food(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+pub async fn get_food(
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
     let query = APIQuery {
         qtype: Entity::Food,
         params,
-    }
-    .into();
-
-    SAPDB
-        .execute_query(query)
-        .map(|records| {
-            (
-                StatusCode::FOUND,
-                Json(
-                    records
-                        .into_iter()
-                        .filter_map(|rec| FoodRecord::try_from(rec).ok())
-                        .collect_vec(),
-                ),
-            )
-        })
-        .map_err(|_| StatusCode::BAD_REQUEST)
+    };
+
+    cached_response(query, if_none_match, if_modified_since, |query| {
+        let records = SAPDB
+            .execute_query(query)
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let foods = records
+            .into_iter()
+            .filter_map(|rec| FoodRecord::try_from(rec).ok())
+            // Serve local copy of image.
+            .map(|mut food| {
+                food.img_url = asset_path(&food.img_url);
+                food
+            })
+            .collect_vec();
+        serialize_json(&foods)
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::app;
+    use crate::{app, test_utils::get_json};
     use axum::{
         body::Body,
-        http::{Request, StatusCode},
+        http::{
+            header::{ETAG, IF_NONE_MATCH},
+            Request, StatusCode,
+        },
     };
     use serde_json::Value;
+    use std::collections::HashMap;
     use tower::ServiceExt;
 
+    use super::{super::meta::MetaResponse, APIQuery, VersionResponse};
+    use saptest::Entity;
+
+    #[tokio::test]
+    async fn test_get_db_version() {
+        let app = app();
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/db/version")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let version: VersionResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(version.version.len(), 16);
+        assert!(version.updated > 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_db_meta() {
+        let resp = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/db/meta")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let meta: MetaResponse = serde_json::from_slice(&body).unwrap();
+
+        assert!(meta.pets.contains(&"Ant".to_owned()));
+        assert!(meta.items.contains(&"Honey".to_owned()));
+        assert!(meta.packs.contains(&"Turtle".to_owned()));
+        assert!(meta.effect_triggers.contains(&"Faint".to_owned()));
+        assert_eq!((meta.tiers.min, meta.tiers.max), (1, 6));
+        assert_eq!((meta.levels.min, meta.levels.max), (1, 3));
+    }
+
+    #[tokio::test]
+    async fn test_get_pet_by_name() {
+        let (status, pet) = get_json("/db/pets/Ant").await;
+        assert_eq!(status, StatusCode::OK);
+
+        // One object with every level.
+        let levels = pet
+            .get("levels")
+            .and_then(|levels| levels.as_array())
+            .unwrap();
+        assert_eq!(levels.len(), 3);
+        assert_eq!(pet.get("name").and_then(|name| name.as_str()), Some("Ant"));
+        assert!(pet
+            .get("packs")
+            .and_then(|packs| packs.as_array())
+            .is_some_and(|packs| packs.contains(&Value::from("Turtle"))));
+
+        let (status, _) = get_json("/db/pets/NotAPet").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_food_by_name() {
+        let (status, food) = get_json("/db/foods/Apple").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            food.get("name").and_then(|name| name.as_str()),
+            Some("Apple")
+        );
+
+        let (status, _) = get_json("/db/foods/NotAFood").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_autocomplete() {
+        let (status, names) = get_json("/db/autocomplete?prefix=an").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let names = names.as_array().unwrap();
+        assert!(!names.is_empty());
+        // Case is ignored.
+        assert!(names.iter().all(|entry| entry
+            .get("name")
+            .and_then(|name| name.as_str())
+            .is_some_and(|name| name.to_lowercase().starts_with("an"))));
+
+        // Prefix is required.
+        let (status, _) = get_json("/db/autocomplete").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn test_get_food_w_params() {
         let app = app();
@@ -105,6 +458,10 @@ mod tests {
         let food_name = foods[0].get("name").and_then(|name| name.as_str());
         let food_pack = foods[0].get("pack").and_then(|name| name.as_str());
         assert!(food_name == Some("Apple") && food_pack == Some("Turtle"));
+
+        // Images are served by the backend.
+        let food_img_url = foods[0].get("img_url").and_then(|url| url.as_str());
+        assert!(food_img_url.is_some_and(|url| url.starts_with("/assets/img/")));
     }
 
     #[tokio::test]
@@ -162,4 +519,44 @@ mod tests {
 
         assert_eq!(resp.status(), StatusCode::FOUND);
     }
+
+    #[test]
+    fn test_cache_key_ignores_param_order() {
+        let query = |params: [(&str, &str); 2]| APIQuery {
+            qtype: Entity::Pet,
+            params: HashMap::from(
+                params.map(|(param, value)| (param.to_owned(), value.to_owned())),
+            ),
+        };
+        let key = query([("tier", "1"), ("pack", "Turtle")]).cache_key();
+
+        assert_eq!(key, query([("pack", "Turtle"), ("tier", "1")]).cache_key());
+        assert_eq!(key, "Pet?pack=Turtle&tier=1");
+    }
+
+    #[tokio::test]
+    async fn test_get_pets_not_modified() {
+        let uri = "/db/pets?pack=Turtle&tier=1";
+        let resp = app()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        let etag = resp.headers().get(ETAG).unwrap().clone();
+
+        // Unchanged results aren't sent again.
+        let resp = app()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header(IF_NONE_MATCH, etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
 }
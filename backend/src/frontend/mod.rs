@@ -0,0 +1,121 @@
+pub mod response;
+
+#[cfg(feature = "embed-frontend")]
+mod embedded {
+    // Generated by `build.rs` from the frontend's `dist` directory.
+    include!(concat!(env!("OUT_DIR"), "/frontend_files.rs"));
+}
+
+use std::path::{Component, Path};
+
+use crate::config::config;
+
+/// Route the frontend reads its runtime config from.
+pub const FRONTEND_CONFIG_ROUTE: &str = "/config.json";
+/// Page served for the frontend's own routes. ex. `/pets/Ant`
+const INDEX_FILE: &str = "index.html";
+/// The index and config are revalidated so new deploys are picked up.
+const INDEX_CACHE_CONTROL: &str = "no-cache";
+const FILE_CACHE_CONTROL: &str = "public, max-age=3600";
+/// Path prefixes of backend routes. Unmatched paths under these are never frontend routes.
+const API_PREFIXES: [&str; 7] = [
+    "db",
+    "battle",
+    "convert",
+    "assets/img",
+    "healthz",
+    "readyz",
+    "metrics",
+];
+
+/// Frontend is served from a directory or embedded files.
+pub fn frontend_available() -> bool {
+    config().frontend.dir.is_some() || cfg!(feature = "embed-frontend")
+}
+
+/// Frontend file to serve for a request path without its leading `/`. [`None`] for backend routes.
+/// * Paths without an extension are frontend routes and get the index page.
+fn frontend_file_path(path: &str) -> Option<&str> {
+    let is_api_path = API_PREFIXES.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+    if is_api_path {
+        return None;
+    }
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let is_route = !file_name.contains('.');
+    Some(if is_route { INDEX_FILE } else { path })
+}
+
+/// Only allow plain relative paths so files outside the frontend directory can't be read.
+fn is_safe_path(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Load a file of the built frontend. Returns [`None`] if it doesn't exist.
+async fn load_frontend_file(path: &str) -> std::io::Result<Option<Vec<u8>>> {
+    if !is_safe_path(path) {
+        return Ok(None);
+    }
+
+    if let Some(dir) = &config().frontend.dir {
+        return match tokio::fs::read(dir.join(path)).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            // Directories.
+            Err(_) if dir.join(path).is_dir() => Ok(None),
+            Err(err) => Err(err),
+        };
+    }
+
+    #[cfg(feature = "embed-frontend")]
+    {
+        Ok(embedded::FRONTEND_FILES
+            .iter()
+            .find(|(file_path, _)| *file_path == path)
+            .map(|(_, bytes)| bytes.to_vec()))
+    }
+    #[cfg(not(feature = "embed-frontend"))]
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_path() {
+        assert!(is_safe_path("index.html"));
+        assert!(is_safe_path("assets/dioxus/frontend_bg.wasm"));
+        assert!(!is_safe_path("../Cargo.toml"));
+        assert!(!is_safe_path("assets/../../Cargo.toml"));
+        assert!(!is_safe_path("/etc/passwd"));
+    }
+
+    #[test]
+    fn test_frontend_file_path() {
+        assert_eq!(frontend_file_path(""), Some(INDEX_FILE));
+        assert_eq!(frontend_file_path("pets/Ant"), Some(INDEX_FILE));
+        assert_eq!(frontend_file_path("battle_ui"), Some(INDEX_FILE));
+        assert_eq!(
+            frontend_file_path("assets/dioxus/frontend_bg.wasm"),
+            Some("assets/dioxus/frontend_bg.wasm")
+        );
+        // Unknown backend routes aren't sent the index page.
+        for path in [
+            "db",
+            "db/pets/Ant/levels",
+            "battle/unknown",
+            "convert/other",
+            "assets/img/missing.png",
+            "healthz/extra",
+            "readyz",
+            "metrics/",
+        ] {
+            assert_eq!(frontend_file_path(path), None, "{path}");
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use axum::{
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        StatusCode, Uri,
+    },
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    frontend_file_path, load_frontend_file, FILE_CACHE_CONTROL, INDEX_CACHE_CONTROL, INDEX_FILE,
+};
+use crate::{assets::cache::content_type, config::config};
+
+/// Settings the frontend reads on startup.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrontendConfigResponse {
+    /// Backend url to send requests to. The frontend's origin if empty.
+    pub api_url: String,
+}
+
+pub async fn get_frontend_config() -> impl IntoResponse {
+    (
+        [(CACHE_CONTROL, INDEX_CACHE_CONTROL)],
+        Json(FrontendConfigResponse {
+            api_url: config().frontend.api_url.clone(),
+        }),
+    )
+}
+
+/// Serve a file of the built frontend.
+/// * Paths without an extension are frontend routes and get the index page.
+/// * Unmatched backend routes are `404 Not Found`.
+pub async fn get_frontend_file(uri: Uri) -> impl IntoResponse {
+    let path = uri.path().trim_start_matches('/');
+    let Some(file_path) = frontend_file_path(path) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    match load_frontend_file(file_path).await {
+        Ok(Some(bytes)) => {
+            let cache_control = if file_path == INDEX_FILE {
+                INDEX_CACHE_CONTROL
+            } else {
+                FILE_CACHE_CONTROL
+            };
+            Ok((
+                [
+                    (CONTENT_TYPE, content_type(file_path)),
+                    (CACHE_CONTROL, cache_control),
+                ],
+                bytes,
+            ))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(err) => {
+            log::error!("Unable to load frontend file {file_path}: {err}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
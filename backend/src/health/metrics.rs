@@ -0,0 +1,240 @@
+use axum::{extract::MatchedPath, http::Request, middleware::Next, response::Response};
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use saptest::teams::team::TeamFightOutcome;
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Upper bounds in seconds of duration histogram buckets.
+const DURATION_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+static METRICS: Lazy<Metrics> = Lazy::new(Default::default);
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if secs <= upper_bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn render(&self, output: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (count, upper_bound) in self.buckets.iter().zip(DURATION_BUCKETS) {
+            let _ = writeln!(
+                output,
+                "{name}_bucket{{{labels}{sep}le=\"{upper_bound}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let _ = writeln!(output, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(output, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    /// Requests keyed by method, route, and status.
+    requests: Mutex<IndexMap<(String, String, u16), u64>>,
+    /// Request durations keyed by method and route.
+    request_durations: Mutex<IndexMap<(String, String), Histogram>>,
+    /// Battles keyed by outcome.
+    battles: Mutex<IndexMap<String, u64>>,
+    turn_limit_hits: AtomicU64,
+    validation_failures: AtomicU64,
+    /// Database query durations keyed by table.
+    db_query_durations: Mutex<IndexMap<String, Histogram>>,
+}
+
+/// Escape a Prometheus label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(output: &mut String, name: &str, help: &str, metric_type: &str) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {metric_type}");
+}
+
+pub fn record_battle(outcome: Option<&TeamFightOutcome>) {
+    let outcome = outcome.map_or(String::from("Error"), |outcome| format!("{outcome:?}"));
+    *METRICS.battles.lock().unwrap().entry(outcome).or_default() += 1;
+}
+
+pub fn record_turn_limit() {
+    METRICS.turn_limit_hits.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_validation_failure() {
+    METRICS.validation_failures.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_db_query(table: &str, duration: Duration) {
+    METRICS
+        .db_query_durations
+        .lock()
+        .unwrap()
+        .entry(table.to_owned())
+        .or_default()
+        .observe(duration)
+}
+
+/// Middleware counting requests and their durations per route.
+pub async fn track_requests<B>(req: Request<B>, next: Next<B>) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_owned();
+
+    let resp = next.run(req).await;
+
+    let status = resp.status().as_u16();
+    *METRICS
+        .requests
+        .lock()
+        .unwrap()
+        .entry((method.clone(), route.clone(), status))
+        .or_default() += 1;
+    METRICS
+        .request_durations
+        .lock()
+        .unwrap()
+        .entry((method, route))
+        .or_default()
+        .observe(start.elapsed());
+
+    resp
+}
+
+/// All metrics in the Prometheus text format.
+pub fn render_metrics() -> String {
+    let mut output = String::new();
+
+    write_header(
+        &mut output,
+        "http_requests_total",
+        "Number of HTTP requests.",
+        "counter",
+    );
+    for ((method, route, status), count) in METRICS.requests.lock().unwrap().iter() {
+        let _ = writeln!(
+            output,
+            "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {count}",
+            escape(method),
+            escape(route)
+        );
+    }
+
+    write_header(
+        &mut output,
+        "http_request_duration_seconds",
+        "Duration of HTTP requests.",
+        "histogram",
+    );
+    for ((method, route), histogram) in METRICS.request_durations.lock().unwrap().iter() {
+        let labels = format!("method=\"{}\",route=\"{}\"", escape(method), escape(route));
+        histogram.render(&mut output, "http_request_duration_seconds", &labels);
+    }
+
+    write_header(
+        &mut output,
+        "battles_total",
+        "Number of battles run by outcome.",
+        "counter",
+    );
+    for (outcome, count) in METRICS.battles.lock().unwrap().iter() {
+        let _ = writeln!(
+            output,
+            "battles_total{{outcome=\"{}\"}} {count}",
+            escape(outcome)
+        );
+    }
+
+    write_header(
+        &mut output,
+        "battle_turn_limit_total",
+        "Number of battles that reached the turn limit.",
+        "counter",
+    );
+    let _ = writeln!(
+        output,
+        "battle_turn_limit_total {}",
+        METRICS.turn_limit_hits.load(Ordering::Relaxed)
+    );
+
+    write_header(
+        &mut output,
+        "battle_validation_failures_total",
+        "Number of battles with an invalid team.",
+        "counter",
+    );
+    let _ = writeln!(
+        output,
+        "battle_validation_failures_total {}",
+        METRICS.validation_failures.load(Ordering::Relaxed)
+    );
+
+    write_header(
+        &mut output,
+        "db_query_duration_seconds",
+        "Duration of database queries.",
+        "histogram",
+    );
+    for (table, histogram) in METRICS.db_query_durations.lock().unwrap().iter() {
+        let labels = format!("table=\"{}\"", escape(table));
+        histogram.render(&mut output, "db_query_duration_seconds", &labels);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(2));
+        histogram.observe(Duration::from_secs(10));
+
+        let mut output = String::new();
+        histogram.render(&mut output, "test", "route=\"/\"");
+
+        // Buckets are cumulative.
+        assert!(output.contains("test_bucket{route=\"/\",le=\"0.001\"} 0"));
+        assert!(output.contains("test_bucket{route=\"/\",le=\"0.0025\"} 1"));
+        assert!(output.contains("test_bucket{route=\"/\",le=\"5\"} 1"));
+        assert!(output.contains("test_bucket{route=\"/\",le=\"+Inf\"} 2"));
+        assert!(output.contains("test_count{route=\"/\"} 2"));
+    }
+}
@@ -0,0 +1,128 @@
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::metrics::render_metrics;
+use crate::{db::cache::db_version, server::is_shutting_down};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReadyResponse {
+    pub status: String,
+    pub pets: usize,
+    pub foods: usize,
+}
+
+/// Process is up.
+pub async fn get_healthz() -> impl IntoResponse {
+    (StatusCode::OK, "ok")
+}
+
+/// Database can be queried and has pets and foods. Not ready once shutting down.
+/// * Uses the record counts from the last database version check so probes never query the database.
+pub async fn get_readyz() -> impl IntoResponse {
+    let records = db_version().records;
+    let (pets, foods) = (records.pets, records.foods);
+    let mut resp = ReadyResponse {
+        status: String::from("ready"),
+        pets: pets.unwrap_or_default(),
+        foods: foods.unwrap_or_default(),
+    };
+
+    if is_shutting_down() {
+        resp.status = String::from("Shutting down.");
+        (StatusCode::SERVICE_UNAVAILABLE, Json(resp))
+    } else if pets.is_none() || foods.is_none() {
+        resp.status = String::from("Unable to query database.");
+        (StatusCode::SERVICE_UNAVAILABLE, Json(resp))
+    } else if resp.pets == 0 || resp.foods == 0 {
+        resp.status = String::from("Database is empty.");
+        (StatusCode::SERVICE_UNAVAILABLE, Json(resp))
+    } else {
+        (StatusCode::OK, Json(resp))
+    }
+}
+
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_metrics(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_get_healthz() {
+        let app = app();
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_readyz() {
+        let app = app();
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/readyz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_metrics() {
+        let app = app();
+        // Request is recorded before metrics are rendered.
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(
+            body.contains("http_requests_total{method=\"GET\",route=\"/healthz\",status=\"200\"}")
+        );
+        assert!(body.contains("# TYPE battles_total counter"));
+    }
+}
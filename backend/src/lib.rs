@@ -0,0 +1,131 @@
+use axum::{extract::DefaultBodyLimit, middleware, Router};
+use http::{
+    header::{ACCEPT, CONTENT_TYPE},
+    HeaderValue,
+};
+use hyper::Method;
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    timeout::TimeoutLayer,
+    trace::TraceLayer,
+};
+
+pub mod args;
+pub mod assets;
+pub mod battle;
+pub mod config;
+pub mod convert;
+pub mod db;
+pub mod frontend;
+pub mod health;
+pub mod limits;
+pub mod logging;
+pub mod routes;
+pub mod server;
+
+use crate::{
+    config::config,
+    frontend::frontend_available,
+    health::metrics::track_requests,
+    limits::rate_limited,
+    logging::{request_span, REQUEST_ID_HEADER},
+    routes::{
+        asset_routes, battle_routes, convert_routes, db_routes, frontend_routes, health_routes,
+    },
+};
+
+pub fn app() -> Router {
+    let config = config();
+    let allowed_origins = if config.server.allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .server
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| origin.parse::<HeaderValue>().ok()),
+        )
+    };
+
+    // https://docs.rs/tower-http/0.4.0/tower_http/cors/index.html
+    let cors = CorsLayer::new()
+        .allow_headers([CONTENT_TYPE, ACCEPT])
+        // Allow `GET` and `POST` when accessing the resource
+        .allow_methods([Method::GET, Method::POST])
+        // Allow requests from the allowed origins.
+        .allow_origin(allowed_origins);
+
+    let (routes, limits) = (&config.routes, &config.limits);
+    let mut router = Router::new();
+    if routes.db {
+        router = router.merge(rate_limited(db_routes(), limits.db));
+    }
+    if routes.battle {
+        router = router.merge(rate_limited(battle_routes(), limits.battle));
+    }
+    if routes.convert {
+        router = router.merge(rate_limited(convert_routes(), limits.db));
+    }
+    if routes.assets {
+        router = router.merge(asset_routes());
+    }
+    if routes.health {
+        router = router.merge(health_routes());
+    }
+    if routes.frontend && frontend_available() {
+        router = router.merge(frontend_routes());
+    }
+
+    router
+        .layer(middleware::from_fn(track_requests))
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        // Return request ID so it can be matched to logs.
+        .layer(PropagateRequestIdLayer::new(
+            REQUEST_ID_HEADER.parse().unwrap(),
+        ))
+        .layer(SetRequestIdLayer::new(
+            REQUEST_ID_HEADER.parse().unwrap(),
+            MakeRequestUuid,
+        ))
+        .layer(DefaultBodyLimit::max(limits.max_body_size))
+        .layer(TimeoutLayer::new(limits.timeout()))
+        .layer(cors)
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, Request, StatusCode},
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::app;
+
+    /// Send a request to the app. Returns the status and JSON body. The body is `null` if it isn't JSON.
+    async fn send(request: Request<Body>) -> (StatusCode, Value) {
+        let resp = app().oneshot(request).await.unwrap();
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    pub async fn get_json(uri: &str) -> (StatusCode, Value) {
+        send(Request::builder().uri(uri).body(Body::empty()).unwrap()).await
+    }
+
+    pub async fn post_json(uri: &str, body: Value) -> (StatusCode, Value) {
+        send(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+    }
+}
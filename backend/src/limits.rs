@@ -0,0 +1,281 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header::RETRY_AFTER, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::config::config;
+
+/// Number of tracked clients before full buckets are dropped.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+/// Least recently seen clients dropped at once if there are still too many clients after dropping full buckets.
+const EVICTED_CLIENTS: usize = MAX_TRACKED_CLIENTS / 10;
+
+/// Token bucket quota for a single client.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Quota {
+    /// Requests allowed per minute once the burst is used up.
+    pub per_minute: u32,
+    /// Requests allowed at once.
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Quota for database reads.
+    pub db: Quota,
+    /// Quota for battle simulations.
+    pub battle: Quota,
+    /// Maximum size of a request body in bytes.
+    pub max_body_size: usize,
+    /// Maximum duration of a request in seconds.
+    pub timeout_secs: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            db: Quota {
+                per_minute: 120,
+                burst: 60,
+            },
+            battle: Quota {
+                per_minute: 30,
+                burst: 10,
+            },
+            max_body_size: 64 * 1024,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl Limits {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Per-client token bucket rate limiter.
+pub struct RateLimiter {
+    quota: Quota,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            buckets: Default::default(),
+        }
+    }
+
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.quota.per_minute) / 60.0
+    }
+
+    /// Take a token from a client's bucket.
+    /// * Returns how long to wait for the next token if the bucket is empty.
+    pub fn check(&self, client: IpAddr) -> Result<(), Duration> {
+        let (rate, burst) = (self.refill_rate(), f64::from(self.quota.burst));
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // Clients with full buckets are indistinguishable from new ones.
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.last_refill).as_secs_f64() * rate < burst
+            });
+        }
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // Drop the least recently seen clients so the number of clients stays bounded.
+            let mut last_seen = buckets
+                .values()
+                .map(|bucket| bucket.last_refill)
+                .collect::<Vec<Instant>>();
+            let num_evicted = buckets.len() + EVICTED_CLIENTS - MAX_TRACKED_CLIENTS;
+            let (_, cutoff, _) = last_seen.select_nth_unstable(num_evicted - 1);
+            let cutoff = *cutoff;
+            buckets.retain(|_, bucket| bucket.last_refill > cutoff);
+        }
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: burst,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if rate > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+}
+
+/// Address of the client making a request.
+/// * Proxy headers are only used if `trust_proxy` is set as they can be spoofed otherwise.
+fn client_ip<B>(req: &Request<B>) -> IpAddr {
+    let headers = req.headers();
+    let proxy_ip = config()
+        .server
+        .trust_proxy
+        .then(|| {
+            let fly_ip = headers.get("Fly-Client-IP");
+            let forwarded_ip = headers.get("X-Forwarded-For");
+            fly_ip
+                .or(forwarded_ip)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .and_then(|ip| ip.trim().parse().ok())
+        })
+        .flatten();
+
+    proxy_ip
+        .or_else(|| {
+            req.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+async fn rate_limit<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match limiter.check(client_ip(&req)) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            let retry_secs = retry_after.as_secs().max(1);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_secs.to_string())],
+                format!("Too many requests. Retry in {retry_secs} seconds."),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Limit each client to `quota` requests on the routes of `router`.
+pub fn rate_limited(router: Router, quota: Quota) -> Router {
+    let limiter = Arc::new(RateLimiter::new(quota));
+    router.route_layer(middleware::from_fn_with_state(limiter, rate_limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app;
+    use axum::{
+        body::Body,
+        http::{
+            header::{CONTENT_TYPE, RETRY_AFTER},
+            Request, StatusCode,
+        },
+        routing::get,
+        Router,
+    };
+    use std::net::{IpAddr, Ipv4Addr};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_burst() {
+        let limiter = RateLimiter::new(Quota {
+            per_minute: 1,
+            burst: 2,
+        });
+        let (client, other_client) = (
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        );
+
+        assert!(limiter.check(client).is_ok());
+        assert!(limiter.check(client).is_ok());
+        // Bucket is empty. Next token in about a minute.
+        let retry_after = limiter.check(client).unwrap_err();
+        assert!(retry_after > Duration::from_secs(50));
+        // Other clients have their own bucket.
+        assert!(limiter.check(other_client).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limiter_max_clients() {
+        let limiter = RateLimiter::new(Quota {
+            per_minute: 0,
+            burst: 2,
+        });
+        let client = |idx: usize| IpAddr::V4(Ipv4Addr::from(idx as u32));
+
+        // Buckets are never full again so only the least recently seen clients can be dropped.
+        for idx in 0..MAX_TRACKED_CLIENTS + 1 {
+            assert!(limiter.check(client(idx)).is_ok());
+        }
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.len() <= MAX_TRACKED_CLIENTS);
+        assert!(buckets.contains_key(&client(MAX_TRACKED_CLIENTS)));
+        assert!(!buckets.contains_key(&client(0)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_route() {
+        let app = rate_limited(
+            Router::new().route("/", get(|| async {})),
+            Quota {
+                per_minute: 1,
+                burst: 1,
+            },
+        );
+        let request = || Request::builder().uri("/").body(Body::empty()).unwrap();
+
+        let resp = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = app.oneshot(request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key(RETRY_AFTER));
+    }
+
+    #[tokio::test]
+    async fn test_body_too_large() {
+        let body = format!(
+            "{{\"padding\": \"{}\"}}",
+            "a".repeat(config().limits.max_body_size)
+        );
+        let resp = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/battle")
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
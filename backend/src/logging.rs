@@ -0,0 +1,185 @@
+use axum::{body::Body, http::Request};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tower_http::request_id::RequestId;
+use tracing::Span;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+use crate::config::LogConfig;
+
+/// Header the request ID is read from and returned in.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines.
+    Text,
+    /// One JSON object per line.
+    Json,
+    /// `key=value` pairs per line.
+    Logfmt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogOutput {
+    Stdout,
+    File,
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+/// Check a log filter. ex. `info` or `info,backend=debug`
+pub fn parse_log_filter(filter: &str) -> Result<String, String> {
+    EnvFilter::try_new(filter)
+        .map(|_| filter.to_owned())
+        .map_err(|err| err.to_string())
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn format_layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Logfmt => tracing_logfmt::builder()
+            .layer()
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
+/// Log to stdout, a rotated file, or both. Records from the `log` crate are included.
+/// * The returned guards flush logs on drop and must be kept until exit.
+pub fn init_logging(config: &LogConfig) -> Vec<WorkerGuard> {
+    let mut guards = vec![];
+    let mut layers: Vec<BoxedLayer> = vec![];
+
+    if matches!(config.output, LogOutput::Stdout | LogOutput::Both) {
+        let (writer, guard) = tracing_appender::non_blocking(std::io::stdout());
+        layers.push(format_layer(config.format, writer, true));
+        guards.push(guard);
+    }
+    if matches!(config.output, LogOutput::File | LogOutput::Both) {
+        let log_dir = config
+            .file
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let log_prefix = config.file.file_name().unwrap_or("backend.log".as_ref());
+        let appender = RollingFileAppender::new(config.rotation.into(), log_dir, log_prefix);
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        layers.push(format_layer(config.format, writer, false));
+        guards.push(guard);
+    }
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(EnvFilter::new(&config.level))
+        .init();
+
+    guards
+}
+
+/// Span for a request with the ID set by `SetRequestIdLayer`.
+pub fn request_span(req: &Request<Body>) -> Span {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        request_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_log_filter() {
+        assert!(parse_log_filter("info").is_ok());
+        assert!(parse_log_filter("info,backend=debug").is_ok());
+        assert!(parse_log_filter("backend=loud").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_id_header() {
+        let app = app();
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(REQUEST_ID_HEADER));
+
+        // Given request IDs are kept.
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/healthz")
+                    .header(REQUEST_ID_HEADER, "test-id")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "test-id");
+    }
+}
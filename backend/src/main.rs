@@ -3,59 +3,91 @@ use std::{
     str::FromStr,
 };
 
-use axum::Router;
-use clap::Parser;
-use http::header::{ACCEPT, CONTENT_TYPE};
-use hyper::Method;
-use log::LevelFilter;
-use tower_http::cors::{Any, CorsLayer};
-
-mod args;
-mod battle;
-mod db;
-mod routes;
-
-use crate::{
+use backend::{
+    app,
     args::Args,
-    routes::{battle_routes, db_routes},
+    assets::cache::cache_all_assets,
+    config::{config, set_config, Config},
+    convert::run_command,
+    db::cache::watch_db_version,
+    logging::init_logging,
+    server::serve,
 };
+use clap::Parser;
 
-#[tokio::main]
-async fn main() {
+fn main() {
     let args = Args::parse();
+    let server_config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Invalid config: {err}");
+            std::process::exit(1);
+        }
+    };
 
-    simple_logging::log_to_file(
-        "backend.log",
-        LevelFilter::from_str(&args.log_level).unwrap(),
-    )
-    .unwrap();
+    if let Some(command) = &args.command {
+        set_config(server_config);
+        match run_command(command) {
+            Ok(converted) => println!("{converted}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if args.print_config {
+        match server_config.to_toml() {
+            Ok(config_toml) => print!("{config_toml}"),
+            Err(err) => eprintln!("Unable to print config: {err}"),
+        }
+        return;
+    }
+
+    // Load the database before the runtime starts any threads. See Config::load_database.
+    if let Err(err) = server_config.load_database() {
+        eprintln!("Unable to load database: {err}");
+        std::process::exit(1);
+    }
+    set_config(server_config);
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("Unable to start runtime: {err}");
+            std::process::exit(1);
+        }
+    };
+    runtime.block_on(run_server());
+}
+
+async fn run_server() {
+    let config = config();
+
+    // Keep guards so buffered logs are flushed on exit.
+    let _log_guards = init_logging(&config.logging);
 
     let addr: SocketAddr = SocketAddr::from((
-        IpAddr::from_str(args.addr.as_str()).unwrap_or(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
-        args.port,
+        IpAddr::from_str(config.server.addr.as_str()).unwrap_or(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        config.server.port,
     ));
 
-    let app = app();
+    // Check for database changes in the background.
+    tokio::spawn(watch_db_version());
 
-    log::info!("Listening on http://{addr}");
+    if config.assets.prefetch {
+        // Download any images not cached yet in the background.
+        tokio::spawn(cache_all_assets());
+    }
 
-    axum::Server::bind(&addr.to_string().parse().unwrap())
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
-}
+    let app = app();
 
-pub fn app() -> Router {
-    // https://docs.rs/tower-http/0.4.0/tower_http/cors/index.html
-    let cors = CorsLayer::new()
-        .allow_headers([CONTENT_TYPE, ACCEPT])
-        // Allow `GET` and `POST` when accessing the resource
-        .allow_methods([Method::GET, Method::POST])
-        // Allow requests from any origin
-        .allow_origin(Any);
-
-    Router::new()
-        .merge(db_routes())
-        .merge(battle_routes())
-        .layer(cors)
+    if let Err(err) = serve(app, addr, &config.server).await {
+        log::error!("Server error: {err}");
+        std::process::exit(1);
+    }
 }
@@ -4,16 +4,61 @@ use axum::{
 };
 
 use crate::{
-    battle::response::post_battle,
-    db::response::{get_food, get_pet},
+    assets::response::get_asset,
+    battle::{
+        analysis::post_analyze, batch::post_batch, compare::post_compare, response::post_battle,
+        resume::post_resume,
+    },
+    convert::response::post_convert_sapai,
+    db::response::{
+        get_autocomplete, get_db_meta, get_db_version, get_food, get_food_by_name, get_pet,
+        get_pet_by_name,
+    },
+    frontend::{
+        response::{get_frontend_config, get_frontend_file},
+        FRONTEND_CONFIG_ROUTE,
+    },
+    health::response::{get_healthz, get_metrics, get_readyz},
 };
 
 pub fn db_routes() -> Router {
     Router::new()
         .route("/db/pets", get(get_pet))
         .route("/db/foods", get(get_food))
+        .route("/db/version", get(get_db_version))
+        .route("/db/meta", get(get_db_meta))
+        .route("/db/pets/:name", get(get_pet_by_name))
+        .route("/db/foods/:name", get(get_food_by_name))
+        .route("/db/autocomplete", get(get_autocomplete))
 }
 
 pub fn battle_routes() -> Router {
-    Router::new().route("/battle", post(post_battle))
+    Router::new()
+        .route("/battle", post(post_battle))
+        .route("/battle/compare", post(post_compare))
+        .route("/battle/batch", post(post_batch))
+        .route("/battle/analyze", post(post_analyze))
+        .route("/battle/resume", post(post_resume))
+}
+
+pub fn convert_routes() -> Router {
+    Router::new().route("/convert/sapai", post(post_convert_sapai))
+}
+
+pub fn asset_routes() -> Router {
+    Router::new().route("/assets/img/:file_name", get(get_asset))
+}
+
+pub fn health_routes() -> Router {
+    Router::new()
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .route("/metrics", get(get_metrics))
+}
+
+/// Frontend config and files. Unmatched paths are served from the frontend.
+pub fn frontend_routes() -> Router {
+    Router::new()
+        .route(FRONTEND_CONFIG_ROUTE, get(get_frontend_config))
+        .fallback_service(get(get_frontend_file))
 }
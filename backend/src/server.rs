@@ -0,0 +1,74 @@
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::config::ServerConfig;
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Shutdown signal received. In-flight requests are being drained.
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::Relaxed)
+}
+
+/// Wait for SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Unable to listen for SIGINT.")
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Unable to listen for SIGTERM.")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Stop accepting connections on a shutdown signal and give open connections `timeout` to finish.
+/// * Connections still open after `timeout` are closed, cancelling their requests.
+async fn graceful_shutdown(handle: Handle, timeout: Duration) {
+    shutdown_signal().await;
+    SHUTTING_DOWN.store(true, Ordering::Relaxed);
+    log::info!(
+        "Shutting down. Waiting up to {}s for {} connection(s).",
+        timeout.as_secs(),
+        handle.connection_count()
+    );
+    handle.graceful_shutdown(Some(timeout));
+}
+
+/// Serve `app` over HTTP, or HTTPS if TLS is configured, until a shutdown signal.
+pub async fn serve(app: Router, addr: SocketAddr, config: &ServerConfig) -> std::io::Result<()> {
+    let handle = Handle::new();
+    tokio::spawn(graceful_shutdown(handle.clone(), config.shutdown_timeout()));
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+
+    if let Some(tls) = &config.tls {
+        let tls_config = RustlsConfig::from_pem_file(&tls.cert, &tls.key).await?;
+        log::info!("Listening on https://{addr}");
+        axum_server::bind_rustls(addr, tls_config)
+            .handle(handle)
+            .serve(service)
+            .await
+    } else {
+        log::info!("Listening on http://{addr}");
+        axum_server::bind(addr).handle(handle).serve(service).await
+    }?;
+
+    log::info!("Shut down.");
+    Ok(())
+}
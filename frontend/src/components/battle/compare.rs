@@ -0,0 +1,271 @@
+use dioxus::prelude::*;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{
+        battle::{
+            fight::{BattleResponse, ACCEPTED_STATUS},
+            ui::{BattleUIState, PetSlots},
+        },
+        status::{ErrorMsg, LoadingMsg, RequestState},
+    },
+    records::{
+        query::post_compare,
+        snapshot::{PetSnapshot, TurnSnapshot},
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct TurnComparison {
+    pub turn: usize,
+    pub diverged: bool,
+    pub first: Option<TurnSnapshot>,
+    pub second: Option<TurnSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct FaintedDiff {
+    pub first_only: Vec<String>,
+    pub second_only: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct StatDiff {
+    pub pet: String,
+    pub first: Option<PetSnapshot>,
+    pub second: Option<PetSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct TeamDiff {
+    pub fainted: FaintedDiff,
+    pub stats: Vec<StatDiff>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CompareResponse {
+    pub status: Option<String>,
+    pub seed: u64,
+    pub outcome_changed: bool,
+    pub divergent_turn: Option<usize>,
+    pub friend_team: TeamDiff,
+    pub enemy_team: TeamDiff,
+    pub turns: Vec<TurnComparison>,
+    pub first: BattleResponse,
+    pub second: BattleResponse,
+}
+
+fn format_pets(pets: &[PetSnapshot]) -> String {
+    pets.iter()
+        .map(|pet| format!("{} ({}/{})", pet.name, pet.attack, pet.health))
+        .join(", ")
+}
+
+fn format_stats(pet: &Option<PetSnapshot>) -> String {
+    pet.as_ref().map_or(String::from("Fainted"), |pet| {
+        format!("{}/{}", pet.attack, pet.health)
+    })
+}
+
+fn TeamDiffPanel<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    title: &'a str,
+    diff: &TeamDiff,
+) -> Element<'a> {
+    let first_fainted = diff.fainted.first_only.join(", ");
+    let second_fainted = diff.fainted.second_only.join(", ");
+
+    cx.render(rsx! {
+        h3 { class: "w3-panel w3-card w3-light-grey", "{title}" }
+        table { class: "w3-table w3-responsive",
+            tr {
+                th { "Fainted only in baseline" }
+                td { "{first_fainted}" }
+            }
+            tr {
+                th { "Fainted only in current" }
+                td { "{second_fainted}" }
+            }
+        }
+        table { class: "w3-table w3-striped w3-responsive",
+            tr {
+                th { "Pet" }
+                th { "Baseline" }
+                th { "Current" }
+            }
+            diff.stats.iter().map(|stat_diff| {
+                let (first_stats, second_stats) = (format_stats(&stat_diff.first), format_stats(&stat_diff.second));
+                rsx! {
+                    tr {
+                        td { "{stat_diff.pet}" }
+                        td { "{first_stats}" }
+                        td { "{second_stats}" }
+                    }
+                }
+            })
+        }
+    })
+}
+
+fn TurnRow<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    turn: &TurnComparison,
+    is_first_divergent: bool,
+) -> Element<'a> {
+    let row_class = if is_first_divergent {
+        "w3-red"
+    } else if turn.diverged {
+        "w3-pale-red"
+    } else {
+        ""
+    };
+    let format_turn = |snapshot: &Option<TurnSnapshot>| {
+        snapshot
+            .as_ref()
+            .map_or((String::from("Ended"), String::from("Ended")), |snapshot| {
+                (
+                    format_pets(&snapshot.friend_team.pets),
+                    format_pets(&snapshot.enemy_team.pets),
+                )
+            })
+    };
+    let (first_friends, first_enemies) = format_turn(&turn.first);
+    let (second_friends, second_enemies) = format_turn(&turn.second);
+
+    cx.render(rsx! {
+        tr { class: row_class,
+            td { "{turn.turn}" }
+            td { "{first_friends}" }
+            td { "{first_enemies}" }
+            td { "{second_friends}" }
+            td { "{second_enemies}" }
+        }
+    })
+}
+
+pub fn CompareSummaryModal<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    comparison: &UseRef<RequestState<CompareResponse>>,
+    modal_state: &'a UseState<&str>,
+) -> Element<'a> {
+    let comparison_summary = comparison.with(|comparison| {
+        let comparison = match comparison {
+            RequestState::Idle => return None,
+            RequestState::Loading => return LoadingMsg(cx, "Comparing battles..."),
+            RequestState::Failed(err) => return ErrorMsg(cx, err),
+            RequestState::Done(comparison) => comparison,
+        };
+        // Unknown battles or invalid teams.
+        if let Some(status) = comparison
+            .status
+            .as_deref()
+            .filter(|status| *status != ACCEPTED_STATUS)
+        {
+            return ErrorMsg(cx, status);
+        }
+        let divergence_msg = comparison
+            .divergent_turn
+            .map_or(String::from("The battles never diverge."), |turn| {
+                format!("The battles diverge at turn {turn}.")
+            });
+        let outcome_class = if comparison.outcome_changed {
+            "w3-panel w3-pale-red"
+        } else {
+            "w3-panel w3-pale-green"
+        };
+
+        cx.render(rsx! {
+            p { "Seed: {comparison.seed}" }
+            div { class: outcome_class,
+                p { "Baseline outcome: {comparison.first.outcome}" }
+                p { "Current outcome: {comparison.second.outcome}" }
+            }
+            h4 { "{divergence_msg}" }
+
+            TeamDiffPanel(cx, "Friend", &comparison.friend_team),
+            TeamDiffPanel(cx, "Enemy", &comparison.enemy_team),
+
+            h3 { class: "w3-panel w3-card w3-light-grey", "Turns" }
+            table { class: "w3-table w3-bordered w3-responsive",
+                tr {
+                    th { "Turn" }
+                    th { "Baseline Friend" }
+                    th { "Baseline Enemy" }
+                    th { "Current Friend" }
+                    th { "Current Enemy" }
+                }
+                comparison.turns.iter().map(|turn| {
+                    TurnRow(cx, turn, comparison.divergent_turn == Some(turn.turn))
+                })
+            }
+            br {}
+        })
+    });
+
+    cx.render(rsx! {
+        div { class: "w3-container w3-modal", display: "{modal_state.get()}",
+            div { class: "w3-display-container",
+                h2 { class: "w3-container w3-black", "Comparison" }
+                button {
+                    class: "w3-button w3-red w3-display-topright",
+                    onclick: move |_| modal_state.set("none"),
+                    "X"
+                }
+            }
+            div { class: "w3-container",
+                div { class: "w3-container w3-white", comparison_summary }
+            }
+        }
+    })
+}
+
+pub fn CompareSummary<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
+    let summary_state = use_state(cx, || "none");
+    let baseline: &UseRef<Option<IndexMap<String, PetSlots>>> = use_ref(cx, || None);
+    let comparison: &UseRef<RequestState<CompareResponse>> = use_ref(cx, RequestState::default);
+    let no_baseline = baseline.with(|baseline| baseline.is_none());
+
+    cx.render(rsx! {
+        div { class: "w3-container w3-large",
+            div { class: "w3-half",
+                button {
+                    class: "w3-button w3-block w3-grey",
+                    // Store current teams to compare future edits against.
+                    onclick: move |_| {
+                        let teams = cx.props.teams.with(|teams| teams.to_owned());
+                        baseline.set(Some(teams))
+                    },
+                    "Set Baseline"
+                }
+            }
+            div { class: "w3-half",
+                button {
+                    class: "w3-button w3-block w3-dark-grey",
+                    disabled: "{no_baseline}",
+                    onclick: move |_| {
+                        let Some(baseline_teams) = baseline.with(|teams| teams.to_owned()) else {
+                            return
+                        };
+                        comparison.set(RequestState::Loading);
+                        cx.spawn({
+                            let comparison = comparison.to_owned();
+                            let teams = cx.props.teams.with(|teams| teams.to_owned());
+                            async move {
+                                let res = post_compare(baseline_teams, teams).await;
+                                comparison.set(match res {
+                                    Ok(resp) => RequestState::Done(resp),
+                                    Err(err) => RequestState::Failed(err.to_string()),
+                                })
+                            }
+                        });
+                        summary_state.set("block")
+                    },
+                    "Compare to Baseline"
+                }
+            }
+            CompareSummaryModal(cx, comparison, summary_state)
+        }
+    })
+}
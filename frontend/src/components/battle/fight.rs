@@ -1,20 +1,42 @@
-use crate::records::query::post_battle;
+use crate::{
+    components::status::{ErrorMsg, LoadingMsg, RequestState},
+    records::{
+        local::{fight_locally, local_seed},
+        query::post_battle,
+        snapshot::TurnState,
+    },
+};
 use dioxus::prelude::*;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sim::{BattleStats, PetStats};
 
-use super::ui::BattleUIState;
+use super::{
+    replay::Replay,
+    ui::{BattleUIState, PetSlots},
+};
 
 const QUICK_CHART_GRAPHVIZ_APIURL: &str = "https://quickchart.io/graphviz?graph=";
+/// Status of a battle fought to completion. Other statuses are errors.
+pub const ACCEPTED_STATUS: &str = "202 Accepted";
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct BattleResponse {
+    pub id: Option<u64>,
     pub status: Option<String>,
     pub outcome: String,
     pub friend_team: Option<Value>,
     pub enemy_team: Option<Value>,
     pub num_turns: usize,
+    pub seed: Option<u64>,
     pub digraph: Option<String>,
+    pub pet_stats: Option<BattleStats>,
+    /// State of both teams after every turn. Only set once the replay of a server battle is opened.
+    pub turns: Option<Vec<TurnState>>,
+    /// Pets and foods whose effects were skipped. Only set for local battles.
+    #[serde(default)]
+    pub unmodeled: Vec<String>,
 }
 
 pub fn PetValueRow<'a>(cx: Scope<'a, BattleUIState<'a>>, pets: &[Value]) -> Element<'a> {
@@ -30,10 +52,47 @@ pub fn PetValueRow<'a>(cx: Scope<'a, BattleUIState<'a>>, pets: &[Value]) -> Elem
     })
 }
 
+pub fn PetStatsRow<'a>(cx: Scope<'a, BattleUIState<'a>>, pet: &PetStats) -> Element<'a> {
+    let fainted_turn = pet
+        .fainted_turn
+        .map_or(String::from("-"), |turn| turn.to_string());
+    cx.render(rsx! {
+        tr {
+            td { "{pet.name}" }
+            td { "{pet.damage_dealt}" }
+            td { "{pet.damage_taken}" }
+            td { "{pet.kills}" }
+            td { "{pet.effect_triggers}" }
+            td { "{pet.stats_granted}" }
+            td { "{pet.summons}" }
+            td { "{fainted_turn}" }
+        }
+    })
+}
+
+/// Performance of every pet on a team. Falls back to alive and fainted pet names if the battle has no pet stats.
 pub fn PostBattleTeamContainer<'a>(
     cx: Scope<'a, BattleUIState<'a>>,
     team: Option<&Value>,
+    pet_stats: Option<&[PetStats]>,
 ) -> Element<'a> {
+    if let Some(pet_stats) = pet_stats {
+        return cx.render(rsx! {
+            table { class: "w3-table w3-bordered w3-responsive",
+                tr {
+                    th { "Pet" }
+                    th { "Damage Dealt" }
+                    th { "Damage Taken" }
+                    th { "Kills" }
+                    th { "Triggers" }
+                    th { "Stats Given" }
+                    th { "Summons" }
+                    th { "Fainted" }
+                }
+                pet_stats.iter().map(|pet| PetStatsRow(cx, pet))
+            }
+        });
+    }
     team.and_then(|team| {
         let fainted_pet_elems = team
             .get("fainted")
@@ -56,16 +115,31 @@ pub fn PostBattleTeamContainer<'a>(
     })
 }
 
-pub fn FightSummaryModal<'a>(
+/// Result of a single battle. Server battles can be replayed and resumed.
+/// * Opening the replay fights `fought_teams` again with the same seed and keeps every turn.
+pub fn BattleOutcome<'a>(
     cx: Scope<'a, BattleUIState<'a>>,
-    outcome: &UseRef<Option<BattleResponse>>,
-    modal_state: &'a UseState<&str>,
+    outcome: &'a UseRef<RequestState<BattleResponse>>,
+    fought_teams: &'a UseRef<Option<IndexMap<String, PetSlots>>>,
+    loading_msg: &str,
 ) -> Element<'a> {
     let digraph_code_state = use_state(cx, || "block");
-    let outcome_summary = outcome.with(|outcome| {
-        let Some(outcome) = outcome else {
-            return None
+    let outcome_ref = outcome;
+    outcome.with(|outcome| {
+        let outcome = match outcome {
+            RequestState::Idle => return None,
+            RequestState::Loading => return LoadingMsg(cx, loading_msg),
+            RequestState::Failed(err) => return ErrorMsg(cx, err),
+            RequestState::Done(outcome) => outcome,
         };
+        // Invalid teams or battles stopped early.
+        let error_msg = outcome
+            .status
+            .as_deref()
+            .filter(|status| *status != ACCEPTED_STATUS);
+        if outcome.friend_team.is_none() {
+            return ErrorMsg(cx, error_msg.unwrap_or("Battle failed."));
+        }
         let (mut friend_title, mut enemy_title) = (String::from("Friend"), String::from("Enemy"));
         let mut is_undecided = false;
 
@@ -78,13 +152,37 @@ pub fn FightSummaryModal<'a>(
 
         // Format of outcome:
         // {"fainted":[],"friends":[],"max_size":5,"name":"Enemy","seed": usize,"sold":[],"stored_friends":[],"triggers":[]}
-        let friend_team_div = PostBattleTeamContainer(cx, outcome.friend_team.as_ref());
-        let enemy_team_div = PostBattleTeamContainer(cx, outcome.enemy_team.as_ref());
+        let friend_team_div = PostBattleTeamContainer(
+            cx,
+            outcome.friend_team.as_ref(),
+            outcome
+                .pet_stats
+                .as_ref()
+                .map(|stats| &stats.friend_team[..]),
+        );
+        let enemy_team_div = PostBattleTeamContainer(
+            cx,
+            outcome.enemy_team.as_ref(),
+            outcome
+                .pet_stats
+                .as_ref()
+                .map(|stats| &stats.enemy_team[..]),
+        );
+        // Only server battles are stored and can be fought again with their seed.
+        let can_open_replay = outcome.turns.is_none() && outcome.id.is_some();
+        let seed = outcome.seed;
+        let unmodeled = outcome.unmodeled.join(", ");
 
         cx.render(rsx! {
+            error_msg.and_then(|msg| ErrorMsg(cx, msg)),
+            (!outcome.unmodeled.is_empty()).then(|| cx.render(rsx! {
+                div { class: "w3-panel w3-pale-yellow",
+                    p { "Effects not simulated in the browser: {unmodeled}. Fight on the server for the full battle." }
+                }
+            })),
             // If it turn limit and battle unfinished, show message.
             is_undecided.then(|| cx.render(rsx! {
-                "Unfinished battle. Reached turn limit of 250."
+                "Unfinished battle. Reached the turn limit."
             })),
 
             h3 { class: "w3-panel w3-card w3-light-grey", friend_title }
@@ -93,6 +191,33 @@ pub fn FightSummaryModal<'a>(
             enemy_team_div,
             br {}
 
+            outcome.turns.as_ref().map(|turns| rsx! {
+                Replay {
+                    turns: turns.clone(),
+                    outcome: outcome_ref
+                }
+            }),
+            can_open_replay.then(|| rsx! {
+                button { class: "w3-button w3-block w3-light-grey",
+                    onclick: move |_| {
+                        let Some(teams) = fought_teams.read().clone() else {
+                            return;
+                        };
+                        outcome_ref.set(RequestState::Loading);
+                        cx.spawn({
+                            let outcome = outcome_ref.to_owned();
+                            async move {
+                                outcome.set(match post_battle(teams, seed, true).await {
+                                    Ok(resp) => RequestState::Done(resp),
+                                    Err(err) => RequestState::Failed(err.to_string()),
+                                })
+                            }
+                        });
+                    },
+                    "Open replay"
+                }
+            }),
+
             outcome.digraph.as_ref().and_then(|digraph_str| {
                 let graphvis_chart_request = format!("{QUICK_CHART_GRAPHVIZ_APIURL}{digraph_str}");
                 cx.render(rsx! {
@@ -123,7 +248,8 @@ pub fn FightSummaryModal<'a>(
                                 "Graphviz"
                             }
                             img { class: "w3-image",
-                                src: "{graphvis_chart_request}"
+                                src: "{graphvis_chart_request}",
+                                alt: "Graph of battle events"
                             }
                         }
 
@@ -135,7 +261,56 @@ pub fn FightSummaryModal<'a>(
 
             br {}
         })
-    });
+    })
+}
+
+/// Message comparing the outcomes of the local and server battles. [`None`] until both are done.
+fn OutcomeMatchMsg<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    local_outcome: &UseRef<RequestState<BattleResponse>>,
+    server_outcome: &UseRef<RequestState<BattleResponse>>,
+) -> Element<'a> {
+    let (local, server) = local_outcome.with(|local| {
+        server_outcome.with(|server| match (local, server) {
+            (RequestState::Done(local), RequestState::Done(server)) => {
+                Some((local.outcome.clone(), server.outcome.clone()))
+            }
+            _ => None,
+        })
+    })?;
+    let (outcome_class, msg) = if local == server {
+        (
+            "w3-panel w3-pale-green",
+            String::from("Local and server outcomes match."),
+        )
+    } else {
+        (
+            "w3-panel w3-pale-red",
+            format!("Local outcome ({local}) differs from the server ({server})."),
+        )
+    };
+    cx.render(rsx! {
+        div { class: outcome_class,
+            p { "{msg}" }
+        }
+    })
+}
+
+pub fn FightSummaryModal<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    local_outcome: &'a UseRef<RequestState<BattleResponse>>,
+    server_outcome: &'a UseRef<RequestState<BattleResponse>>,
+    fought_teams: &'a UseRef<Option<IndexMap<String, PetSlots>>>,
+    modal_state: &'a UseState<&str>,
+) -> Element<'a> {
+    let local_summary = BattleOutcome(cx, local_outcome, fought_teams, "Fighting...");
+    let server_summary = BattleOutcome(
+        cx,
+        server_outcome,
+        fought_teams,
+        "Fighting on the server...",
+    );
+    let is_comparing = server_outcome.with(|outcome| !matches!(outcome, RequestState::Idle));
 
     cx.render(rsx! {
         div { class: "w3-container w3-modal", display: "{modal_state.get()}",
@@ -148,7 +323,16 @@ pub fn FightSummaryModal<'a>(
                 }
             }
             div { class: "w3-container",
-                div { class: "w3-container w3-white", outcome_summary }
+                div { class: "w3-container w3-white",
+                    p { "Fought in the browser. Only some pet abilities and foods are simulated." }
+                    local_summary,
+                    is_comparing.then(|| cx.render(rsx! {
+                        h2 { "Server Battle" }
+                        p { "Fought on the server with every pet ability and food." }
+                        OutcomeMatchMsg(cx, local_outcome, server_outcome),
+                        server_summary
+                    }))
+                }
             }
         }
     })
@@ -156,26 +340,55 @@ pub fn FightSummaryModal<'a>(
 
 pub fn FightSummary<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
     let summary_state = use_state(cx, || "none");
-    let post_battle_outcome: &UseRef<Option<BattleResponse>> = use_ref(cx, || None);
+    let compare_on_server = use_state(cx, || false);
+    let local_outcome: &UseRef<RequestState<BattleResponse>> = use_ref(cx, RequestState::default);
+    let server_outcome: &UseRef<RequestState<BattleResponse>> = use_ref(cx, RequestState::default);
+    // Teams of the last fight. Fought again if its replay is opened.
+    let fought_teams: &UseRef<Option<IndexMap<String, PetSlots>>> = use_ref(cx, || None);
 
     cx.render(rsx! {
         div { class: "w3-container w3-xlarge",
             button {
                 class: "w3-button w3-block w3-red",
                 onclick: move |_| {
-                    cx.spawn({
-                        let post_battle_outcome = post_battle_outcome.to_owned();
-                        let teams = cx.props.teams.with(|teams| teams.to_owned());
-                        async move {
-                            let res = post_battle(teams).await;
-                            post_battle_outcome.set(res.ok())
-                        }
+                    let teams = cx.props.teams.with(|teams| teams.to_owned());
+                    fought_teams.set(Some(teams.clone()));
+                    local_outcome.set(match fight_locally(teams.clone(), local_seed()) {
+                        Ok(resp) => RequestState::Done(resp),
+                        Err(err) => RequestState::Failed(err.to_string()),
                     });
+
+                    // Only fought on the server on request.
+                    if *compare_on_server.get() {
+                        server_outcome.set(RequestState::Loading);
+                        cx.spawn({
+                            let server_outcome = server_outcome.to_owned();
+                            async move {
+                                let res = post_battle(teams, None, false).await;
+                                server_outcome.set(match res {
+                                    Ok(resp) => RequestState::Done(resp),
+                                    Err(err) => RequestState::Failed(err.to_string()),
+                                })
+                            }
+                        });
+                    } else {
+                        server_outcome.set(RequestState::Idle);
+                    }
                     summary_state.set("block")
                 },
                 "Fight!"
             }
-            FightSummaryModal(cx, post_battle_outcome, summary_state)
+            label { class: "w3-large",
+                input {
+                    class: "w3-check",
+                    r#type: "checkbox",
+                    checked: "{compare_on_server}",
+                    "aria-label": "Also fight on the server",
+                    onchange: move |_| compare_on_server.set(!compare_on_server.get())
+                }
+                " Also fight on the server"
+            }
+            FightSummaryModal(cx, local_outcome, server_outcome, fought_teams, summary_state)
         }
     })
 }
@@ -0,0 +1,204 @@
+use dioxus::prelude::*;
+use indexmap::IndexMap;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{HtmlElement, KeyboardEvent};
+
+use crate::components::battle::ui::{BattleUIState, PetSlots};
+
+/// Maximum number of team edits that can be undone.
+const MAX_HISTORY_SIZE: usize = 100;
+
+/// Undo and redo stacks of both teams' states.
+#[derive(Debug, Default)]
+pub struct TeamHistory {
+    undo_stack: Vec<IndexMap<String, PetSlots>>,
+    redo_stack: Vec<IndexMap<String, PetSlots>>,
+    /// Group of the last recorded edit. See [`TeamHistory::record_grouped`].
+    last_group: Option<String>,
+}
+
+impl TeamHistory {
+    /// Record the state of the teams before an edit. Clears any undone edits.
+    pub fn record(&mut self, teams: IndexMap<String, PetSlots>) {
+        if self.undo_stack.len() == MAX_HISTORY_SIZE {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(teams);
+        self.redo_stack.clear();
+        self.last_group = None;
+    }
+
+    /// Record an edit unless the last edit was in the same `group`.
+    /// * ex. Stepping a stat input several times is undone at once.
+    pub fn record_grouped(&mut self, teams: IndexMap<String, PetSlots>, group: String) {
+        if self.last_group.as_ref() != Some(&group) {
+            self.record(teams);
+            self.last_group = Some(group);
+        }
+    }
+
+    /// Restore the teams to their state before the last edit.
+    /// * Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self, teams: &mut IndexMap<String, PetSlots>) -> bool {
+        let Some(prev_teams) = self.undo_stack.pop() else {
+            return false;
+        };
+        self.redo_stack.push(std::mem::replace(teams, prev_teams));
+        self.last_group = None;
+        true
+    }
+
+    /// Reapply the last undone edit.
+    /// * Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self, teams: &mut IndexMap<String, PetSlots>) -> bool {
+        let Some(next_teams) = self.redo_stack.pop() else {
+            return false;
+        };
+        self.undo_stack.push(std::mem::replace(teams, next_teams));
+        self.last_group = None;
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+/// Undo the last team edit. Deselects the current pet as its slot may no longer exist.
+pub fn undo_team_edit(
+    teams: &UseRef<IndexMap<String, PetSlots>>,
+    history: &UseRef<TeamHistory>,
+    selected_pet_idx: &UseState<Option<usize>>,
+) {
+    if history.with_mut(|history| teams.with_mut(|teams| history.undo(teams))) {
+        selected_pet_idx.set(None)
+    }
+}
+
+/// Redo the last undone team edit. Deselects the current pet as its slot may no longer exist.
+pub fn redo_team_edit(
+    teams: &UseRef<IndexMap<String, PetSlots>>,
+    history: &UseRef<TeamHistory>,
+    selected_pet_idx: &UseState<Option<usize>>,
+) {
+    if history.with_mut(|history| teams.with_mut(|teams| history.redo(teams))) {
+        selected_pet_idx.set(None)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    Undo,
+    Redo,
+}
+
+/// Action of a keyboard shortcut. Undo on Ctrl+Z. Redo on Ctrl+Shift+Z or Ctrl+Y.
+pub fn history_shortcut(key: &str, is_ctrl: bool, is_shift: bool) -> Option<HistoryAction> {
+    if !is_ctrl {
+        return None;
+    }
+    if key.eq_ignore_ascii_case("z") {
+        Some(if is_shift {
+            HistoryAction::Redo
+        } else {
+            HistoryAction::Undo
+        })
+    } else if key.eq_ignore_ascii_case("y") {
+        Some(HistoryAction::Redo)
+    } else {
+        None
+    }
+}
+
+/// If a key was pressed in a text field. Its own undo and redo are kept.
+fn is_in_text_field(evt: &KeyboardEvent) -> bool {
+    evt.target()
+        .and_then(|target| target.dyn_into::<HtmlElement>().ok())
+        .map_or(false, |elem| {
+            matches!(elem.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                || elem.is_content_editable()
+        })
+}
+
+/// Keydown listener on the document. Removed when the team builder is unmounted.
+struct ShortcutListener {
+    callback: Closure<dyn FnMut(KeyboardEvent)>,
+}
+
+impl Drop for ShortcutListener {
+    fn drop(&mut self) {
+        if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+            let _ = document.remove_event_listener_with_callback(
+                "keydown",
+                self.callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+/// Undo and redo team edits with keyboard shortcuts anywhere on the page. See [`history_shortcut`].
+/// * The browser's default action for a shortcut is prevented. Shortcuts in text fields are left to the browser.
+pub fn use_history_shortcuts(
+    cx: &ScopeState,
+    teams: &UseRef<IndexMap<String, PetSlots>>,
+    history: &UseRef<TeamHistory>,
+    selected_pet_idx: &UseState<Option<usize>>,
+) {
+    cx.use_hook(|| {
+        let (teams, history, selected_pet_idx) =
+            (teams.clone(), history.clone(), selected_pet_idx.clone());
+        let callback = Closure::<dyn FnMut(KeyboardEvent)>::new(move |evt: KeyboardEvent| {
+            if is_in_text_field(&evt) {
+                return;
+            }
+            let is_ctrl = evt.ctrl_key() || evt.meta_key();
+            let Some(action) = history_shortcut(&evt.key(), is_ctrl, evt.shift_key()) else {
+                return;
+            };
+            evt.prevent_default();
+            match action {
+                HistoryAction::Undo => undo_team_edit(&teams, &history, &selected_pet_idx),
+                HistoryAction::Redo => redo_team_edit(&teams, &history, &selected_pet_idx),
+            }
+        });
+        if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+            let _ = document
+                .add_event_listener_with_callback("keydown", callback.as_ref().unchecked_ref());
+        }
+        ShortcutListener { callback }
+    });
+}
+
+pub fn HistoryToolbar<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
+    let (can_undo, can_redo) = cx
+        .props
+        .history
+        .with(|history| (history.can_undo(), history.can_redo()));
+
+    cx.render(rsx! {
+        div { class: "w3-bar",
+            button {
+                class: "w3-bar-item w3-button w3-light-grey",
+                title: "Undo (Ctrl+Z)",
+                disabled: "{!can_undo}",
+                onclick: move |_| {
+                    undo_team_edit(cx.props.teams, cx.props.history, cx.props.selected_pet_idx)
+                },
+                "Undo"
+            }
+            button {
+                class: "w3-bar-item w3-button w3-light-grey",
+                title: "Redo (Ctrl+Shift+Z)",
+                disabled: "{!can_redo}",
+                onclick: move |_| {
+                    redo_team_edit(cx.props.teams, cx.props.history, cx.props.selected_pet_idx)
+                },
+                "Redo"
+            }
+        }
+    })
+}
@@ -1,4 +1,5 @@
-use dioxus::prelude::*;
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use dioxus_web::use_eval;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use log::info;
@@ -6,17 +7,55 @@ use log::info;
 use crate::{
     components::{
         battle::{
-            state::{add_pet_to_team, assign_pet_property},
+            state::{add_pet_to_team, assign_pet_property, navigate_slots},
             ui::{BattleUIState, FILTER_FIELDS},
-            MAX_PET_TIER,
         },
+        status::{use_records_state, ErrorMsg, LoadingMsg, RequestState},
         tabs::TabContainer,
     },
-    records::pet::PetProperty,
+    records::{meta::db_meta, pet::PetProperty, record::SAPSimpleRecord},
     RECORDS,
 };
 
-const SELECTABLE_PACKS: [&str; 6] = ["Turtle", "Puppy", "Star", "Weekly", "Unknown", "All"];
+/// Option of the pack and trigger filters that matches any value.
+const ANY_OPTION: &str = "All";
+
+/// Focus the name filter on `/` unless the user is typing in another field.
+/// * Handled in JS as the `/` would otherwise be typed into the filter.
+const NAME_FILTER_SHORTCUT_JS: &str = r#"
+if (!window.nameFilterShortcut) {
+    window.nameFilterShortcut = true;
+    document.addEventListener("keydown", (evt) => {
+        const tag = evt.target.tagName;
+        const nameFilter = document.getElementById("name-filter");
+        if (evt.key === "/" && nameFilter && !["INPUT", "SELECT", "TEXTAREA"].includes(tag)) {
+            evt.preventDefault();
+            nameFilter.focus();
+        }
+    });
+}
+"#;
+
+/// Items of a category shown in the item containers.
+/// * Only level one pets and holdable foods are shown.
+fn shown_items<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    category: &str,
+) -> Vec<(&'static String, &'static SAPSimpleRecord)> {
+    let Some(items) = RECORDS.get().and_then(|records| records.get(category)) else {
+        return vec![];
+    };
+    items
+        .iter()
+        .filter(|(_, item)| {
+            let is_shown = match item {
+                SAPSimpleRecord::Pet(_) => item.level() == Some(1),
+                SAPSimpleRecord::Food(_) => item.holdable(),
+            };
+            is_shown && item.is_valid_item(cx)
+        })
+        .collect_vec()
+}
 
 fn ItemTypeClickMsg<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a> {
     cx.render(rsx! {
@@ -34,26 +73,63 @@ fn UnableToRetrieveItemMsg<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a>
     })
 }
 
+/// Message shown while records aren't loaded.
+fn RecordsStateMsg<'a>(
+    cx: Scope<'a, BattleUIState<'a>>,
+    records_state: RequestState<()>,
+) -> Element<'a> {
+    match records_state {
+        RequestState::Loading => LoadingMsg(cx, "Loading items..."),
+        RequestState::Failed(err) => ErrorMsg(cx, &format!("Unable to load items. {err}")),
+        RequestState::Idle | RequestState::Done(_) => ItemTypeClickMsg(cx),
+    }
+}
+
 pub fn PetsContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a> {
+    let active_pet_idx = use_state(cx, || None::<usize>);
+    let records_state = use_records_state(cx);
+
     let Some(records) = RECORDS.get() else {
-        return ItemTypeClickMsg(cx)
+        return RecordsStateMsg(cx, records_state)
     };
 
-    let Some(pets) = records.get("Pets") else {
+    if !records.contains_key("Pets") {
         return UnableToRetrieveItemMsg(cx)
     };
 
+    let active_item = active_pet_idx.map_or(String::new(), |idx| format!("pet-item-{idx}"));
+
     cx.render(rsx! {
         div { class: "w3-table w3-striped w3-responsive w3-white",
-            pets.iter()
-            // Only show one level of pet.
-            .filter(|(_, pet_info)| pet_info.level() == Some(1) && pet_info.is_valid_item(cx) )
-            .map(|(_leveled_pet_id, pet_info)| {
+            tabindex: "0",
+            "role": "listbox",
+            "aria-label": "Pets. Arrow keys select a pet and Enter adds it to the team.",
+            "aria-activedescendant": "{active_item}",
+            onkeydown: move |evt| {
+                let pets = shown_items(cx, "Pets");
+                let key = evt.key();
+                if let Some(idx) = navigate_slots(&key, *active_pet_idx.get(), pets.len()) {
+                    active_pet_idx.set(Some(idx))
+                } else if key == Key::Enter {
+                    let Some((_, pet_info)) = active_pet_idx.and_then(|idx| pets.get(idx)) else {
+                        return
+                    };
+                    if let Err(err) = add_pet_to_team(cx, pet_info) {
+                        info!("{err}")
+                    }
+                }
+            },
+            shown_items(cx, "Pets").into_iter().enumerate().map(|(i, (_leveled_pet_id, pet_info))| {
+                let is_active = Some(i) == **active_pet_idx;
                 rsx! {
                     img {
-                        class: "w3-image w3-hover-opacity",
+                        id: "pet-item-{i}",
+                        class: if is_active { "w3-image w3-hover-opacity w3-pale-red" } else { "w3-image w3-hover-opacity" },
                         src: "{pet_info.img_url()}",
                         title: "{pet_info.name()}",
+                        alt: "{pet_info.name()}",
+                        "role": "option",
+                        "aria-selected": "{is_active}",
                         // Add pet on click.
                         onclick: move |_| {
                             if let Err(err) = add_pet_to_team(cx, pet_info) {
@@ -77,40 +153,64 @@ pub fn PetsContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a> {
 }
 
 pub fn FoodsContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a> {
+    let active_food_idx = use_state(cx, || None::<usize>);
+    let records_state = use_records_state(cx);
+
     let Some(records) = RECORDS.get() else {
-        return ItemTypeClickMsg(cx)
+        return RecordsStateMsg(cx, records_state)
     };
 
-    let Some(foods) = records.get("Foods") else {
+    if !records.contains_key("Foods") {
         return UnableToRetrieveItemMsg(cx)
     };
 
+    let active_item = active_food_idx.map_or(String::new(), |idx| format!("food-item-{idx}"));
+    // Assign food to current pet if any.
+    let assign_food = move |name: &str| {
+        if let Some(Err(err)) = cx.props.selected_pet_idx.get().map(|idx| {
+            assign_pet_property(cx, idx, PetProperty::Food(Some(name.to_string())))
+        }) {
+            info!("{err}")
+        }
+    };
+
     cx.render(rsx! {
         div { class: "w3-table w3-striped w3-responsive w3-white",
-            foods.iter()
-            .filter(|(_, food_info)| food_info.holdable() && food_info.is_valid_item(cx))
-            .map(|(name, food_info)|
+            tabindex: "0",
+            "role": "listbox",
+            "aria-label": "Foods. Arrow keys select a food and Enter gives it to the current pet.",
+            "aria-activedescendant": "{active_item}",
+            onkeydown: move |evt| {
+                let foods = shown_items(cx, "Foods");
+                let key = evt.key();
+                if let Some(idx) = navigate_slots(&key, *active_food_idx.get(), foods.len()) {
+                    active_food_idx.set(Some(idx))
+                } else if key == Key::Enter {
+                    if let Some((name, _)) = active_food_idx.and_then(|idx| foods.get(idx)) {
+                        assign_food(name)
+                    }
+                }
+            },
+            shown_items(cx, "Foods").into_iter().enumerate().map(|(i, (name, food_info))| {
+                let is_active = Some(i) == **active_food_idx;
                 rsx! {
                     img {
-                        class: "w3-image w3-hover-opacity",
+                        id: "food-item-{i}",
+                        class: if is_active { "w3-image w3-hover-opacity w3-pale-red" } else { "w3-image w3-hover-opacity" },
                         src: "{food_info.img_url()}",
                         title: "{food_info.name()}",
+                        alt: "{food_info.name()}",
+                        "role": "option",
+                        "aria-selected": "{is_active}",
                         draggable: "true",
                         // Dragging an item icon selects it; dropping it deselects it.
                         ondragend: move |_| cx.props.selected_item.set(None),
                         ondragstart: move |_| cx.props.selected_item.set(Some(name.to_string())),
                         // On item click, assign to current pet if any.
-                        onclick: move |_| {
-                            if let Some(Err(err)) = cx.props.selected_pet_idx.get().map(|idx| {
-                                // Set selected item.
-                                assign_pet_property(cx, idx, PetProperty::Food(Some(name.to_string())))
-                            }) {
-                                info!("{err}")
-                            }
-                        }
+                        onclick: move |_| assign_food(name)
                     }
                 }
-            )
+            })
         }
     })
 }
@@ -132,7 +232,7 @@ pub fn GameItemsContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a> {
 }
 
 pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
-    let Some((Some(selected_name), Some(selected_tier), Some(selected_pack))) = FILTER_FIELDS
+    let Some((Some(selected_name), Some(selected_tier), Some(selected_pack), Some(selected_trigger))) = FILTER_FIELDS
         .into_iter()
         .map(|field| cx.props.filters.with(|fields| fields.get(field).cloned())).collect_tuple() else
     {
@@ -146,6 +246,13 @@ pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element
         })
     };
     let is_valid_state = use_state(cx, || true);
+    let eval = use_eval(cx);
+    cx.use_hook(|| eval(NAME_FILTER_SHORTCUT_JS.to_string()));
+    // Filter values are only known once records are loaded.
+    let Some(meta) = db_meta() else {
+        return None
+    };
+    let max_tier = meta.tiers.max;
 
     cx.render(rsx! {
         div { class: "w3-container w3-cell-middle",
@@ -154,7 +261,10 @@ pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element
                 h3 { "Name" }
                 input {
                     class: "w3-input",
+                    id: "name-filter",
                     name: "Name",
+                    "aria-label": "Name filter",
+                    "aria-keyshortcuts": "/",
                     "type": "search",
                     value: "{selected_name}",
                     oninput: move |evt| {
@@ -171,13 +281,14 @@ pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element
                 input {
                     class: "w3-input",
                     name: "Tier",
+                    "aria-label": "Tier filter",
                     "type": "number",
                     value: "{selected_tier}",
                     min: "0",
-                    max: "{MAX_PET_TIER}",
+                    max: "{max_tier}",
                     onchange: move |evt| {
                         if let Ok(tier)
-                            = evt.data.value.parse::<u64>().map(|tier| tier.clamp(0, MAX_PET_TIER))
+                            = evt.data.value.parse::<u64>().map(|tier| tier.clamp(0, max_tier))
                         {
                             cx.props
                                 .filters
@@ -193,6 +304,7 @@ pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element
                 select {
                     class: "w3-select",
                     name: "Pack",
+                    "aria-label": "Pack filter",
                     value: "{selected_pack}",
                     onchange: move |evt| {
                         cx.props
@@ -203,7 +315,7 @@ pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element
                                     .and_modify(|field| { *field = evt.data.value.clone() });
                             });
                     },
-                    SELECTABLE_PACKS.into_iter().map(|pack| {
+                    meta.packs.iter().map(String::as_str).chain([ANY_OPTION]).map(|pack| {
                         cx.render(rsx! {
                             option {
                                 value: "{pack}",
@@ -212,6 +324,30 @@ pub fn GameItemsFilterContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element
                         })
                     })
                 }
+                h3 { "Trigger" }
+                select {
+                    class: "w3-select",
+                    name: "Trigger",
+                    "aria-label": "Pet trigger filter",
+                    value: "{selected_trigger}",
+                    onchange: move |evt| {
+                        cx.props
+                            .filters
+                            .with_mut(|filters| {
+                                filters
+                                    .entry("Trigger")
+                                    .and_modify(|field| { *field = evt.data.value.clone() });
+                            });
+                    },
+                    [ANY_OPTION].into_iter().chain(meta.effect_triggers.iter().map(String::as_str)).map(|trigger| {
+                        cx.render(rsx! {
+                            option {
+                                value: "{trigger}",
+                                "{trigger}"
+                            }
+                        })
+                    })
+                }
             }
         }
     })
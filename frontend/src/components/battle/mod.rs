@@ -1,17 +1,14 @@
+pub mod compare;
 pub mod fight;
+pub mod history;
 pub mod item_selection;
+pub mod replay;
 pub mod selected_pet;
 pub mod state;
 pub mod team;
 pub mod ui;
 
-pub const ALLOWED_TEAM_SIZE: usize = 5;
-pub const MAX_PET_TIER: u64 = 6;
-pub const MIN_PET_HEALTH: u64 = 1;
-pub const MAX_PET_HEALTH: u64 = 50;
-
-pub const ATTACK_ICON: &str =
-    "https://static.wikia.nocookie.net/superautopets/images/a/aa/Attack_Icon.png";
-pub const HEALTH_ICON: &str =
-    "https://static.wikia.nocookie.net/superautopets/images/4/44/Health_Icon.png";
-pub const EMPTY_SLOT_ICON: &str = "https://upload.wikimedia.org/wikipedia/commons/thumb/c/c1/Empty_set_symbol.svg/200px-Empty_set_symbol.svg.png";
+// Images cached by the backend. See `asset_url`.
+pub const ATTACK_ICON: &str = "/assets/img/attack_icon.png";
+pub const HEALTH_ICON: &str = "/assets/img/health_icon.png";
+pub const EMPTY_SLOT_ICON: &str = "/assets/img/empty_slot_icon.png";
@@ -0,0 +1,238 @@
+use crate::{
+    components::status::RequestState,
+    records::{
+        meta::team_size,
+        query::post_resume,
+        snapshot::TurnState,
+        team::{PetEdit, ResumeRequest, TeamSide},
+    },
+};
+use dioxus::prelude::*;
+use serde_json::Value;
+
+use super::fight::BattleResponse;
+
+#[derive(Props)]
+pub struct ReplayProps<'a> {
+    pub turns: Vec<TurnState>,
+    /// Replaced with the resumed battle.
+    pub outcome: &'a UseRef<RequestState<BattleResponse>>,
+}
+
+/// Name, attack, health, and item name of a pet from a team in the backend's format.
+fn pet_summary(pet: &Value) -> (&str, i64, i64, &str) {
+    let stat = |name: &str| {
+        pet.get("stats")
+            .and_then(|stats| stats.get(name))
+            .and_then(|stat| stat.as_i64())
+            .unwrap_or_default()
+    };
+    let name = pet
+        .get("name")
+        .and_then(|name| name.as_str())
+        .unwrap_or("?");
+    let item = pet
+        .get("item")
+        .and_then(|item| item.get("name"))
+        .and_then(|name| name.as_str())
+        .unwrap_or("");
+    (name, stat("attack"), stat("health"), item)
+}
+
+/// Pets of a team in the backend's format by position. Empty slots are [`None`].
+fn team_pets(team: &Value) -> Vec<Option<&Value>> {
+    team.get("friends")
+        .and_then(|pets| pets.as_array())
+        .map(|pets| {
+            pets.iter()
+                .map(|pet| (!pet.is_null()).then_some(pet))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Step through the turns of a server battle. Pets at any turn can be edited and the battle continued from there.
+pub fn Replay<'a>(cx: Scope<'a, ReplayProps<'a>>) -> Element<'a> {
+    let step = use_state(cx, || 0_usize);
+    let edits: &UseRef<Vec<PetEdit>> = use_ref(cx, Vec::new);
+    let selected_edit: &UseState<Option<usize>> = use_state(cx, || None);
+
+    let last_step = cx.props.turns.len().checked_sub(1)?;
+    let current_step = (*step.get()).min(last_step);
+    let state = &cx.props.turns[current_step];
+
+    let change_step = move |new_step: usize| {
+        step.set(new_step);
+        edits.set(vec![]);
+        selected_edit.set(None);
+    };
+
+    let team_rows = [
+        (TeamSide::Friend, &state.friend_team),
+        (TeamSide::Enemy, &state.enemy_team),
+    ]
+    .into_iter()
+    .map(|(side, team)| {
+        let pets = team_pets(team);
+        let side_name = format!("{side:?}");
+        rsx! {
+            tr {
+                th { "{side_name}" }
+                pets.into_iter().enumerate().map(|(pos, pet)| {
+                    let Some(pet) = pet else {
+                        return rsx! { td { "-" } };
+                    };
+                    let (name, attack, health, item) = pet_summary(pet);
+                    let is_edited = edits.with(|edits| {
+                        edits.iter().any(|edit| edit.team == side && edit.position == pos)
+                    });
+                    let cell_class = if is_edited { "w3-pale-yellow w3-hover-light-grey" } else { "w3-hover-light-grey" };
+                    rsx! {
+                        td { class: cell_class,
+                            role: "button",
+                            title: "Edit pet",
+                            onclick: move |_| {
+                                let idx = edits.with_mut(|edits| {
+                                    edits
+                                        .iter()
+                                        .position(|edit| edit.team == side && edit.position == pos)
+                                        .unwrap_or_else(|| {
+                                            edits.push(PetEdit {
+                                                team: side,
+                                                position: pos,
+                                                attack: None,
+                                                health: None,
+                                                item: None,
+                                                move_to: None,
+                                            });
+                                            edits.len() - 1
+                                        })
+                                });
+                                selected_edit.set(Some(idx))
+                            },
+                            "{name} ({attack}/{health}) {item}"
+                        }
+                    }
+                })
+            }
+        }
+    });
+
+    let edit_form = selected_edit.get().and_then(|idx| {
+        let edit = edits.with(|edits| edits.get(idx).cloned())?;
+        let team = match edit.team {
+            TeamSide::Friend => &state.friend_team,
+            TeamSide::Enemy => &state.enemy_team,
+        };
+        let pet = team_pets(team).get(edit.position).copied().flatten()?;
+        let (name, attack, health, item) = pet_summary(pet);
+        let attack = edit.attack.unwrap_or(attack);
+        let health = edit.health.unwrap_or(health);
+        let item = edit.item.clone().unwrap_or_else(|| item.to_owned());
+        let position = edit.move_to.unwrap_or(edit.position);
+        let max_position = team_size().saturating_sub(1);
+        let title = format!("{:?} {name} at position {}", edit.team, edit.position);
+
+        cx.render(rsx! {
+            div { class: "w3-panel w3-light-grey",
+                h4 { "{title}" }
+                label { "Attack" }
+                input { class: "w3-input", r#type: "number", min: "0", max: "50", value: "{attack}",
+                    onchange: move |evt| {
+                        if let Ok(attack) = evt.data.value.parse::<i64>() {
+                            edits.with_mut(|edits| edits[idx].attack = Some(attack))
+                        }
+                    }
+                }
+                label { "Health" }
+                input { class: "w3-input", r#type: "number", min: "1", max: "50", value: "{health}",
+                    onchange: move |evt| {
+                        if let Ok(health) = evt.data.value.parse::<i64>() {
+                            edits.with_mut(|edits| edits[idx].health = Some(health))
+                        }
+                    }
+                }
+                label { "Item (empty for none)" }
+                input { class: "w3-input", r#type: "text", value: "{item}",
+                    onchange: move |evt| {
+                        edits.with_mut(|edits| edits[idx].item = Some(evt.data.value.trim().to_owned()))
+                    }
+                }
+                label { "Position (0 is the front)" }
+                input { class: "w3-input", r#type: "number", min: "0", max: "{max_position}", value: "{position}",
+                    onchange: move |evt| {
+                        if let Ok(position) = evt.data.value.parse::<usize>() {
+                            edits.with_mut(|edits| {
+                                let edit = &mut edits[idx];
+                                edit.move_to = (position != edit.position).then_some(position)
+                            })
+                        }
+                    }
+                }
+            }
+        })
+    });
+
+    let num_edits = edits.with(|edits| edits.len());
+    let is_resuming = cx
+        .props
+        .outcome
+        .with(|outcome| matches!(outcome, RequestState::Loading));
+    let (is_first_step, is_last_step) = (current_step == 0, current_step == last_step);
+
+    cx.render(rsx! {
+        div { class: "w3-panel w3-card w3-light-grey",
+            h3 { "Replay" }
+            p { "Turn {state.turn}. Select a pet to edit it, then continue the battle from this turn." }
+            div { class: "w3-bar",
+                button { class: "w3-button w3-bar-item",
+                    disabled: "{is_first_step}",
+                    onclick: move |_| change_step(current_step.saturating_sub(1)),
+                    "Previous"
+                }
+                button { class: "w3-button w3-bar-item",
+                    disabled: "{is_last_step}",
+                    onclick: move |_| change_step((current_step + 1).min(last_step)),
+                    "Next"
+                }
+            }
+            table { class: "w3-table w3-bordered w3-responsive",
+                team_rows
+            }
+            edit_form,
+            div { class: "w3-bar",
+                button { class: "w3-button w3-bar-item w3-red",
+                    disabled: "{is_resuming}",
+                    onclick: move |_| {
+                        let request = ResumeRequest {
+                            friend_team: state.friend_team.clone(),
+                            enemy_team: state.enemy_team.clone(),
+                            // Teams keep the random state of the battle.
+                            seed: None,
+                            turn: state.turn,
+                            edits: edits.read().clone(),
+                            replay: true,
+                        };
+                        let outcome = cx.props.outcome.to_owned();
+                        outcome.set(RequestState::Loading);
+                        cx.spawn(async move {
+                            outcome.set(match post_resume(request).await {
+                                Ok(resp) => RequestState::Done(resp),
+                                Err(err) => RequestState::Failed(err.to_string()),
+                            })
+                        });
+                        change_step(0)
+                    },
+                    "Edit and continue from here ({num_edits} edits)"
+                }
+                button { class: "w3-button w3-bar-item",
+                    onclick: move |_| {
+                        edits.set(vec![]);
+                        selected_edit.set(None)
+                    },
+                    "Clear edits"
+                }
+            }
+        }
+    })
+}
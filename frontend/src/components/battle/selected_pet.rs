@@ -5,9 +5,14 @@ use crate::{
     components::battle::{
         state::{assign_pet_property, get_selected_pet_property},
         ui::BattleUIState,
-        ATTACK_ICON, HEALTH_ICON, MAX_PET_HEALTH, MIN_PET_HEALTH,
+        ATTACK_ICON, HEALTH_ICON,
+    },
+    records::{
+        effect::SimpleEffect,
+        meta::{db_meta, pet_levels},
+        pet::PetProperty,
+        query::{asset_url, retrieve_record},
     },
-    records::{effect::SimpleEffect, pet::PetProperty, query::retrieve_record},
 };
 
 fn LabeledStatInput<'a>(
@@ -19,11 +24,25 @@ fn LabeledStatInput<'a>(
     let Some(pet_idx) = cx.props.selected_pet_idx.get() else {
         return None;
     };
+    let Some(stats) = db_meta().map(|meta| meta.stats) else {
+        return None;
+    };
+    // Pets can't start a battle fainted.
+    let (min_stat, max_stat) = if stat_label == "Health" {
+        (stats.min.max(1), stats.max)
+    } else {
+        (stats.min, stats.max)
+    };
     let valid_input = if **is_valid_state {
         "w3-input w3-center w3-half"
     } else {
         "w3-input w3-half w3-center w3-pale-red"
     };
+    let stat_icon = asset_url(if stat_label == "Health" {
+        HEALTH_ICON
+    } else {
+        ATTACK_ICON
+    });
     cx.render(rsx! {
         div { class: "w3-container",
             img {
@@ -32,23 +51,27 @@ fn LabeledStatInput<'a>(
                 width: "10%",
                 height: "10%",
                 title: "{stat_label}",
-                src: if stat_label == "Health" { HEALTH_ICON } else { ATTACK_ICON }
+                alt: "{stat_label}",
+                src: "{stat_icon}"
             }
             input {
                 class: valid_input,
                 "type": "number",
                 placeholder: "{stat_label}",
+                "aria-label": "{stat_label}",
                 value: "{starting_value}",
-                min: "{MIN_PET_HEALTH}",
-                max: "{MAX_PET_HEALTH}",
+                min: "{min_stat}",
+                max: "{max_stat}",
                 required: true,
+                // Only validated while typing. The pet is edited once the value is committed.
+                oninput: move |evt| is_valid_state.set(evt.data.value.parse::<u64>().is_ok()),
                 onchange: move |evt| {
                     if let Ok(input_stat_value)
                         = &evt
                             .data
                             .value
                             .parse::<u64>()
-                            .map(|value| value.clamp(MIN_PET_HEALTH, MAX_PET_HEALTH))
+                            .map(|value| value.clamp(min_stat, max_stat))
                     {
                         is_valid_state.set(true);
                         let stat_value: Option<PetProperty> = match stat_label {
@@ -150,7 +173,7 @@ fn PetEffectContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element<'a> {
             h2 { "Effect" }
             // Allow level selection.
             select { class: "w3-select w3-center", value: "{pet_lvl}",
-                (1..=3).map(|lvl| {
+                pet_levels().map(|lvl| {
                     rsx! {
                         option {
                             value: "{lvl}",
@@ -1,9 +1,10 @@
-use dioxus::prelude::*;
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use indexmap::IndexMap;
 use std::error::Error;
 
 use crate::{
-    components::battle::{ui::BattleUIState, ALLOWED_TEAM_SIZE},
-    records::{pet::PetProperty, query::retrieve_record, record::SAPSimpleRecord},
+    components::battle::ui::{BattleUIState, PetSlots},
+    records::{meta::team_size, pet::PetProperty, query::retrieve_record, record::SAPSimpleRecord},
 };
 
 pub fn get_selected_pet_property(
@@ -36,6 +37,30 @@ pub fn get_selected_pet_property(
     ).flatten()
 }
 
+/// Record the state of the teams before an edit so it can be undone.
+fn record_team_edit(cx: &Scoped<BattleUIState>, teams_before: IndexMap<String, PetSlots>) {
+    cx.props
+        .history
+        .with_mut(|history| history.record(teams_before))
+}
+
+/// Index of the slot to select after pressing a navigation key.
+/// * Left and Up select the previous slot. Right and Down select the next slot.
+/// * Home and End select the first and last slot.
+/// * Returns `None` if the key doesn't navigate or there are no slots.
+pub fn navigate_slots(key: &Key, selected_idx: Option<usize>, num_slots: usize) -> Option<usize> {
+    let last_idx = num_slots.checked_sub(1)?;
+    let new_idx = match (key, selected_idx) {
+        (Key::Home, _) => 0,
+        (Key::End, _) => last_idx,
+        (Key::ArrowLeft | Key::ArrowUp | Key::ArrowRight | Key::ArrowDown, None) => 0,
+        (Key::ArrowLeft | Key::ArrowUp, Some(idx)) => idx.saturating_sub(1),
+        (Key::ArrowRight | Key::ArrowDown, Some(idx)) => idx + 1,
+        _ => return None,
+    };
+    Some(new_idx.min(last_idx))
+}
+
 pub fn swap_pet_on_team(
     cx: &Scoped<BattleUIState>,
     from: usize,
@@ -43,6 +68,7 @@ pub fn swap_pet_on_team(
 ) -> Result<(), Box<dyn Error>> {
     if from != to {
         let selected_team = cx.props.selected_team.get();
+        record_team_edit(cx, cx.props.teams.with(|teams| teams.clone()));
         cx.props.teams.with_mut(|teams| {
             if let Some(selected_team) = teams.get_mut(selected_team) {
                 selected_team.swap(from, to);
@@ -61,14 +87,14 @@ pub fn add_pet_to_team(
     let SAPSimpleRecord::Pet(pet) = item_info else {
         return Err("Got a food record. Cannot add item to team.".into());
     };
-    // Create pet only if selected team has less than 6 pets.
+    // Create pet only if selected team isn't full.
     let selected_team = cx.props.selected_team.get();
-    let team_size = cx
+    let num_pets = cx
         .props
         .teams
         .with(|teams| teams.get(selected_team).map(|teams| teams.len()));
 
-    if team_size.filter(|size| *size < ALLOWED_TEAM_SIZE).is_some() {
+    if num_pets.filter(|size| *size < team_size()).is_some() {
         // Add empty space if pet name is 'Slot'.
         let slot = if pet.name == "Slot" {
             None
@@ -76,6 +102,7 @@ pub fn add_pet_to_team(
             Some(pet.clone())
         };
 
+        record_team_edit(cx, cx.props.teams.with(|teams| teams.clone()));
         // Get a mut handle to the selected team pets.
         cx.props.teams.with_mut(|teams| {
             if let Some(selected_team) = teams.get_mut(selected_team) {
@@ -88,6 +115,7 @@ pub fn add_pet_to_team(
 }
 
 pub fn remove_pet_from_team(cx: &Scoped<BattleUIState>, pet_idx: usize) {
+    record_team_edit(cx, cx.props.teams.with(|teams| teams.clone()));
     cx.props.teams.with_mut(|teams| {
         if let Some(selected_team_pets) = teams.get_mut(cx.props.selected_team.get()) {
             // Remove pet from pets.
@@ -106,22 +134,46 @@ pub fn assign_pet_property<'a>(
     property: PetProperty,
 ) -> Result<(), Box<dyn Error>> {
     let selected_team = cx.props.selected_team.get();
+    let teams_before = cx.props.teams.with(|teams| teams.clone());
+    // Repeated changes to a stat are one edit.
+    let stat_name = match property {
+        PetProperty::Attack(_) => Some("Attack"),
+        PetProperty::Health(_) => Some("Health"),
+        _ => None,
+    };
 
-    cx.props.teams.with_mut(|teams| {
+    let is_changed = cx.props.teams.with_mut(|teams| {
         let Some((_, Some(selected_pet))) = teams
             .get_mut(selected_team)
             .and_then(|team| team.get_mut(selected_pet_idx))
             else
         {
-            return Err("Cannot access pet".into())
+            return Err::<bool, Box<dyn Error>>("Cannot access pet".into())
         };
-        match property {
-            PetProperty::Attack(atk) => selected_pet.attack = atk,
-            PetProperty::Health(health) => selected_pet.health = health,
-            PetProperty::Food(food) => selected_pet.item = food,
-            PetProperty::Level(lvl) => selected_pet.level = Some(lvl),
+        let is_changed = match property {
+            PetProperty::Attack(atk) => std::mem::replace(&mut selected_pet.attack, atk) != atk,
+            PetProperty::Health(health) => {
+                std::mem::replace(&mut selected_pet.health, health) != health
+            }
+            PetProperty::Food(food) => std::mem::replace(&mut selected_pet.item, food.clone()) != food,
+            PetProperty::Level(lvl) => {
+                std::mem::replace(&mut selected_pet.level, Some(lvl)) != Some(lvl)
+            }
             _ => return Err("Cannot assign value to that property".into()),
+        };
+        Ok(is_changed)
+    })?;
+
+    // Only record edits that change the pet. ex. Dragging the same item over a pet.
+    if is_changed {
+        if let Some(stat_name) = stat_name {
+            let group = format!("{selected_team}_{selected_pet_idx}_{stat_name}");
+            cx.props
+                .history
+                .with_mut(|history| history.record_grouped(teams_before, group))
+        } else {
+            record_team_edit(cx, teams_before)
         }
-        Ok(())
-    })
+    }
+    Ok(())
 }
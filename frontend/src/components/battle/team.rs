@@ -1,20 +1,83 @@
-use dioxus::prelude::*;
+use dioxus::{
+    html::input_data::keyboard_types::{Key, Modifiers},
+    prelude::*,
+};
 use log::info;
 
 use crate::{
     components::battle::{
-        state::{assign_pet_property, remove_pet_from_team, swap_pet_on_team},
+        state::{assign_pet_property, navigate_slots, remove_pet_from_team, swap_pet_on_team},
         ui::BattleUIState,
     },
-    records::{pet::PetProperty, query::retrieve_record},
+    records::{
+        pet::{PetProperty, SimplePet},
+        query::retrieve_record,
+    },
 };
 
+/// Label read by screen readers for a team slot. Position 1 is the frontmost pet.
+fn slot_label(pet: &Option<SimplePet>, position: usize) -> String {
+    let Some(pet) = pet else {
+        return format!("Position {position}: Empty");
+    };
+    let mut label = format!(
+        "Position {position}: Level {} {} with {} attack and {} health",
+        pet.level.unwrap_or(1),
+        pet.name,
+        pet.attack.unwrap_or(0),
+        pet.health.unwrap_or(0)
+    );
+    if let Some(item) = &pet.item {
+        label.push_str(&format!(", holding {item}"))
+    }
+    label
+}
+
+/// Left and Right select a pet, Shift+Left and Shift+Right move it, and Delete removes it.
+fn handle_team_shortcut<'a>(cx: Scope<'a, BattleUIState<'a>>, evt: &KeyboardData, num_pets: usize) {
+    let key = evt.key();
+    let selected_idx = *cx.props.selected_pet_idx.get();
+
+    match key {
+        Key::Delete | Key::Backspace => {
+            if let Some(idx) = selected_idx {
+                remove_pet_from_team(cx, idx)
+            }
+        }
+        Key::Escape => cx.props.selected_pet_idx.set(None),
+        _ => {
+            let Some(new_idx) = navigate_slots(&key, selected_idx, num_pets) else {
+                return;
+            };
+            match selected_idx {
+                // Move the pet. Swapping keeps it selected.
+                Some(idx) if evt.modifiers().contains(Modifiers::SHIFT) => {
+                    if let Err(err) = swap_pet_on_team(cx, idx, new_idx) {
+                        info!("{err}")
+                    }
+                }
+                _ => cx.props.selected_pet_idx.set(Some(new_idx)),
+            }
+        }
+    }
+}
+
 pub fn TeamContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
     cx.props.teams.with(|teams| {
         if let Some(selected_team_pets) = teams.get(cx.props.selected_team.get()) {
+            let num_pets = selected_team_pets.len();
+            let active_slot = cx
+                .props
+                .selected_pet_idx
+                .map_or(String::new(), |idx| format!("team-slot-{idx}"));
             cx.render(rsx! {
                 table {
                     class: "w3-table w3-responsive w3-white",
+                    tabindex: "0",
+                    "role": "listbox",
+                    "aria-label": "{cx.props.selected_team} team. Left and right arrows select a pet, Shift moves it and Delete removes it.",
+                    "aria-activedescendant": "{active_slot}",
+                    onkeydown: move |evt| handle_team_shortcut(cx, &evt.data, num_pets),
                     tr {
                         if selected_team_pets.is_empty() {
                             cx.render(rsx! {
@@ -31,10 +94,15 @@ pub fn TeamContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
                         // Pets are added in reverse order to keep frontmost pet at rightside of table row.
                         selected_team_pets.iter().enumerate().map(|(i, (pet_img_url, pet))| {
                             let title = pet.as_ref().map_or("None", |pet| &pet.name);
+                            let label = slot_label(pet, num_pets - i);
                             let is_selected = Some(i) == **cx.props.selected_pet_idx;
                             cx.render(rsx! {
                                 td {
+                                    id: "team-slot-{i}",
                                     class: if is_selected { "w3-red" } else { "" },
+                                    "role": "option",
+                                    "aria-selected": "{is_selected}",
+                                    "aria-label": "{label}",
 
                                     PetItemIcon(cx, i)
 
@@ -42,6 +110,7 @@ pub fn TeamContainer<'a>(cx: Scope<'a, BattleUIState<'a>>) -> Element {
                                         class: "w3-image",
                                         src: "{pet_img_url}",
                                         title: "{title}",
+                                        alt: "{title}",
                                         // Starting pet.
                                         ondragstart: move |_| cx.props.selected_pet_idx.set(Some(i)),
                                         // Assign item to pet.
@@ -100,6 +169,7 @@ fn PetItemIcon<'a>(cx: Scope<'a, BattleUIState<'a>>, pet_idx: usize) -> Element<
             style: "width: 15%;height: 15%;float: left;",
             src: "{img_url}",
             title: "{pet_item}",
+            alt: "Holding {pet_item}",
             // On item double click, remove item.
             ondblclick: move |_| {
                 if let Err(err) = assign_pet_property(cx, pet_idx, PetProperty::Food(None)) {
@@ -1,23 +1,26 @@
 use dioxus::prelude::*;
+use dioxus_router::use_route;
 use indexmap::IndexMap;
 use std::collections::{HashMap, VecDeque};
 
 use crate::{
     components::{
         battle::{
+            compare::CompareSummary,
             fight::FightSummary,
+            history::{use_history_shortcuts, HistoryToolbar, TeamHistory},
             item_selection::{GameItemsContainer, GameItemsFilterContainer},
             selected_pet::PetAttrContainer,
             team::TeamContainer,
-            ALLOWED_TEAM_SIZE,
         },
+        encyclopedia::segment_to_name,
         tabs::TabContainer,
     },
-    records::pet::SimplePet,
+    records::{meta::team_size, pet::SimplePet, query::retrieve_record, record::SAPSimpleRecord},
 };
 
-pub const FILTER_FIELDS: [&str; 3] = ["Name", "Tier", "Pack"];
-pub const FILTER_FIELD_DEFAULTS: [&str; 3] = ["", "1", "Turtle"];
+pub const FILTER_FIELDS: [&str; 4] = ["Name", "Tier", "Pack", "Trigger"];
+pub const FILTER_FIELD_DEFAULTS: [&str; 4] = ["", "1", "Turtle", "All"];
 
 pub type PetSlots = VecDeque<(String, Option<SimplePet>)>;
 
@@ -29,6 +32,7 @@ pub struct BattleUIState<'a> {
     pub selected_pet_attr: &'a UseState<String>,
     pub filters: &'a UseRef<HashMap<&'static str, String>>,
     pub teams: &'a UseRef<IndexMap<String, PetSlots>>,
+    pub history: &'a UseRef<TeamHistory>,
 }
 
 pub fn Battle(cx: Scope) -> Element {
@@ -41,19 +45,27 @@ pub fn Battle(cx: Scope) -> Element {
         let field_values = FILTER_FIELD_DEFAULTS.map(|field| field.to_owned());
         HashMap::<&str, String>::from_iter(FILTER_FIELDS.into_iter().zip(field_values.into_iter()))
     });
+    // Pet to start the friend team with. Set by the "Use in Battle" link of a pet page.
+    let start_pet = use_route(cx)
+        .query_param("pet")
+        .map(|pet_id| segment_to_name(&pet_id));
     // Stored state for pets.
     let team_pets = use_ref(cx, || {
         let mut teams = IndexMap::<String, PetSlots>::new();
-        teams.insert(
-            String::from("Friend"),
-            VecDeque::with_capacity(ALLOWED_TEAM_SIZE),
-        );
-        teams.insert(
-            String::from("Enemy"),
-            VecDeque::with_capacity(ALLOWED_TEAM_SIZE),
-        );
+        let mut friends = VecDeque::with_capacity(team_size());
+        if let Some(SAPSimpleRecord::Pet(pet)) = start_pet
+            .as_deref()
+            .and_then(|pet_id| retrieve_record("Pets", pet_id))
+        {
+            friends.push_front((pet.img_url.clone(), Some(pet.clone())))
+        }
+        teams.insert(String::from("Friend"), friends);
+        teams.insert(String::from("Enemy"), VecDeque::with_capacity(team_size()));
         teams
     });
+    // Previous team states for undo/redo.
+    let team_history = use_ref(cx, TeamHistory::default);
+    use_history_shortcuts(cx, team_pets, team_history, selected_pet_idx);
 
     let team_container_component = || {
         cx.render(rsx! {
@@ -63,7 +75,8 @@ pub fn Battle(cx: Scope) -> Element {
                 selected_pet_idx: selected_pet_idx,
                 selected_pet_attr: selected_pet_property,
                 filters: selected_filters,
-                teams: team_pets
+                teams: team_pets,
+                history: team_history
             }
         })
     };
@@ -75,7 +88,8 @@ pub fn Battle(cx: Scope) -> Element {
                 selected_pet_idx: selected_pet_idx,
                 selected_pet_attr: selected_pet_property,
                 filters: selected_filters,
-                teams: team_pets
+                teams: team_pets,
+                history: team_history
             }
         })
     };
@@ -90,44 +104,58 @@ pub fn Battle(cx: Scope) -> Element {
         (String::from("Food"), pet_attr_component()),
     ];
     cx.render(rsx! {
-        div { class: "w3-container",
-            div { class: "w3-container w3-half",
-                TabContainer {
-                    desc: "Team",
-                    selected_tab: selected_team,
-                    tabs: IndexMap::from_iter(team_containers)
+        HistoryToolbar {
+            selected_team: selected_team,
+            selected_item: selected_item,
+            selected_pet_idx: selected_pet_idx,
+            selected_pet_attr: selected_pet_property,
+            filters: selected_filters,
+            teams: team_pets,
+            history: team_history
+        }
+
+        div {
+            div { class: "w3-container",
+                div { class: "w3-container w3-half",
+                    TabContainer {
+                        desc: "Team",
+                        selected_tab: selected_team,
+                        tabs: IndexMap::from_iter(team_containers)
+                    }
                 }
-            }
-            div { class: "w3-container w3-half",
-                TabContainer {
-                    desc: "Current Pet",
-                    selected_tab: selected_pet_property,
-                    tabs: IndexMap::from_iter(pet_attr_containers)
+                div { class: "w3-container w3-half",
+                    TabContainer {
+                        desc: "Current Pet",
+                        selected_tab: selected_pet_property,
+                        tabs: IndexMap::from_iter(pet_attr_containers)
+                    }
                 }
             }
-        }
 
-        br {}
+            br {}
 
-        div { class: "w3-container",
-            div { class: "w3-container w3-threequarter",
-                GameItemsContainer {
-                    selected_team: selected_team,
-                    selected_item: selected_item,
-                    selected_pet_idx: selected_pet_idx,
-                    selected_pet_attr: selected_pet_property,
-                    filters: selected_filters,
-                    teams: team_pets
+            div { class: "w3-container",
+                div { class: "w3-container w3-threequarter",
+                    GameItemsContainer {
+                        selected_team: selected_team,
+                        selected_item: selected_item,
+                        selected_pet_idx: selected_pet_idx,
+                        selected_pet_attr: selected_pet_property,
+                        filters: selected_filters,
+                        teams: team_pets,
+                        history: team_history
+                    }
                 }
-            }
-            div { class: "w3-container w3-quarter w3-leftbar",
-                GameItemsFilterContainer {
-                    selected_team: selected_team,
-                    selected_item: selected_item,
-                    selected_pet_idx: selected_pet_idx,
-                    selected_pet_attr: selected_pet_property,
-                    filters: selected_filters,
-                    teams: team_pets
+                div { class: "w3-container w3-quarter w3-leftbar",
+                    GameItemsFilterContainer {
+                        selected_team: selected_team,
+                        selected_item: selected_item,
+                        selected_pet_idx: selected_pet_idx,
+                        selected_pet_attr: selected_pet_property,
+                        filters: selected_filters,
+                        teams: team_pets,
+                        history: team_history
+                    }
                 }
             }
         }
@@ -140,7 +168,20 @@ pub fn Battle(cx: Scope) -> Element {
             selected_pet_idx: selected_pet_idx,
             selected_pet_attr: selected_pet_property,
             filters: selected_filters,
-            teams: team_pets
+            teams: team_pets,
+            history: team_history
+        }
+
+        br {}
+
+        CompareSummary {
+            selected_team: selected_team,
+            selected_item: selected_item,
+            selected_pet_idx: selected_pet_idx,
+            selected_pet_attr: selected_pet_property,
+            filters: selected_filters,
+            teams: team_pets,
+            history: team_history
         }
 
         // To prevent footer overlap.
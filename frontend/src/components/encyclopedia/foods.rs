@@ -0,0 +1,119 @@
+use dioxus::prelude::*;
+use dioxus_router::{use_route, Link};
+use itertools::Itertools;
+
+use crate::{
+    components::{
+        encyclopedia::{name_to_segment, segment_to_name, RecordsUnavailable},
+        status::use_records_state,
+    },
+    RECORDS,
+};
+
+pub fn FoodsPage(cx: Scope) -> Element {
+    // Re-render once records are loaded.
+    use_records_state(cx);
+    let Some(foods) = RECORDS.get().and_then(|records| records.get("Foods")) else {
+        return cx.render(rsx! { RecordsUnavailable {} });
+    };
+    let foods = foods
+        .values()
+        .unique_by(|food| food.name())
+        .sorted_by_key(|food| (food.tier(), food.name()))
+        .collect_vec();
+
+    cx.render(rsx! {
+        div { class: "w3-container",
+            h1 { "Foods" }
+            div { class: "w3-row-padding",
+                foods.into_iter().map(|food| {
+                    let name = food.name();
+                    let food_path = format!("/foods/{}", name_to_segment(&name));
+                    rsx! {
+                        div { class: "w3-col s4 m2 w3-center",
+                            Link { to: "{food_path}",
+                                img {
+                                    class: "w3-image w3-hover-opacity",
+                                    src: "{food.img_url()}",
+                                    title: "{name}"
+                                }
+                                p { "{name}" }
+                            }
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
+
+pub fn FoodPage(cx: Scope) -> Element {
+    // Re-render once records are loaded.
+    use_records_state(cx);
+    let route = use_route(cx);
+    let Some(name) = route.segment("name").map(segment_to_name) else {
+        return None;
+    };
+    let Some(foods) = RECORDS.get().and_then(|records| records.get("Foods")) else {
+        return cx.render(rsx! { RecordsUnavailable {} });
+    };
+    let food_records = foods
+        .values()
+        .filter(|food| food.name() == name)
+        .collect_vec();
+    let Some(food) = food_records.first() else {
+        return cx.render(rsx! {
+            div { class: "w3-container",
+                h2 { "No food named {name}." }
+            }
+        });
+    };
+
+    let packs = food_records
+        .iter()
+        .map(|food| food.pack())
+        .unique()
+        .join(", ");
+    let holdable = if food.holdable() { "Yes" } else { "No" };
+    let (effect, single_use) = food
+        .effect()
+        .map(|effect| (effect.text, if effect.uses == 1 { "Yes" } else { "No" }))
+        .unwrap_or_default();
+
+    cx.render(rsx! {
+        div { class: "w3-container",
+            div { class: "w3-row",
+                div { class: "w3-col m3 w3-center",
+                    img { class: "w3-image", src: "{food.img_url()}", title: "{name}" }
+                    Link { class: "w3-button w3-black", to: "/foods", "All Foods" }
+                }
+                div { class: "w3-col m9",
+                    h1 { "{name}" }
+                    table { class: "w3-table w3-bordered w3-responsive",
+                        tr {
+                            th { "Tier" }
+                            td { "{food.tier()}" }
+                        }
+                        tr {
+                            th { "Pack" }
+                            td { "{packs}" }
+                        }
+                        tr {
+                            th { "Holdable" }
+                            td { "{holdable}" }
+                        }
+                        tr {
+                            th { "Effect" }
+                            td { "{effect}" }
+                        }
+                        tr {
+                            th { "Single Use" }
+                            td { "{single_use}" }
+                        }
+                    }
+                }
+            }
+            br {}
+        }
+    })
+}
@@ -0,0 +1,37 @@
+pub mod foods;
+pub mod pets;
+
+use dioxus::prelude::*;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::components::status::{use_records_state, ErrorMsg, LoadingMsg, RequestState};
+
+/// Characters escaped in a url path segment. Everything but unreserved characters.
+const SEGMENT_ESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Convert an item name to a url path segment.
+/// ex. Zombie Cricket -> Zombie%20Cricket
+pub fn name_to_segment(name: &str) -> String {
+    utf8_percent_encode(name, SEGMENT_ESCAPED).to_string()
+}
+
+/// Convert a url path segment back to an item name.
+pub fn segment_to_name(segment: &str) -> String {
+    percent_decode_str(segment).decode_utf8_lossy().into_owned()
+}
+
+pub fn RecordsUnavailable(cx: Scope) -> Element {
+    match use_records_state(cx) {
+        RequestState::Loading => LoadingMsg(cx, "Loading records..."),
+        RequestState::Failed(err) => ErrorMsg(cx, &format!("Unable to load records. {err}")),
+        RequestState::Idle | RequestState::Done(_) => cx.render(rsx! {
+            div { class: "w3-container w3-panel",
+                "Records could not be retrieved from the API."
+            }
+        }),
+    }
+}
@@ -0,0 +1,150 @@
+use dioxus::prelude::*;
+use dioxus_router::{use_route, Link};
+use itertools::Itertools;
+
+use crate::{
+    components::{
+        encyclopedia::{name_to_segment, segment_to_name, RecordsUnavailable},
+        status::use_records_state,
+    },
+    RECORDS,
+};
+
+pub fn PetsPage(cx: Scope) -> Element {
+    // Re-render once records are loaded.
+    use_records_state(cx);
+    let Some(pets) = RECORDS.get().and_then(|records| records.get("Pets")) else {
+        return cx.render(rsx! { RecordsUnavailable {} });
+    };
+    // Only show one level of each pet.
+    let pets = pets
+        .values()
+        .filter(|pet| pet.level() == Some(1) && pet.name() != "Slot")
+        .unique_by(|pet| pet.name())
+        .sorted_by_key(|pet| (pet.tier(), pet.name()))
+        .collect_vec();
+
+    cx.render(rsx! {
+        div { class: "w3-container",
+            h1 { "Pets" }
+            div { class: "w3-row-padding",
+                pets.into_iter().map(|pet| {
+                    let name = pet.name();
+                    let pet_path = format!("/pets/{}", name_to_segment(&name));
+                    rsx! {
+                        div { class: "w3-col s4 m2 w3-center",
+                            Link { to: "{pet_path}",
+                                img {
+                                    class: "w3-image w3-hover-opacity",
+                                    src: "{pet.img_url()}",
+                                    title: "{name}"
+                                }
+                                p { "{name}" }
+                            }
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
+
+pub fn PetPage(cx: Scope) -> Element {
+    // Re-render once records are loaded.
+    use_records_state(cx);
+    let route = use_route(cx);
+    let Some(name) = route.segment("name").map(segment_to_name) else {
+        return None;
+    };
+    let Some(pets) = RECORDS.get().and_then(|records| records.get("Pets")) else {
+        return cx.render(rsx! { RecordsUnavailable {} });
+    };
+    let pet_records = pets.values().filter(|pet| pet.name() == name).collect_vec();
+    let Some(pet) = pet_records.first() else {
+        return cx.render(rsx! {
+            div { class: "w3-container",
+                h2 { "No pet named {name}." }
+            }
+        });
+    };
+
+    let packs = pet_records.iter().map(|pet| pet.pack()).unique().join(", ");
+    let trigger = pet
+        .effect()
+        .map_or(String::from("None"), |effect| effect.trigger);
+    // Levels of the pet from a single pack.
+    let pack = pet.pack();
+    let levels = pet_records
+        .iter()
+        .filter(|pet| pet.pack() == pack)
+        .sorted_by_key(|pet| pet.level())
+        .collect_vec();
+    let battle_path = format!("/battle?pet={}_{pack}_1", name_to_segment(&name));
+
+    cx.render(rsx! {
+        div { class: "w3-container",
+            div { class: "w3-row",
+                div { class: "w3-col m3 w3-center",
+                    img { class: "w3-image", src: "{pet.img_url()}", title: "{name}" }
+                    Link { class: "w3-button w3-red", to: "{battle_path}", "Use in Battle" }
+                }
+                div { class: "w3-col m9",
+                    h1 { "{name}" }
+                    table { class: "w3-table w3-responsive",
+                        tr {
+                            th { "Tier" }
+                            td { "{pet.tier()}" }
+                        }
+                        tr {
+                            th { "Pack" }
+                            td { "{packs}" }
+                        }
+                        tr {
+                            th { "Trigger" }
+                            td { "{trigger}" }
+                        }
+                    }
+                }
+            }
+            br {}
+            table { class: "w3-table w3-bordered w3-striped w3-responsive",
+                tr {
+                    th { "Level" }
+                    levels.iter().map(|pet| {
+                        let lvl = pet.level().unwrap_or(1);
+                        rsx! { th { "{lvl}" } }
+                    })
+                }
+                tr {
+                    th { "Attack" }
+                    levels.iter().map(|pet| {
+                        let attack = pet.attack().unwrap_or_default();
+                        rsx! { td { "{attack}" } }
+                    })
+                }
+                tr {
+                    th { "Health" }
+                    levels.iter().map(|pet| {
+                        let health = pet.health().unwrap_or_default();
+                        rsx! { td { "{health}" } }
+                    })
+                }
+                tr {
+                    th { "Effect" }
+                    levels.iter().map(|pet| {
+                        let effect = pet.effect().map(|effect| effect.text).unwrap_or_default();
+                        rsx! { td { "{effect}" } }
+                    })
+                }
+                tr {
+                    th { "Uses" }
+                    levels.iter().map(|pet| {
+                        let uses = pet.effect().map(|effect| effect.uses).unwrap_or_default();
+                        rsx! { td { "{uses}" } }
+                    })
+                }
+            }
+            br {}
+        }
+    })
+}
@@ -1,9 +1,11 @@
 use dioxus::prelude::*;
 
-const SAP_LOGO: &str =
-    "https://static.wikia.nocookie.net/superautopets/images/5/5d/Super_Auto_Pets_Header.jpg";
+use crate::records::query::asset_url;
+
+const SAP_LOGO: &str = "/assets/img/sap_header.jpg";
 
 pub fn Home(cx: Scope) -> Element {
+    let sap_logo = asset_url(SAP_LOGO);
     cx.render(rsx! {
         div { class: "w3-container w3-white",
 
@@ -11,7 +13,7 @@ pub fn Home(cx: Scope) -> Element {
                 h1 { class: "w3-text-black", "Welcome to SAPTester!" }
                 h3 {
                     "A site to simulate battles in	"
-                    img { class: "w3-image w3-round", src: "{SAP_LOGO}", border: "2px solid" }
+                    img { class: "w3-image w3-round", src: "{sap_logo}", alt: "Super Auto Pets", border: "2px solid" }
                 }
 
                 div { class: "w3-panel w3-large w3-text-red",
@@ -1,6 +1,9 @@
 pub mod about;
 pub mod battle;
+pub mod encyclopedia;
 pub mod footer;
 pub mod home;
 pub mod nav;
+pub mod settings;
+pub mod status;
 pub mod tabs;
@@ -13,11 +13,26 @@ pub fn Nav(cx: Scope) -> Element {
                     to: "/battle",
                     "Battle"
                 }
+                Link {
+                    class: "w3-bar-item w3-button w3-hover-white",
+                    to: "/pets",
+                    "Pets"
+                }
+                Link {
+                    class: "w3-bar-item w3-button w3-hover-white",
+                    to: "/foods",
+                    "Foods"
+                }
                 Link {
                     class: "w3-bar-item w3-button w3-hover-white",
                     to: "/about",
                     "About"
                 }
+                Link {
+                    class: "w3-bar-item w3-button w3-hover-white",
+                    to: "/settings",
+                    "Settings"
+                }
                 Link {
                     to: SAPTESTER_URL,
                     external: true,
@@ -0,0 +1,117 @@
+use dioxus::prelude::*;
+
+use crate::records::query::{
+    backend_url, check_backend, custom_backend_url, set_custom_backend_url,
+};
+
+/// Result of testing a backend url. Shown below the url input.
+#[derive(Debug, Clone, PartialEq)]
+enum CheckState {
+    Unchecked,
+    Checking,
+    Ok(String),
+    Failed(String),
+}
+
+/// Reload the page so records are fetched from the new backend.
+fn reload_page() {
+    if let Some(window) = web_sys::window() {
+        let _ = window.location().reload();
+    }
+}
+
+fn CheckStatus<'a>(cx: Scope<'a>, check_state: &'a UseState<CheckState>) -> Element<'a> {
+    let (class, msg) = match check_state.get() {
+        CheckState::Unchecked => return None,
+        CheckState::Checking => ("w3-pale-yellow", String::from("Checking backend...")),
+        CheckState::Ok(msg) => ("w3-pale-green", msg.to_owned()),
+        CheckState::Failed(msg) => ("w3-pale-red", msg.to_owned()),
+    };
+
+    cx.render(rsx! {
+        div { class: "w3-panel {class}", role: "status", p { "{msg}" } }
+    })
+}
+
+pub fn Settings(cx: Scope) -> Element {
+    let url_input = use_state(cx, || custom_backend_url().unwrap_or_default());
+    let check_state = use_state(cx, || CheckState::Unchecked);
+    let current_url = backend_url();
+    let is_custom = custom_backend_url().is_some();
+
+    // Check the backend and store its url if it's ready.
+    let check_url = move |save: bool| {
+        let url = url_input.trim().to_owned();
+        if url.is_empty() {
+            check_state.set(CheckState::Failed(String::from("Enter a backend url.")));
+            return;
+        }
+        check_state.set(CheckState::Checking);
+        cx.spawn({
+            let check_state = check_state.to_owned();
+            async move {
+                match check_backend(&url).await {
+                    Ok(_) if save => match set_custom_backend_url(Some(&url)) {
+                        Ok(()) => reload_page(),
+                        Err(err) => check_state.set(CheckState::Failed(err.to_string())),
+                    },
+                    Ok(status) => check_state.set(CheckState::Ok(format!(
+                        "Connected. Backend has {} pets and {} foods.",
+                        status.pets, status.foods
+                    ))),
+                    Err(err) => check_state.set(CheckState::Failed(format!(
+                        "Unable to use backend at {url}: {err}"
+                    ))),
+                }
+            }
+        });
+    };
+
+    cx.render(rsx! {
+        div { class: "w3-container",
+            h2 { "Settings" }
+            h4 { "Backend" }
+            p { "Requests are sent to " code { "{current_url}" } "." }
+            if is_custom {
+                rsx! { p { "This is a custom backend. Reset to use the default backend." } }
+            }
+            label { r#for: "backend-url", "Custom backend url" }
+            input {
+                id: "backend-url",
+                class: "w3-input w3-border",
+                r#type: "url",
+                placeholder: "https://saptest.fly.dev",
+                "aria-label": "Custom backend url",
+                value: "{url_input}",
+                oninput: move |evt| {
+                    url_input.set(evt.value.clone());
+                    check_state.set(CheckState::Unchecked)
+                }
+            }
+            CheckStatus(cx, check_state)
+            br {}
+            div { class: "w3-bar",
+                button {
+                    class: "w3-button w3-grey w3-margin-right",
+                    onclick: move |_| check_url(false),
+                    "Test"
+                }
+                button {
+                    class: "w3-button w3-black w3-margin-right",
+                    onclick: move |_| check_url(true),
+                    "Save"
+                }
+                button {
+                    class: "w3-button w3-red",
+                    disabled: "{!is_custom}",
+                    onclick: move |_| match set_custom_backend_url(None) {
+                        Ok(()) => reload_page(),
+                        Err(err) => check_state.set(CheckState::Failed(err.to_string())),
+                    },
+                    "Reset"
+                }
+            }
+            p { class: "w3-small", "Saving checks the backend first and reloads the page." }
+        }
+    })
+}
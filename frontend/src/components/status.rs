@@ -0,0 +1,40 @@
+use dioxus::prelude::*;
+
+/// State of a request to the backend.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum RequestState<T> {
+    #[default]
+    Idle,
+    Loading,
+    Done(T),
+    /// Failed with an error message to show the user.
+    Failed(String),
+}
+
+/// State of loading records on app init. Shared from the app root.
+pub type RecordsState = RequestState<()>;
+
+/// Current state of loading records.
+/// * Components calling this re-render once records are loaded.
+pub fn use_records_state(cx: &ScopeState) -> RecordsState {
+    use_shared_state::<RecordsState>(cx)
+        .map(|state| state.read().clone())
+        .unwrap_or_default()
+}
+
+pub fn LoadingMsg<'a, P>(cx: Scope<'a, P>, msg: &str) -> Element<'a> {
+    cx.render(rsx! {
+        div { class: "w3-panel w3-center", role: "status", "aria-live": "polite",
+            div { class: "spinner" }
+            p { "{msg}" }
+        }
+    })
+}
+
+pub fn ErrorMsg<'a, P>(cx: Scope<'a, P>, msg: &str) -> Element<'a> {
+    cx.render(rsx! {
+        div { class: "w3-panel w3-pale-red", role: "alert",
+            p { "{msg}" }
+        }
+    })
+}
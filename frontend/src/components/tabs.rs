@@ -9,17 +9,39 @@ pub struct TabState<'a> {
 }
 
 pub fn TabContainer<'a>(cx: Scope<'a, TabState<'a>>) -> Element {
+    // Dropdown also opens on click so tabs can be reached with the keyboard.
+    let is_open = use_state(cx, || false);
+    let dropdown_class = if **is_open {
+        "w3-dropdown-content w3-show"
+    } else {
+        "w3-dropdown-content"
+    };
+
     cx.render(rsx! {
         div { class: "w3-container",
             div { class: "w3-dropdown-hover",
-                button { class: "w3-button", "{cx.props.desc}" }
-                div { class: "w3-dropdown-content",
+                button {
+                    class: "w3-button",
+                    "aria-haspopup": "true",
+                    "aria-expanded": "{is_open}",
+                    onclick: move |_| is_open.set(!is_open.get()),
+                    "{cx.props.desc}"
+                }
+                div { class: dropdown_class,
+                    "role": "tablist",
+                    "aria-label": "{cx.props.desc}",
 
                     cx.props.tabs.keys().map(|tab| {
+                        let is_selected = tab == cx.props.selected_tab.get();
                         cx.render(rsx! {
                             button {
                                 class: "w3-button",
-                                onclick: move |_| cx.props.selected_tab.set(tab.clone()),
+                                "role": "tab",
+                                "aria-selected": "{is_selected}",
+                                onclick: move |_| {
+                                    cx.props.selected_tab.set(tab.clone());
+                                    is_open.set(false)
+                                },
                                 "{tab}"
                             }
                             br {}
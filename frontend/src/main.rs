@@ -9,10 +9,28 @@ use indexmap::IndexMap;
 use once_cell::sync::OnceCell;
 
 use crate::{
-    components::{about::About, battle::ui::Battle, footer::Footer, home::Home, nav::Nav},
-    records::query::{get_all_sap_records, ItemRecords},
+    components::{
+        about::About,
+        battle::ui::Battle,
+        encyclopedia::{
+            foods::{FoodPage, FoodsPage},
+            pets::{PetPage, PetsPage},
+        },
+        footer::Footer,
+        home::Home,
+        nav::Nav,
+        settings::Settings,
+        status::{use_records_state, ErrorMsg, LoadingMsg, RecordsState, RequestState},
+    },
+    records::{
+        meta::DbMeta,
+        query::{get_all_sap_records, init_backend_url, retry_with_backoff, ItemRecords},
+    },
 };
 
+/// Bundled so the site doesn't depend on external stylesheets.
+const STYLESHEET: &str = include_str!("../assets/style.css");
+
 pub const DEV_BACKEND_API_URL: &str = "http://127.0.0.1:3030";
 pub const BACKEND_API_URL: &str = "https://saptest.fly.dev";
 pub const SAPTEST_URL: &str = "https://github.com/koisland/SuperAutoTest";
@@ -21,6 +39,7 @@ pub const SAPAI_URL: &str = "https://github.com/manny405/sapai";
 
 pub type SAPRecords = IndexMap<String, ItemRecords>;
 static RECORDS: OnceCell<SAPRecords> = OnceCell::new();
+static META: OnceCell<DbMeta> = OnceCell::new();
 
 fn main() {
     // Init debug tool for WebAssembly.
@@ -38,24 +57,73 @@ fn AppRoutes(cx: Scope) -> Element {
             }
             Route { to: "/home", Home {} }
             Route { to: "/battle", Battle {} }
+            Route { to: "/pets", PetsPage {} }
+            Route { to: "/pets/:name", PetPage {} }
+            Route { to: "/foods", FoodsPage {} }
+            Route { to: "/foods/:name", FoodPage {} }
             Route { to: "/about", About {} }
+            Route { to: "/settings", Settings {} }
             Redirect { from: "", to: "/home" }
             Footer {}
         }
     })
 }
 
+/// Loading or error message for records. Shown at the bottom of every page.
+fn RecordsStatus<'a>(cx: Scope<'a>, records_future: &'a UseFuture<()>) -> Element<'a> {
+    let records_state = use_records_state(cx);
+    let is_failed = matches!(records_state, RequestState::Failed(_));
+    let status_msg = match records_state {
+        RequestState::Loading => LoadingMsg(cx, "Loading pets and foods..."),
+        RequestState::Failed(err) => ErrorMsg(cx, &format!("Unable to load pets and foods. {err}")),
+        RequestState::Idle | RequestState::Done(_) => return None,
+    };
+
+    cx.render(rsx! {
+        div { class: "w3-bottom w3-white",
+            status_msg,
+            is_failed.then(|| cx.render(rsx! {
+                div { class: "w3-container w3-padding",
+                    button {
+                        class: "w3-button w3-black",
+                        onclick: move |_| records_future.restart(),
+                        "Retry"
+                    }
+                }
+            }))
+        }
+    })
+}
+
 pub fn App(cx: Scope) -> Element {
+    use_shared_state_provider(cx, || RecordsState::Loading);
+    let records_state = use_shared_state::<RecordsState>(cx).map(|state| state.clone());
+
     // Get all SAP records from backend on app init.
-    if let Some(Ok(item_img_urls)) =
-        use_future(cx, (), |_| async move { get_all_sap_records().await }).value()
-    {
-        let _ = RECORDS.set(item_img_urls.to_owned());
-    };
+    let records_future = use_future(cx, (), move |_| async move {
+        let set_state = |state: RecordsState| {
+            if let Some(records_state) = &records_state {
+                *records_state.write() = state
+            }
+        };
+        set_state(RequestState::Loading);
+        init_backend_url().await;
+
+        match retry_with_backoff(get_all_sap_records).await {
+            Ok((item_img_urls, meta)) => {
+                let _ = RECORDS.set(item_img_urls);
+                let _ = META.set(meta);
+                set_state(RequestState::Done(()))
+            }
+            Err(err) => set_state(RequestState::Failed(err.to_string())),
+        }
+    });
 
     cx.render(rsx! {
-        link { rel: "stylesheet", href: "https://www.w3schools.com/w3css/4/w3.css" }
-        link { rel: "stylesheet", href: "https://fonts.googleapis.com/css?family=Raleway" }
-        body { class: "w3-white", AppRoutes {} }
+        style { "{STYLESHEET}" }
+        body { class: "w3-white",
+            AppRoutes {}
+            RecordsStatus(cx, records_future)
+        }
     })
 }
@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+/// `localStorage` key of the database version cached records are from.
+const RECORDS_VERSION_KEY: &str = "saptester_records_version";
+/// Prefix of `localStorage` keys of cached records. Suffixed with their category.
+const RECORDS_KEY_PREFIX: &str = "saptester_records_";
+
+/// Version of the backend's database. Returned by its `/db/version` route.
+#[derive(Debug, Deserialize)]
+pub struct DbVersion {
+    pub version: String,
+    pub updated: u64,
+}
+
+pub fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+}
+
+/// Records are cached per backend as each can have a different database.
+fn cache_version(backend_url: &str, version: &DbVersion) -> String {
+    format!("{backend_url}#{}", version.version)
+}
+
+/// Get the cached JSON of a record category if it's from the given database version.
+pub fn cached_records(backend_url: &str, version: &DbVersion, categ: &str) -> Option<String> {
+    let storage = local_storage()?;
+    let cached_version = storage.get_item(RECORDS_VERSION_KEY).ok().flatten()?;
+    if cached_version != cache_version(backend_url, version) {
+        return None;
+    }
+    storage
+        .get_item(&format!("{RECORDS_KEY_PREFIX}{categ}"))
+        .ok()
+        .flatten()
+}
+
+/// Cache the JSON of a record category.
+/// * Records of older database versions are cleared. Storage errors are only logged.
+pub fn cache_records(backend_url: &str, version: &DbVersion, categ: &str, records_json: &str) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    let new_version = cache_version(backend_url, version);
+    let cached_version = storage.get_item(RECORDS_VERSION_KEY).ok().flatten();
+
+    if cached_version.as_ref() != Some(&new_version) {
+        // Remove records of the old version first so versions are never mixed.
+        for old_categ in ["pets", "foods", "meta"] {
+            let _ = storage.remove_item(&format!("{RECORDS_KEY_PREFIX}{old_categ}"));
+        }
+        if storage.set_item(RECORDS_VERSION_KEY, &new_version).is_err() {
+            log::warn!("Unable to cache records version.");
+            return;
+        }
+    }
+    if storage
+        .set_item(&format!("{RECORDS_KEY_PREFIX}{categ}"), records_json)
+        .is_err()
+    {
+        log::warn!("Unable to cache {categ}. Local storage may be full.");
+    }
+}
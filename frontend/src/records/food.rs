@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::error::Error;
 
-use super::effect::SimpleEffect;
+use super::{effect::SimpleEffect, query::asset_url};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SimpleFood {
@@ -25,7 +25,7 @@ impl TryFrom<&Value> for SimpleFood {
         let img_url = value
             .get("img_url")
             .and_then(|url| url.as_str())
-            .map(|url| url.to_string());
+            .map(asset_url);
         let tier = value.get("tier").and_then(|tier| tier.as_u64());
         let holdable = value
             .get("holdable")
@@ -0,0 +1,42 @@
+use indexmap::IndexMap;
+use std::error::Error;
+
+use crate::{
+    components::battle::{
+        fight::{BattleResponse, ACCEPTED_STATUS},
+        ui::PetSlots,
+    },
+    records::query::build_teams,
+};
+
+/// Seed for a battle fought in the browser. Time since the page loaded in microseconds.
+pub fn local_seed() -> u64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0, |performance| (performance.now() * 1000.0) as u64)
+}
+
+/// Fight the teams in the browser with the simplified battle core in [`sim`]. No request is made to the backend.
+/// * Pet abilities and foods are only partially modeled so the outcome can differ from the backend's.
+pub fn fight_locally(
+    teams: IndexMap<String, PetSlots>,
+    seed: u64,
+) -> Result<BattleResponse, Box<dyn Error>> {
+    // Same format as the backend's battle request.
+    let teams: sim::Teams = serde_json::from_value(serde_json::to_value(build_teams(teams)?)?)?;
+    let result = sim::fight(&teams, seed)?;
+
+    Ok(BattleResponse {
+        id: None,
+        status: Some(ACCEPTED_STATUS.to_owned()),
+        outcome: format!("{:?}", result.outcome),
+        friend_team: Some(serde_json::to_value(result.friend_team)?),
+        enemy_team: Some(serde_json::to_value(result.enemy_team)?),
+        num_turns: result.num_turns,
+        seed: Some(result.seed),
+        digraph: None,
+        pet_stats: Some(result.pet_stats),
+        turns: None,
+        unmodeled: result.unmodeled,
+    })
+}
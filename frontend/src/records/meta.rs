@@ -0,0 +1,46 @@
+use serde::Deserialize;
+use std::ops::RangeInclusive;
+
+use crate::META;
+
+/// Pets per team until the backend's limit is known.
+pub const DEFAULT_TEAM_SIZE: usize = 5;
+/// Levels a pet can be until the backend's limits are known.
+const DEFAULT_PET_LEVELS: RangeInclusive<u64> = 1..=3;
+
+/// Inclusive range of allowed values.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Bounds<T> {
+    pub min: T,
+    pub max: T,
+}
+
+/// Values accepted by the backend. Returned by its `/db/meta` route.
+#[derive(Debug, Deserialize)]
+pub struct DbMeta {
+    pub pets: Vec<String>,
+    pub foods: Vec<String>,
+    /// Foods pets can hold.
+    pub items: Vec<String>,
+    pub packs: Vec<String>,
+    pub effect_triggers: Vec<String>,
+    pub tiers: Bounds<u64>,
+    pub levels: Bounds<u64>,
+    pub stats: Bounds<u64>,
+    pub team_size: usize,
+}
+
+/// Backend metadata. Only set once records are loaded.
+pub fn db_meta() -> Option<&'static DbMeta> {
+    META.get()
+}
+
+/// Pets per team allowed by the backend.
+pub fn team_size() -> usize {
+    db_meta().map_or(DEFAULT_TEAM_SIZE, |meta| meta.team_size)
+}
+
+/// Levels a pet can be.
+pub fn pet_levels() -> RangeInclusive<u64> {
+    db_meta().map_or(DEFAULT_PET_LEVELS, |meta| meta.levels.min..=meta.levels.max)
+}
@@ -1,6 +1,10 @@
+pub mod cache;
 pub mod effect;
 pub mod food;
+pub mod local;
+pub mod meta;
 pub mod pet;
 pub mod query;
 pub mod record;
+pub mod snapshot;
 pub mod team;
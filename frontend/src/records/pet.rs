@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::error::Error;
 
-use crate::records::effect::SimpleEffect;
+use crate::records::{effect::SimpleEffect, query::asset_url};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PetProperty {
@@ -22,6 +22,11 @@ pub struct SimplePet {
     pub health: Option<u64>,
     pub level: Option<u64>,
     pub item: Option<String>,
+    /// Slot of the pet in a battle request. 0 is the front of the team.
+    pub position: Option<usize>,
+    /// Stats only kept for one battle.
+    pub temp_attack: Option<u64>,
+    pub temp_health: Option<u64>,
     #[serde(skip_serializing)]
     pub tier: u64,
     #[serde(skip_serializing)]
@@ -43,7 +48,7 @@ impl TryFrom<&Value> for SimplePet {
         let img_url = value
             .get("img_url")
             .and_then(|url| url.as_str())
-            .map(|url| url.to_string());
+            .map(asset_url);
 
         let attack = value.get("attack").and_then(|attack| attack.as_u64());
         let level = value.get("lvl").and_then(|level| level.as_u64());
@@ -65,6 +70,9 @@ impl TryFrom<&Value> for SimplePet {
             level,
             effect: Some(effect),
             item: None,
+            position: None,
+            temp_attack: None,
+            temp_health: None,
             tier,
             pack: pack.to_owned(),
         })
@@ -1,15 +1,23 @@
+use gloo_timers::future::TimeoutFuture;
 use indexmap::IndexMap;
 use itertools::Itertools;
+use once_cell::sync::OnceCell;
+use reqwest::{Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
-use std::{collections::VecDeque, error::Error};
+use std::{error::Error, future::Future};
 
 use crate::{
-    components::battle::{fight::BattleResponse, ui::PetSlots, EMPTY_SLOT_ICON},
+    components::battle::{
+        compare::CompareResponse, fight::BattleResponse, ui::PetSlots, EMPTY_SLOT_ICON,
+    },
     records::{
+        cache::{cache_records, cached_records, local_storage, DbVersion},
         food::SimpleFood,
+        meta::DbMeta,
         pet::SimplePet,
         record::SAPSimpleRecord,
-        team::{SimpleTeam, Teams},
+        team::{CompareRequest, ResumeRequest, SimpleTeam, Teams},
     },
     BACKEND_API_URL, DEV_BACKEND_API_URL, RECORDS,
 };
@@ -17,6 +25,120 @@ use crate::{
 pub type ItemRecords = IndexMap<String, SAPSimpleRecord>;
 pub const IN_DEV: Option<&str> = std::option_env!("DEV_MODE");
 
+/// Attempts made for requests retried with [`retry_with_backoff`].
+pub const MAX_REQUEST_ATTEMPTS: u32 = 4;
+/// Delay before the first retry. Doubled after every retry.
+const INITIAL_RETRY_DELAY_MS: u32 = 500;
+
+/// Backend url chosen at runtime. See [`init_backend_url`].
+static BACKEND_URL: OnceCell<String> = OnceCell::new();
+/// `localStorage` key of the user's backend url.
+const CUSTOM_BACKEND_URL_KEY: &str = "saptester_backend_url";
+
+/// Readiness of a backend. Returned by its `/readyz` route.
+#[derive(Debug, Deserialize)]
+pub struct BackendStatus {
+    pub status: String,
+    pub pets: usize,
+    pub foods: usize,
+}
+
+/// Frontend settings served by the backend when it hosts the frontend.
+#[derive(Deserialize)]
+struct FrontendConfig {
+    /// Empty if the backend is on the same origin.
+    api_url: String,
+}
+
+/// Backend url used if the frontend isn't hosted by a backend.
+fn default_backend_url() -> &'static str {
+    if IN_DEV.is_some() {
+        DEV_BACKEND_API_URL
+    } else {
+        BACKEND_API_URL
+    }
+}
+
+/// Origin the frontend is served from. ex. `https://saptest.fly.dev`
+fn page_origin() -> Option<String> {
+    web_sys::window().and_then(|window| window.location().origin().ok())
+}
+
+async fn get_frontend_config() -> Result<String, Box<dyn Error>> {
+    let origin = page_origin().ok_or("No page origin.")?;
+    let config: FrontendConfig = reqwest::get(format!("{origin}/config.json"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if config.api_url.is_empty() {
+        Ok(origin)
+    } else {
+        Ok(config.api_url.trim_end_matches('/').to_owned())
+    }
+}
+
+/// Backend url set in the settings. Stored in `localStorage`.
+pub fn custom_backend_url() -> Option<String> {
+    local_storage()
+        .and_then(|storage| storage.get_item(CUSTOM_BACKEND_URL_KEY).ok().flatten())
+        .filter(|url| !url.is_empty())
+}
+
+/// Store a backend url to use on the next page load. Removes it if [`None`].
+pub fn set_custom_backend_url(url: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let storage = local_storage().ok_or("Local storage unavailable.")?;
+    match url {
+        Some(url) => storage.set_item(CUSTOM_BACKEND_URL_KEY, url.trim_end_matches('/')),
+        None => storage.remove_item(CUSTOM_BACKEND_URL_KEY),
+    }
+    .map_err(|_| "Unable to update local storage.".into())
+}
+
+/// Check that a backend can be reached and has records.
+pub async fn check_backend(url: &str) -> Result<BackendStatus, Box<dyn Error>> {
+    let url = url.trim_end_matches('/');
+    let resp = reqwest::get(format!("{url}/readyz")).await?;
+    let is_ready = resp.status().is_success();
+    let status: BackendStatus = resp.json().await?;
+
+    if is_ready {
+        Ok(status)
+    } else {
+        Err(status.status.into())
+    }
+}
+
+/// Get the backend url from the settings, or from `/config.json` if a backend hosts the frontend.
+/// * Otherwise, the default backend is used. Must be called before any requests.
+pub async fn init_backend_url() {
+    let url = match custom_backend_url() {
+        Some(url) => url,
+        None => get_frontend_config()
+            .await
+            .unwrap_or_else(|_| default_backend_url().to_owned()),
+    };
+    log::info!("Using backend {url}");
+    let _ = BACKEND_URL.set(url);
+}
+
+pub fn backend_url() -> &'static str {
+    BACKEND_URL
+        .get()
+        .map(String::as_str)
+        .unwrap_or_else(default_backend_url)
+}
+
+/// Url of an image. Paths to images cached by the backend are prefixed with the backend url.
+pub fn asset_url(url: &str) -> String {
+    if url.starts_with('/') {
+        format!("{}{url}", backend_url())
+    } else {
+        url.to_owned()
+    }
+}
+
 pub fn retrieve_record<'a>(rec_type: &'a str, item_name: &'a str) -> Option<&'a SAPSimpleRecord> {
     RECORDS
         .get()
@@ -24,62 +146,83 @@ pub fn retrieve_record<'a>(rec_type: &'a str, item_name: &'a str) -> Option<&'a
         .and_then(|items| items.get(item_name))
 }
 
-pub async fn get_all_sap_records() -> Result<IndexMap<String, ItemRecords>, Box<dyn Error>> {
+/// Get the version of the backend's database.
+pub async fn get_db_version() -> Result<DbVersion, Box<dyn Error>> {
+    let backend_url = backend_url();
+    let resp = reqwest::get(format!("{backend_url}/db/version")).await?;
+    let status = resp.status();
+    let resp_text = resp.text().await?;
+    if is_error_status(status) {
+        return Err(backend_error(status, &resp_text));
+    }
+    serde_json::from_str(&resp_text).map_err(Into::into)
+}
+
+/// Get all pet and food records and the values the backend accepts.
+pub async fn get_all_sap_records(
+) -> Result<(IndexMap<String, ItemRecords>, DbMeta), Box<dyn Error>> {
     let mut item_img_urls: IndexMap<String, ItemRecords> = IndexMap::new();
-    let mut pets = get_sap_records("pets").await?;
+    // Records are only cached if the backend has a version.
+    let version = match get_db_version().await {
+        Ok(version) => Some(version),
+        Err(err) => {
+            log::warn!("Unable to get database version. Records won't be cached: {err}");
+            None
+        }
+    };
+    let mut pets = get_sap_records("pets", version.as_ref()).await?;
     // Add empty slot.
     let empty_slot = SAPSimpleRecord::Pet(SimplePet {
         name: "Slot".to_owned(),
         tier: 0,
         level: Some(1),
-        img_url: EMPTY_SLOT_ICON.to_owned(),
+        img_url: asset_url(EMPTY_SLOT_ICON),
         pack: String::from("Unknown"),
         ..Default::default()
     });
     pets.insert("Slot".to_owned(), empty_slot);
 
-    let foods = get_sap_records("foods").await?;
+    let foods = get_sap_records("foods", version.as_ref()).await?;
+    let meta: DbMeta = serde_json::from_str(&get_records_json("meta", version.as_ref()).await?)?;
 
     item_img_urls.insert("Pets".to_string(), pets);
     item_img_urls.insert("Foods".to_string(), foods);
-    Ok(item_img_urls)
+    Ok((item_img_urls, meta))
 }
 
-/// Reformat slots so pets in correct order before being sent to battle endpoint.
-fn reformat_slots(slots: VecDeque<(String, Option<SimplePet>)>) -> Vec<Option<SimplePet>> {
+/// Pets in slots with their explicit positions for the battle endpoint.
+/// * Slots are stored back to front so the front-most pet is always on the right side visually.
+fn positioned_pets(slots: PetSlots) -> Vec<Option<SimplePet>> {
+    let num_slots = slots.len();
     slots
         .into_iter()
-        // Slots stored in reverse order so front-most pet always on right side visually.
-        .rev()
-        .map(|mut slot| {
-            // Convert item name id with pack in it to basic name.
-            // ex. Honey_Turtle -> Honey
-            if let Some(item_pack_name) = slot.1.as_mut().and_then(|pet| pet.item.as_mut()) {
-                *item_pack_name = retrieve_record("Foods", item_pack_name)
-                    .map(|rec| rec.name())
-                    .unwrap_or(item_pack_name.to_owned())
-            }
-            slot.1
+        .enumerate()
+        .map(|(idx, (_, pet))| {
+            pet.map(|mut pet| {
+                pet.position = Some(num_slots - 1 - idx);
+                // Convert item name id with pack in it to basic name.
+                // ex. Honey_Turtle -> Honey
+                if let Some(item_pack_name) = pet.item.as_mut() {
+                    *item_pack_name = retrieve_record("Foods", item_pack_name)
+                        .map(|rec| rec.name())
+                        .unwrap_or(item_pack_name.to_owned())
+                }
+                pet
+            })
         })
         .collect_vec()
 }
 
-pub async fn post_battle(
-    mut teams: IndexMap<String, PetSlots>,
-) -> Result<BattleResponse, Box<dyn Error>> {
-    let backend_url = if IN_DEV.is_some() {
-        DEV_BACKEND_API_URL
-    } else {
-        BACKEND_API_URL
-    };
+/// Build the battle request body from the friend and enemy team slots.
+pub fn build_teams(mut teams: IndexMap<String, PetSlots>) -> Result<Teams, Box<dyn Error>> {
     let (Some(friends), Some(enemies)) = (
-        teams.remove("Friend").map(reformat_slots),
-        teams.remove("Enemy").map(reformat_slots)
+        teams.remove("Friend").map(positioned_pets),
+        teams.remove("Enemy").map(positioned_pets)
     ) else {
         return Err("Missing a team.".into())
     };
 
-    let teams = Teams {
+    Ok(Teams {
         friend_team: SimpleTeam {
             name: "Friend".into(),
             pets: friends,
@@ -88,29 +231,149 @@ pub async fn post_battle(
             name: "Enemy".into(),
             pets: enemies,
         },
-    };
+        seed: None,
+        replay: false,
+    })
+}
+
+/// Retry a request with exponential backoff until it succeeds or [`MAX_REQUEST_ATTEMPTS`] is reached.
+pub async fn retry_with_backoff<T, F, Fut>(mut request: F) -> Result<T, Box<dyn Error>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn Error>>>,
+{
+    let mut delay_ms = INITIAL_RETRY_DELAY_MS;
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt >= MAX_REQUEST_ATTEMPTS => return Err(err),
+            Err(err) => {
+                log::warn!("Attempt {attempt} of {MAX_REQUEST_ATTEMPTS} failed: {err}. Retrying in {delay_ms} ms.");
+                TimeoutFuture::new(delay_ms).await;
+                delay_ms *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn is_error_status(status: StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
+
+/// Error shown for a failed request. Includes the backend's message if it sent one.
+fn backend_error(status: StatusCode, body: &str) -> Box<dyn Error> {
+    let body = body.trim();
+    if body.is_empty() {
+        format!("Backend responded with {status}.").into()
+    } else {
+        format!("Backend responded with {status}: {body}").into()
+    }
+}
+
+/// Deserialize a response from the backend.
+/// * Error responses with a JSON body are kept so their `status` can be shown.
+async fn parse_response<T: DeserializeOwned>(resp: Response) -> Result<T, Box<dyn Error>> {
+    let status = resp.status();
+    let body = resp.text().await?;
+    match serde_json::from_str(&body) {
+        Ok(value) => Ok(value),
+        Err(_) if is_error_status(status) => Err(backend_error(status, &body)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Fight the teams on the backend. The same `seed` fights the same battle.
+/// * `replay` keeps every turn so the battle can be edited and resumed. Only requested when the replay is opened.
+pub async fn post_battle(
+    teams: IndexMap<String, PetSlots>,
+    seed: Option<u64>,
+    replay: bool,
+) -> Result<BattleResponse, Box<dyn Error>> {
+    let backend_url = backend_url();
+    let mut teams = build_teams(teams)?;
+    teams.seed = seed;
+    teams.replay = replay;
 
     let client = reqwest::Client::new();
-    let res = client
+    let resp = client
         .post(format!("{backend_url}/battle"))
         .json(&teams)
         .send()
-        .await?
-        .text()
         .await?;
 
-    serde_json::from_str(&res).map_err(Into::into)
+    parse_response(resp).await
 }
 
-pub async fn get_sap_records(categ: &str) -> Result<ItemRecords, Box<dyn Error>> {
-    let backend_url = if IN_DEV.is_some() {
-        DEV_BACKEND_API_URL
-    } else {
-        BACKEND_API_URL
+/// Fight the baseline and current teams with the same seed and compare their battles.
+pub async fn post_compare(
+    baseline: IndexMap<String, PetSlots>,
+    current: IndexMap<String, PetSlots>,
+) -> Result<CompareResponse, Box<dyn Error>> {
+    let backend_url = backend_url();
+    let request = CompareRequest {
+        first: build_teams(baseline)?,
+        second: build_teams(current)?,
     };
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{backend_url}/battle/compare"))
+        .json(&request)
+        .send()
+        .await?;
+
+    parse_response(resp).await
+}
+
+/// Continue a battle from the teams at one of its turns after applying any edits.
+pub async fn post_resume(request: ResumeRequest) -> Result<BattleResponse, Box<dyn Error>> {
+    let backend_url = backend_url();
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{backend_url}/battle/resume"))
+        .json(&request)
+        .send()
+        .await?;
+
+    parse_response(resp).await
+}
+
+/// Get the JSON of a record category from the cache or the backend.
+/// * Records from the backend are cached if a database `version` is given.
+async fn get_records_json(
+    categ: &str,
+    version: Option<&DbVersion>,
+) -> Result<String, Box<dyn Error>> {
+    let backend_url = backend_url();
+    if let Some(records_json) =
+        version.and_then(|version| cached_records(backend_url, version, categ))
+    {
+        log::info!("Using cached {categ}.");
+        return Ok(records_json);
+    }
+
     let url = format!("{backend_url}/db/{categ}");
+    let resp = reqwest::get(url).await?;
+    let status = resp.status();
+    let resp_text = resp.text().await?;
+    if is_error_status(status) {
+        return Err(backend_error(status, &resp_text));
+    }
+
+    if let Some(version) = version {
+        cache_records(backend_url, version, categ, &resp_text);
+    }
+    Ok(resp_text)
+}
 
-    let resp_text = reqwest::get(url).await?.text().await?;
+pub async fn get_sap_records(
+    categ: &str,
+    version: Option<&DbVersion>,
+) -> Result<ItemRecords, Box<dyn Error>> {
+    let resp_text = get_records_json(categ, version).await?;
     let pet_records: Value = serde_json::from_str(&resp_text)?;
 
     if let Some(records) = pet_records.as_array() {
@@ -35,6 +35,18 @@ impl SAPSimpleRecord {
                     } else if *filter_name == "Tier" {
                         let tier = filter_val.parse::<u64>()?;
                         Ok(self.tier() == tier)
+                    } else if *filter_name == "Trigger" {
+                        // Foods and empty slots have no trigger so are always shown.
+                        // Pets without an effect only match any trigger.
+                        let is_slot = self.name().to_lowercase() == "slot";
+                        Ok(match self {
+                            SAPSimpleRecord::Food(_) => true,
+                            SAPSimpleRecord::Pet(_) if is_slot => true,
+                            SAPSimpleRecord::Pet(_) => {
+                                filter_val == "All"
+                                    || self.trigger().is_some_and(|trigger| &trigger == filter_val)
+                            }
+                        })
                     } else {
                         Ok(&self.pack() == filter_val || filter_val == "All")
                     }
@@ -64,6 +76,16 @@ impl SAPSimpleRecord {
         }
     }
 
+    /// Trigger of a pet's effect.
+    pub fn trigger(&self) -> Option<String> {
+        match self {
+            SAPSimpleRecord::Pet(rec) => {
+                rec.effect.as_ref().map(|effect| effect.trigger.to_owned())
+            }
+            SAPSimpleRecord::Food(_) => None,
+        }
+    }
+
     pub fn holdable(&self) -> bool {
         match self {
             SAPSimpleRecord::Pet(_) => false,
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Pet state at the end of a turn.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PetSnapshot {
+    pub id: Option<String>,
+    pub name: String,
+    pub attack: i64,
+    pub health: i64,
+    pub item: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TeamSnapshot {
+    pub pets: Vec<PetSnapshot>,
+    pub fainted: Vec<PetSnapshot>,
+}
+
+/// State of both teams at the end of a turn. Turn 0 is the state before the battle starts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TurnSnapshot {
+    pub turn: usize,
+    pub friend_team: TeamSnapshot,
+    pub enemy_team: TeamSnapshot,
+}
+
+/// Full state of both teams at the end of a turn. Sent back to the backend to resume a battle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TurnState {
+    pub turn: usize,
+    pub friend_team: Value,
+    pub enemy_team: Value,
+}
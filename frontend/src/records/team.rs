@@ -1,10 +1,16 @@
 use super::pet::SimplePet;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Teams {
     pub friend_team: SimpleTeam,
     pub enemy_team: SimpleTeam,
+    /// Seed for random effects. The backend picks one if not given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Keep the state of both teams after every turn so the battle can be resumed.
+    pub replay: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,3 +18,39 @@ pub struct SimpleTeam {
     pub name: String,
     pub pets: Vec<Option<SimplePet>>,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareRequest {
+    pub first: Teams,
+    pub second: Teams,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeamSide {
+    Friend,
+    Enemy,
+}
+
+/// A change to a pet before a battle resumes. Fields left as [`None`] are unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetEdit {
+    pub team: TeamSide,
+    /// Position of the pet. 0 is the front.
+    pub position: usize,
+    pub attack: Option<i64>,
+    pub health: Option<i64>,
+    /// Held food. An empty name removes the item.
+    pub item: Option<String>,
+    pub move_to: Option<usize>,
+}
+
+/// Teams from a turn of a battle to continue fighting from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumeRequest {
+    pub friend_team: Value,
+    pub enemy_team: Value,
+    pub seed: Option<u64>,
+    pub turn: usize,
+    pub edits: Vec<PetEdit>,
+    pub replay: bool,
+}
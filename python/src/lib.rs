@@ -0,0 +1,147 @@
+//! Python bindings for team conversion and battles. Teams and results are passed as dicts
+//! in the same JSON shape as the `/battle` and `/battle/batch` routes.
+use std::fmt::Display;
+
+use backend::battle::{
+    batch::{run_batch, BatchResponse},
+    response::{self, random_seed},
+    team::{build_pet, build_team, SimplePet, SimpleTeam, Teams},
+};
+use once_cell::sync::Lazy;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::runtime::Runtime;
+
+/// Runtime batches are fought on. Battles are still run on the simulation workers.
+static RUNTIME: Lazy<Runtime> =
+    Lazy::new(|| Runtime::new().expect("Unable to start battle runtime."));
+
+fn value_error<E: Display>(err: E) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Deserialize a Python object through `json.dumps`.
+fn from_py<T: DeserializeOwned>(py: Python<'_>, obj: &PyAny) -> PyResult<T> {
+    let json: String = py
+        .import("json")?
+        .call_method1("dumps", (obj,))?
+        .extract()?;
+    serde_json::from_str(&json).map_err(value_error)
+}
+
+/// Serialize a value to a Python object through `json.loads`.
+fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(value_error)?;
+    Ok(py
+        .import("json")?
+        .call_method1("loads", (json,))?
+        .to_object(py))
+}
+
+fn teams(
+    py: Python<'_>,
+    friend_team: &PyAny,
+    enemy_team: &PyAny,
+    seed: Option<u64>,
+) -> PyResult<Teams> {
+    Ok(Teams {
+        friend_team: from_py(py, friend_team)?,
+        enemy_team: from_py(py, enemy_team)?,
+        seed,
+        replay: false,
+    })
+}
+
+/// Build a `saptest` pet from a pet dict. ex. `{"name": "Ant", "level": 2, "item": "Honey"}`
+/// * Raises `ValueError` if the pet or item is unknown.
+#[pyfunction]
+fn parse_pet(py: Python<'_>, pet: &PyAny) -> PyResult<PyObject> {
+    let pet: SimplePet = from_py(py, pet)?;
+    let pet = build_pet(pet).map_err(value_error)?;
+    to_py(py, &pet)
+}
+
+/// Build a `saptest` team from a team dict. ex. `{"name": "Friend", "pets": [{"name": "Ant"}, None]}`
+/// * Raises `ValueError` if any pet or item is unknown.
+#[pyfunction]
+fn parse_team(py: Python<'_>, team: &PyAny) -> PyResult<PyObject> {
+    let team: SimpleTeam = from_py(py, team)?;
+    let team = build_team(team).map_err(value_error)?;
+    to_py(py, &team)
+}
+
+/// Fight two teams until the battle is decided or the turn limit is reached.
+/// * Returns a dict in the same shape as the `/battle` response.
+#[pyfunction]
+#[pyo3(signature = (friend_team, enemy_team, seed = None))]
+fn battle(
+    py: Python<'_>,
+    friend_team: &PyAny,
+    enemy_team: &PyAny,
+    seed: Option<u64>,
+) -> PyResult<PyObject> {
+    let teams = teams(py, friend_team, enemy_team, seed)?;
+    let seed = seed.unwrap_or_else(random_seed);
+    let resp = py
+        .allow_threads(|| response::battle(teams, seed, |_, _, _| {}))
+        .map_err(value_error)?;
+    to_py(py, &resp)
+}
+
+/// Fight two teams `runs` times. Run `i` is fought with seed `seed + i`.
+/// * Returns a dict in the same shape as the `/battle/batch` response.
+#[pyfunction]
+#[pyo3(signature = (friend_team, enemy_team, runs, seed = None))]
+fn batch(
+    py: Python<'_>,
+    friend_team: &PyAny,
+    enemy_team: &PyAny,
+    runs: u64,
+    seed: Option<u64>,
+) -> PyResult<PyObject> {
+    if runs == 0 {
+        return Err(value_error("Runs must be at least 1."));
+    }
+    let teams = teams(py, friend_team, enemy_team, seed)?;
+    let seed = seed.unwrap_or_else(random_seed);
+    let tally = py
+        .allow_threads(|| RUNTIME.block_on(run_batch(teams, seed, runs)))
+        .map_err(value_error)?;
+    to_py(py, &BatchResponse::from_tally(seed, tally))
+}
+
+#[pymodule]
+fn saptester(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_pet, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_team, m)?)?;
+    m.add_function(wrap_pyfunction!(battle, m)?)?;
+    m.add_function(wrap_pyfunction!(batch, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let team = SimpleTeam {
+                name: "Friend".to_owned(),
+                pets: vec![
+                    Some(SimplePet {
+                        name: "Ant".to_owned(),
+                        item: Some("Honey".to_owned()),
+                        ..Default::default()
+                    }),
+                    None,
+                ],
+            };
+            let obj = to_py(py, &team).unwrap();
+            let round_trip: SimpleTeam = from_py(py, obj.as_ref(py)).unwrap();
+
+            assert_eq!(round_trip, team);
+        })
+    }
+}
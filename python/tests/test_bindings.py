@@ -0,0 +1,72 @@
+"""Tests of the `saptester` module. Build it first with `maturin develop`."""
+import pytest
+
+import saptester
+
+FRIEND = {"name": "Friend", "pets": [{"name": "Ant", "item": "Honey"}, {"name": "Dog", "level": 2}]}
+ENEMY = {"name": "Enemy", "pets": [{"name": "Cricket"}, {"name": "Fish"}]}
+UNKNOWN_PET = {"name": "Friend", "pets": [{"name": "Antt"}]}
+UNKNOWN_ITEM = {"name": "Friend", "pets": [{"name": "Ant", "item": "Honeyy"}]}
+REPEATED_POSITION = {
+    "name": "Friend",
+    "pets": [{"name": "Ant", "position": 0}, {"name": "Dog", "position": 0}],
+}
+
+
+def test_parse_pet():
+    pet = saptester.parse_pet({"name": "Ant", "level": 2, "item": "Honey"})
+
+    assert pet["name"] == "Ant"
+    assert pet["item"]["name"] == "Honey"
+
+
+def test_parse_team():
+    team = saptester.parse_team(FRIEND)
+
+    assert team["name"] == "Friend"
+    assert [pet["name"] for pet in team["friends"] if pet] == ["Ant", "Dog"]
+
+
+@pytest.mark.parametrize("team", [UNKNOWN_PET, UNKNOWN_ITEM, REPEATED_POSITION, {"name": "Friend"}])
+def test_parse_team_invalid(team):
+    with pytest.raises(ValueError):
+        saptester.parse_team(team)
+
+
+def test_parse_pet_invalid():
+    with pytest.raises(ValueError):
+        saptester.parse_pet({"name": "Antt"})
+
+
+def test_battle():
+    battle = saptester.battle(FRIEND, ENEMY, seed=42)
+
+    assert battle["outcome"] in ("Win", "Loss", "Draw", "None")
+    assert battle["seed"] == 42
+    assert battle["num_turns"] > 0
+    # Same seed, same battle.
+    assert saptester.battle(FRIEND, ENEMY, seed=42) == battle
+
+
+@pytest.mark.parametrize("team", [UNKNOWN_PET, UNKNOWN_ITEM, REPEATED_POSITION])
+def test_battle_invalid(team):
+    with pytest.raises(ValueError):
+        saptester.battle(team, ENEMY)
+
+
+def test_batch():
+    batch = saptester.batch(FRIEND, ENEMY, runs=20, seed=42)
+
+    assert batch["runs"] == 20
+    assert batch["wins"] + batch["losses"] + batch["draws"] + batch["unfinished"] == 20
+    assert 0.0 <= batch["win_rate"] <= 1.0
+    # Same seed, same runs.
+    rerun = saptester.batch(FRIEND, ENEMY, runs=20, seed=42)
+    assert (rerun["wins"], rerun["losses"], rerun["draws"]) == (batch["wins"], batch["losses"], batch["draws"])
+
+
+def test_batch_invalid():
+    with pytest.raises(ValueError):
+        saptester.batch(FRIEND, ENEMY, runs=0)
+    with pytest.raises(ValueError):
+        saptester.batch(UNKNOWN_PET, ENEMY, runs=10)
@@ -0,0 +1,68 @@
+//! Bakes pet and food definitions from the `saptest` database into the crate.
+//! * The database is only read at build time so the crate doesn't depend on SQLite and can be compiled to wasm.
+use saptest::{
+    db::record::{FoodRecord, PetRecord, SAPRecord},
+    Entity, SAPQuery, SAPDB,
+};
+use std::{collections::HashSet, env, error::Error, fs, path::PathBuf};
+
+fn all_records(entity: Entity) -> Result<Vec<SAPRecord>, Box<dyn Error>> {
+    let mut query = SAPQuery::from_iter(std::iter::empty::<(String, Vec<String>)>());
+    query.set_table(entity);
+    Ok(SAPDB.execute_query(query)?)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let mut pet_defs = String::new();
+    let mut seen_pets = HashSet::new();
+    for pet in all_records(Entity::Pet)?
+        .into_iter()
+        .filter_map(|rec| PetRecord::try_from(rec).ok())
+    {
+        // A pet's row for each pack is kept as stats and effects can differ between packs.
+        if !seen_pets.insert((pet.name.to_string(), pet.lvl, pet.pack.to_string())) {
+            continue;
+        }
+        pet_defs.push_str(&format!(
+            "    PetDef {{ name: {:?}, pack: {:?}, tier: {}, lvl: {}, attack: {}, health: {}, effect_trigger: {:?}, effect_atk: {}, effect_health: {}, is_token: {} }},\n",
+            pet.name.to_string(),
+            pet.pack.to_string(),
+            pet.tier,
+            pet.lvl,
+            pet.attack,
+            pet.health,
+            pet.effect_trigger.as_deref(),
+            pet.effect_atk,
+            pet.effect_health,
+            pet.is_token,
+        ));
+    }
+
+    let mut food_defs = String::new();
+    let mut seen_foods = HashSet::new();
+    for food in all_records(Entity::Food)?
+        .into_iter()
+        .filter_map(|rec| FoodRecord::try_from(rec).ok())
+    {
+        if !seen_foods.insert(food.name.to_string()) {
+            continue;
+        }
+        food_defs.push_str(&format!(
+            "    FoodDef {{ name: {:?}, tier: {}, holdable: {} }},\n",
+            food.name.to_string(),
+            food.tier,
+            food.holdable,
+        ));
+    }
+
+    let out_file = PathBuf::from(env::var("OUT_DIR")?).join("defs.rs");
+    fs::write(
+        out_file,
+        format!(
+            "pub static PETS: &[PetDef] = &[\n{pet_defs}];\npub static FOODS: &[FoodDef] = &[\n{food_defs}];\n"
+        ),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,930 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    defs::{food_def, pet_def},
+    rng::Rng,
+    stats::{BattleStats, PetStats},
+    team::{SimplePet, SimpleTeam, Teams, EMPTY_SLOT_NAME},
+};
+
+/// Battles are stopped once more than this many turns are fought. Same as the backend's default limit.
+pub const MAX_TURNS: usize = 250;
+pub const MAX_TEAM_SIZE: usize = 5;
+pub const MAX_PET_STATS: usize = 50;
+pub const MIN_PET_LEVEL: usize = 1;
+pub const MAX_PET_LEVEL: usize = 3;
+
+/// Effect of a held food.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemEffect {
+    /// Deal extra damage when attacking.
+    ExtraDamage { damage: usize, single_use: bool },
+    /// Take less damage. Damage taken is at least `min`.
+    ReduceDamage {
+        damage: usize,
+        min: usize,
+        single_use: bool,
+    },
+    /// Take extra damage.
+    ExtraDamageTaken(usize),
+    /// Deal damage to the pet behind the attacked pet.
+    Splash(usize),
+    /// Attacks that deal damage knock out.
+    Kill,
+    /// Summon a pet after fainting.
+    Summon {
+        name: &'static str,
+        attack: usize,
+        health: usize,
+    },
+    /// Come back as the same pet after fainting.
+    Respawn { attack: usize, health: usize },
+}
+
+/// Held foods with an effect. Other holdable foods can be held but do nothing.
+pub const ITEM_EFFECTS: [(&str, ItemEffect); 10] = [
+    (
+        "Meat Bone",
+        ItemEffect::ExtraDamage {
+            damage: 3,
+            single_use: false,
+        },
+    ),
+    (
+        "Steak",
+        ItemEffect::ExtraDamage {
+            damage: 20,
+            single_use: true,
+        },
+    ),
+    (
+        "Garlic",
+        ItemEffect::ReduceDamage {
+            damage: 2,
+            min: 1,
+            single_use: false,
+        },
+    ),
+    (
+        "Melon",
+        ItemEffect::ReduceDamage {
+            damage: 20,
+            min: 0,
+            single_use: true,
+        },
+    ),
+    (
+        "Coconut",
+        ItemEffect::ReduceDamage {
+            damage: usize::MAX,
+            min: 0,
+            single_use: true,
+        },
+    ),
+    ("Weak", ItemEffect::ExtraDamageTaken(3)),
+    ("Chili", ItemEffect::Splash(5)),
+    ("Peanut", ItemEffect::Kill),
+    (
+        "Honey",
+        ItemEffect::Summon {
+            name: "Bee",
+            attack: 1,
+            health: 1,
+        },
+    ),
+    (
+        "Mushroom",
+        ItemEffect::Respawn {
+            attack: 1,
+            health: 1,
+        },
+    ),
+];
+
+/// Pet abilities. Values come from the pet's `effect_atk` and `effect_health` at its level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ability {
+    /// Faint: Give a random friend the effect's stats.
+    BuffRandomFriend,
+    /// Faint: Give the effect's stats to this many friends behind.
+    BuffFriendsBehind(usize),
+    /// Faint: Summon `count` pets with the effect's stats.
+    Summon { name: &'static str, count: usize },
+    /// Faint: Deal the effect's attack as damage to every pet.
+    DamageAll,
+    /// Faint: Give a food to one friend behind per level.
+    GiveItemBehind(&'static str),
+    /// Start of battle: Deal the effect's attack as damage to a random enemy. Once per level.
+    DamageRandomEnemies,
+}
+
+/// Pets with an ability. Other pets only attack.
+pub const ABILITIES: [(&str, Ability); 7] = [
+    ("Ant", Ability::BuffRandomFriend),
+    (
+        "Cricket",
+        Ability::Summon {
+            name: "Zombie Cricket",
+            count: 1,
+        },
+    ),
+    ("Flamingo", Ability::BuffFriendsBehind(2)),
+    ("Hedgehog", Ability::DamageAll),
+    ("Mosquito", Ability::DamageRandomEnemies),
+    (
+        "Sheep",
+        Ability::Summon {
+            name: "Ram",
+            count: 2,
+        },
+    ),
+    ("Turtle", Ability::GiveItemBehind("Melon")),
+];
+
+/// Words in the triggers of abilities that never activate in battle.
+const SHOP_TRIGGERS: [&str; 7] = ["buy", "sell", "level", "turn", "roll", "shop", "none"];
+
+pub fn item_effect(item: &str) -> Option<ItemEffect> {
+    ITEM_EFFECTS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(item))
+        .map(|(_, effect)| *effect)
+}
+
+pub fn ability(pet: &str) -> Option<Ability> {
+    ABILITIES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(pet))
+        .map(|(_, ability)| *ability)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+    /// Stopped at the turn limit.
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetState {
+    pub name: String,
+    pub attack: usize,
+    pub health: usize,
+    pub level: usize,
+    pub item: Option<String>,
+}
+
+/// A team after a battle. Same field names as a `saptest` team.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamState {
+    pub name: String,
+    pub friends: Vec<PetState>,
+    pub fainted: Vec<PetState>,
+}
+
+/// Result of a battle from the friend team's side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BattleResult {
+    pub outcome: Outcome,
+    pub friend_team: TeamState,
+    pub enemy_team: TeamState,
+    pub num_turns: usize,
+    pub seed: u64,
+    pub pet_stats: BattleStats,
+    /// Pets and foods in the battle whose effects aren't modeled. See [`unmodeled`].
+    pub unmodeled: Vec<String>,
+}
+
+/// Damage dealt by an attack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Hit {
+    damage: usize,
+    splash: Option<usize>,
+    kills: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Fighter {
+    pet: PetState,
+    ability: Option<Ability>,
+    effect_atk: usize,
+    effect_health: usize,
+    /// Index of the pet's stats on its team. Set once it joins a [`Side`].
+    stats_idx: usize,
+    /// Index of the stats of the last enemy that damaged the pet. Damage from friends isn't kept.
+    last_hit_by: Option<usize>,
+    /// Attack and health only kept for this battle. Added at the start of battle.
+    temp_stats: (usize, usize),
+}
+
+impl Fighter {
+    /// A pet added during battle. Summoned pets don't hold items.
+    fn summon(name: &str, attack: usize, health: usize) -> Self {
+        let def = pet_def(name, 1);
+        Fighter {
+            pet: PetState {
+                name: def.map_or(name, |def| def.name).to_owned(),
+                attack,
+                health,
+                level: 1,
+                item: None,
+            },
+            ability: ability(name),
+            effect_atk: def.map_or(0, |def| def.effect_atk),
+            effect_health: def.map_or(0, |def| def.effect_health),
+            stats_idx: 0,
+            last_hit_by: None,
+            temp_stats: (0, 0),
+        }
+    }
+
+    fn item_effect(&self) -> Option<ItemEffect> {
+        self.pet.item.as_deref().and_then(item_effect)
+    }
+
+    fn is_alive(&self) -> bool {
+        self.pet.health > 0
+    }
+
+    fn add_stats(&mut self, attack: usize, health: usize) {
+        self.pet.attack = (self.pet.attack + attack).min(MAX_PET_STATS);
+        self.pet.health = (self.pet.health + health).min(MAX_PET_STATS);
+    }
+
+    /// Attack with the held food's bonus. Single use foods are used up.
+    fn hit(&mut self) -> Hit {
+        let mut hit = Hit {
+            damage: self.pet.attack,
+            splash: None,
+            kills: false,
+        };
+        match self.item_effect() {
+            Some(ItemEffect::ExtraDamage { damage, single_use }) => {
+                hit.damage += damage;
+                if single_use {
+                    self.pet.item = None;
+                }
+            }
+            Some(ItemEffect::Splash(damage)) => hit.splash = Some(damage),
+            Some(ItemEffect::Kill) => hit.kills = true,
+            _ => {}
+        }
+        hit
+    }
+
+    /// Take damage reduced or increased by the held food. Returns the health lost.
+    fn take_damage(&mut self, mut damage: usize, kills: bool) -> usize {
+        if damage == 0 {
+            return 0;
+        }
+        let health = self.pet.health;
+        match self.item_effect() {
+            Some(ItemEffect::ReduceDamage {
+                damage: reduction,
+                min,
+                single_use,
+            }) => {
+                damage = damage.saturating_sub(reduction).max(min);
+                if single_use {
+                    self.pet.item = None;
+                }
+            }
+            Some(ItemEffect::ExtraDamageTaken(extra)) => damage += extra,
+            _ => {}
+        }
+        if damage > 0 {
+            self.pet.health = if kills {
+                0
+            } else {
+                self.pet.health.saturating_sub(damage)
+            };
+        }
+        health - self.pet.health
+    }
+}
+
+impl TryFrom<&SimplePet> for Fighter {
+    type Error = String;
+
+    fn try_from(pet: &SimplePet) -> Result<Self, Self::Error> {
+        let level = pet
+            .level
+            .map_or(1, |lvl| lvl.clamp(MIN_PET_LEVEL, MAX_PET_LEVEL));
+        let def = pet_def(&pet.name, level)
+            .ok_or_else(|| format!("Unknown pet {} at level {level}.", pet.name))?;
+        let item = match pet.item.as_deref() {
+            Some(item) => Some(
+                food_def(item)
+                    .filter(|food| food.holdable)
+                    .ok_or_else(|| format!("Unknown item {item}."))?
+                    .name
+                    .to_owned(),
+            ),
+            None => None,
+        };
+        Ok(Fighter {
+            pet: PetState {
+                name: def.name.to_owned(),
+                attack: pet.attack.unwrap_or(def.attack).min(MAX_PET_STATS),
+                health: pet.health.unwrap_or(def.health).min(MAX_PET_STATS),
+                level,
+                item,
+            },
+            ability: ability(def.name),
+            effect_atk: def.effect_atk,
+            effect_health: def.effect_health,
+            stats_idx: 0,
+            last_hit_by: None,
+            temp_stats: (pet.temp_attack.unwrap_or(0), pet.temp_health.unwrap_or(0)),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Side {
+    name: String,
+    /// The first pet is the front of the team.
+    pets: Vec<Fighter>,
+    fainted: Vec<PetState>,
+    /// Stats of every pet that was on the team.
+    stats: Vec<PetStats>,
+}
+
+impl TryFrom<&SimpleTeam> for Side {
+    type Error = String;
+
+    fn try_from(team: &SimpleTeam) -> Result<Self, Self::Error> {
+        let pets = team
+            .ordered_pets(MAX_TEAM_SIZE)?
+            .into_iter()
+            .filter(|pet| pet.name != EMPTY_SLOT_NAME)
+            .map(Fighter::try_from)
+            .collect::<Result<Vec<Fighter>, String>>()?;
+        if pets.len() > MAX_TEAM_SIZE {
+            return Err(format!(
+                "Team {} has more than {MAX_TEAM_SIZE} pets.",
+                team.name
+            ));
+        }
+        Ok(Side::new(&team.name, pets))
+    }
+}
+
+impl Side {
+    fn new(name: &str, pets: Vec<Fighter>) -> Self {
+        let mut side = Side {
+            name: name.to_owned(),
+            pets: vec![],
+            fainted: vec![],
+            stats: vec![],
+        };
+        for pet in pets {
+            side.add(side.pets.len(), pet);
+        }
+        side
+    }
+
+    /// Add a pet at `idx` and start tracking its stats.
+    fn add(&mut self, idx: usize, mut pet: Fighter) {
+        pet.stats_idx = self.stats.len();
+        self.stats.push(PetStats::new(&pet.pet.name));
+        self.pets.insert(idx.min(self.pets.len()), pet);
+    }
+
+    /// Damage the pet at `idx`. `attacker` is the stats of the enemy dealing it or [`None`] for a friend.
+    /// * Returns the health lost.
+    fn damage(&mut self, idx: usize, damage: usize, kills: bool, attacker: Option<usize>) -> usize {
+        let Some(pet) = self.pets.get_mut(idx) else {
+            return 0;
+        };
+        let lost = pet.take_damage(damage, kills);
+        if lost > 0 {
+            if attacker.is_some() {
+                pet.last_hit_by = attacker;
+            }
+            self.stats[pet.stats_idx].damage_taken += lost;
+        }
+        lost
+    }
+
+    /// Damage every pet for the pet with stats `attacker`, an enemy if `from_enemy`.
+    /// * Returns the health lost.
+    fn damage_all(&mut self, damage: usize, attacker: usize, from_enemy: bool) -> usize {
+        (0..self.pets.len())
+            .map(|idx| self.damage(idx, damage, false, from_enemy.then_some(attacker)))
+            .sum()
+    }
+
+    /// Take an attack with the front pet. Splash damage hits the pet behind it.
+    /// * Returns the health lost by both pets.
+    fn receive(&mut self, hit: Hit, attacker: usize) -> usize {
+        let mut lost = self.damage(0, hit.damage, hit.kills, Some(attacker));
+        if let Some(splash) = hit.splash {
+            lost += self.damage(1, splash, false, Some(attacker));
+        }
+        lost
+    }
+
+    /// Summon a pet at `idx` if there's room. Returns `true` if it was summoned.
+    fn summon(&mut self, idx: usize, pet: Fighter) -> bool {
+        let has_room = self.pets.len() < MAX_TEAM_SIZE;
+        if has_room {
+            self.add(idx, pet);
+        }
+        has_room
+    }
+
+    /// Up to `count` pets still alive from `idx` back.
+    fn alive_behind(&mut self, idx: usize, count: usize) -> impl Iterator<Item = &mut Fighter> {
+        self.pets
+            .iter_mut()
+            .skip(idx)
+            .filter(|pet| pet.is_alive())
+            .take(count)
+    }
+
+    /// Remove fainted pets front to back and trigger their faint effects.
+    /// * Damage to friends is dealt right away. Damage to enemies is returned to be dealt by the caller.
+    fn resolve_faints(&mut self, rng: &mut Rng, turn: usize) -> Faints {
+        let mut faints = Faints::default();
+        while let Some(idx) = self.pets.iter().position(|pet| !pet.is_alive()) {
+            let fainted = self.pets.remove(idx);
+            let stats_idx = fainted.stats_idx;
+            self.stats[stats_idx].fainted_turn = Some(turn);
+            faints.killers.extend(fainted.last_hit_by);
+
+            match fainted.ability {
+                Some(Ability::BuffRandomFriend) => {
+                    if let Some(friend) = rng
+                        .index(self.pets.len())
+                        .and_then(|friend_idx| self.pets.get_mut(friend_idx))
+                    {
+                        friend.add_stats(fainted.effect_atk, fainted.effect_health);
+                        let stats = &mut self.stats[stats_idx];
+                        stats.effect_triggers += 1;
+                        stats.stats_granted += fainted.effect_atk + fainted.effect_health;
+                    }
+                }
+                Some(Ability::BuffFriendsBehind(count)) => {
+                    let mut buffed = 0;
+                    for friend in self.alive_behind(idx, count) {
+                        friend.add_stats(fainted.effect_atk, fainted.effect_health);
+                        buffed += 1;
+                    }
+                    let stats = &mut self.stats[stats_idx];
+                    stats.effect_triggers += 1;
+                    stats.stats_granted += buffed * (fainted.effect_atk + fainted.effect_health);
+                }
+                Some(Ability::Summon { name, count }) => {
+                    self.stats[stats_idx].effect_triggers += 1;
+                    for _ in 0..count {
+                        let summon =
+                            Fighter::summon(name, fainted.effect_atk, fainted.effect_health);
+                        if self.summon(idx, summon) {
+                            self.stats[stats_idx].summons += 1;
+                        }
+                    }
+                }
+                Some(Ability::DamageAll) => {
+                    self.stats[stats_idx].effect_triggers += 1;
+                    self.damage_all(fainted.effect_atk, stats_idx, false);
+                    faints.enemy_damage.push((fainted.effect_atk, stats_idx));
+                }
+                Some(Ability::GiveItemBehind(item)) => {
+                    for friend in self.alive_behind(idx, fainted.pet.level) {
+                        friend.pet.item = Some(item.to_owned());
+                    }
+                    self.stats[stats_idx].effect_triggers += 1;
+                }
+                Some(Ability::DamageRandomEnemies) | None => {}
+            }
+            let item_summon = match fainted.item_effect() {
+                Some(ItemEffect::Summon {
+                    name,
+                    attack,
+                    health,
+                }) => Some(Fighter::summon(name, attack, health)),
+                Some(ItemEffect::Respawn { attack, health }) => {
+                    Some(Fighter::summon(&fainted.pet.name, attack, health))
+                }
+                _ => None,
+            };
+            if item_summon.is_some_and(|pet| self.summon(idx, pet)) {
+                self.stats[stats_idx].summons += 1;
+            }
+            self.fainted.push(fainted.pet);
+        }
+        faints
+    }
+
+    fn state(self) -> (TeamState, Vec<PetStats>) {
+        let state = TeamState {
+            name: self.name,
+            friends: self.pets.into_iter().map(|pet| pet.pet).collect(),
+            fainted: self.fainted,
+        };
+        (state, self.stats)
+    }
+}
+
+/// Pets removed from one side of a battle.
+#[derive(Debug, Default)]
+struct Faints {
+    /// Stats of the enemy that knocked out each pet.
+    killers: Vec<usize>,
+    /// Damage to every enemy from faint effects and the stats of the pet dealing it.
+    enemy_damage: Vec<(usize, usize)>,
+}
+
+/// Remove fainted pets on both teams and credit their kills.
+/// * Repeats until no pets are knocked out by faint effects.
+fn resolve_faints(friends: &mut Side, enemies: &mut Side, rng: &mut Rng, turn: usize) {
+    loop {
+        let friend_faints = friends.resolve_faints(rng, turn);
+        let enemy_faints = enemies.resolve_faints(rng, turn);
+        for killer in friend_faints.killers {
+            enemies.stats[killer].kills += 1;
+        }
+        for killer in enemy_faints.killers {
+            friends.stats[killer].kills += 1;
+        }
+        for (damage, attacker) in friend_faints.enemy_damage {
+            friends.stats[attacker].damage_dealt += enemies.damage_all(damage, attacker, true);
+        }
+        for (damage, attacker) in enemy_faints.enemy_damage {
+            enemies.stats[attacker].damage_dealt += friends.damage_all(damage, attacker, true);
+        }
+        let has_fainted = |side: &Side| side.pets.iter().any(|pet| !pet.is_alive());
+        if !has_fainted(friends) && !has_fainted(enemies) {
+            break;
+        }
+    }
+}
+
+/// Add temporary stats, then trigger start of battle abilities. Pets with higher attack go first. Friends go first on ties.
+fn start_of_battle(friends: &mut Side, enemies: &mut Side, rng: &mut Rng) {
+    for pet in friends.pets.iter_mut().chain(enemies.pets.iter_mut()) {
+        let (attack, health) = std::mem::take(&mut pet.temp_stats);
+        pet.add_stats(attack, health);
+    }
+
+    let mut order = friends
+        .pets
+        .iter()
+        .enumerate()
+        .map(|(idx, pet)| (true, idx, pet))
+        .chain(
+            enemies
+                .pets
+                .iter()
+                .enumerate()
+                .map(|(idx, pet)| (false, idx, pet)),
+        )
+        .filter(|(_, _, pet)| pet.ability == Some(Ability::DamageRandomEnemies))
+        .map(|(is_friend, idx, pet)| (pet.pet.attack, is_friend, idx))
+        .collect::<Vec<_>>();
+    order.sort_by(|(attack, ..), (other_attack, ..)| other_attack.cmp(attack));
+
+    for (_, is_friend, idx) in order {
+        let (team, opponents) = if is_friend {
+            (&mut *friends, &mut *enemies)
+        } else {
+            (&mut *enemies, &mut *friends)
+        };
+        let pet = &team.pets[idx];
+        if !pet.is_alive() {
+            continue;
+        }
+        let (level, effect_atk, stats_idx) = (pet.pet.level, pet.effect_atk, pet.stats_idx);
+        let mut damage_dealt = 0;
+        for _ in 0..level {
+            let alive = opponents
+                .pets
+                .iter()
+                .enumerate()
+                .filter(|(_, enemy)| enemy.is_alive())
+                .map(|(enemy_idx, _)| enemy_idx)
+                .collect::<Vec<usize>>();
+            if let Some(enemy_idx) = rng.index(alive.len()).map(|i| alive[i]) {
+                damage_dealt += opponents.damage(enemy_idx, effect_atk, false, Some(stats_idx));
+            }
+        }
+        let stats = &mut team.stats[stats_idx];
+        stats.effect_triggers += 1;
+        stats.damage_dealt += damage_dealt;
+    }
+    // Start of battle is part of the first turn.
+    resolve_faints(friends, enemies, rng, 1);
+}
+
+fn outcome(friends: &Side, enemies: &Side) -> Option<Outcome> {
+    match (friends.pets.is_empty(), enemies.pets.is_empty()) {
+        (true, true) => Some(Outcome::Draw),
+        (false, true) => Some(Outcome::Win),
+        (true, false) => Some(Outcome::Loss),
+        (false, false) => None,
+    }
+}
+
+fn run(mut friends: Side, mut enemies: Side, seed: u64) -> BattleResult {
+    let mut rng = Rng::new(seed);
+    start_of_battle(&mut friends, &mut enemies, &mut rng);
+
+    let mut num_turns = 0;
+    let outcome = loop {
+        if let Some(outcome) = outcome(&friends, &enemies) {
+            break outcome;
+        }
+        if num_turns > MAX_TURNS {
+            break Outcome::None;
+        }
+        // Front pets attack each other at the same time.
+        let (friend_front, enemy_front) = (friends.pets[0].stats_idx, enemies.pets[0].stats_idx);
+        let friend_hit = friends.pets[0].hit();
+        let enemy_hit = enemies.pets[0].hit();
+        friends.stats[friend_front].damage_dealt += enemies.receive(friend_hit, friend_front);
+        enemies.stats[enemy_front].damage_dealt += friends.receive(enemy_hit, enemy_front);
+
+        num_turns += 1;
+        resolve_faints(&mut friends, &mut enemies, &mut rng, num_turns);
+    };
+
+    let (friend_team, friend_stats) = friends.state();
+    let (enemy_team, enemy_stats) = enemies.state();
+    BattleResult {
+        outcome,
+        friend_team,
+        enemy_team,
+        num_turns,
+        seed,
+        pet_stats: BattleStats {
+            friend_team: friend_stats,
+            enemy_team: enemy_stats,
+        },
+        unmodeled: vec![],
+    }
+}
+
+/// Names of the pets and held foods on either team with battle effects that aren't modeled.
+/// * Pets with abilities that only activate in the shop are left out. So are unknown names.
+pub fn unmodeled(teams: &Teams) -> Vec<String> {
+    let mut names = vec![];
+    for pet in [&teams.friend_team, &teams.enemy_team]
+        .into_iter()
+        .flat_map(|team| team.pets.iter().flatten())
+    {
+        // Triggers are the same at every level.
+        if let Some(def) = pet_def(&pet.name, MIN_PET_LEVEL) {
+            let in_battle = def.effect_trigger.is_some_and(|trigger| {
+                let trigger = trigger.to_lowercase();
+                !SHOP_TRIGGERS.iter().any(|word| trigger.contains(word))
+            });
+            if in_battle && ability(def.name).is_none() {
+                names.push(def.name);
+            }
+        }
+        if let Some(food) = pet.item.as_deref().and_then(food_def) {
+            if item_effect(food.name).is_none() {
+                names.push(food.name);
+            }
+        }
+    }
+    names.sort_unstable();
+    names.dedup();
+    names.into_iter().map(str::to_owned).collect()
+}
+
+/// Fight two teams until one has no pets left or more than [`MAX_TURNS`] turns are fought.
+/// * Returns an error message if a pet or item is unknown.
+pub fn fight(teams: &Teams, seed: u64) -> Result<BattleResult, String> {
+    let friends = Side::try_from(&teams.friend_team)?;
+    let enemies = Side::try_from(&teams.enemy_team)?;
+    Ok(BattleResult {
+        unmodeled: unmodeled(teams),
+        ..run(friends, enemies, seed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fighter(name: &str, attack: usize, health: usize, item: Option<&str>) -> Fighter {
+        let mut pet = Fighter::summon(name, attack, health);
+        pet.pet.item = item.map(str::to_owned);
+        pet
+    }
+
+    fn side(name: &str, pets: Vec<Fighter>) -> Side {
+        Side::new(name, pets)
+    }
+
+    #[test]
+    fn test_item_damage() {
+        let mut steak_pet = fighter("Dog", 3, 5, Some("Steak"));
+        let hit = steak_pet.hit();
+        assert_eq!(hit.damage, 23);
+        // Single use foods are used up.
+        assert_eq!(steak_pet.pet.item, None);
+
+        let mut melon_pet = fighter("Fish", 2, 10, Some("Melon"));
+        melon_pet.take_damage(hit.damage, hit.kills);
+        assert_eq!((melon_pet.pet.health, melon_pet.pet.item), (7, None));
+
+        // Garlic never reduces damage below 1.
+        let mut garlic_pet = fighter("Fish", 2, 10, Some("Garlic"));
+        garlic_pet.take_damage(2, false);
+        assert_eq!(garlic_pet.pet.health, 9);
+
+        let mut peanut_pet = fighter("Scorpion", 1, 1, Some("Peanut"));
+        let hit = peanut_pet.hit();
+        let mut big_pet = fighter("Fish", 50, 50, None);
+        big_pet.take_damage(hit.damage, hit.kills);
+        assert!(!big_pet.is_alive());
+    }
+
+    #[test]
+    fn test_faint_effects() {
+        let mut team = side(
+            "Friend",
+            vec![
+                fighter("Dog", 1, 0, Some("Honey")),
+                fighter("Fish", 2, 0, Some("Mushroom")),
+                fighter("Beaver", 3, 3, None),
+            ],
+        );
+        team.resolve_faints(&mut Rng::new(0), 1);
+
+        let names = team
+            .pets
+            .iter()
+            .map(|pet| (pet.pet.name.as_str(), pet.pet.attack, pet.pet.health))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![("Bee", 1, 1), ("Fish", 1, 1), ("Beaver", 3, 3)]);
+        assert_eq!(team.fainted.len(), 2);
+        // Respawned pets lose their item.
+        assert_eq!(team.pets[1].pet.item, None);
+    }
+
+    #[test]
+    fn test_run() {
+        let friends = side(
+            "Friend",
+            vec![fighter("Dog", 5, 5, None), fighter("Fish", 1, 1, None)],
+        );
+        let enemies = side("Enemy", vec![fighter("Beaver", 2, 8, None)]);
+        let result = run(friends.clone(), enemies.clone(), 42);
+
+        assert_eq!(result.outcome, Outcome::Win);
+        assert_eq!(result.num_turns, 2);
+        assert_eq!(result.friend_team.friends[0].health, 1);
+        assert_eq!(result.enemy_team.fainted.len(), 1);
+        let dog = &result.pet_stats.friend_team[0];
+        assert_eq!((dog.damage_dealt, dog.damage_taken, dog.kills), (8, 4, 1));
+        assert_eq!(result.pet_stats.enemy_team[0].fainted_turn, Some(2));
+        // Same seed gives the same battle.
+        assert_eq!(run(friends, enemies, 42), result);
+
+        let stalemate = run(
+            side("Friend", vec![fighter("Dog", 0, 5, None)]),
+            side("Enemy", vec![fighter("Fish", 0, 5, None)]),
+            0,
+        );
+        assert_eq!(stalemate.outcome, Outcome::None);
+        assert_eq!(stalemate.num_turns, MAX_TURNS + 1);
+    }
+
+    #[test]
+    fn test_faint_abilities() {
+        let pets = |side: &Side| {
+            side.pets
+                .iter()
+                .map(|pet| (pet.pet.name.clone(), pet.pet.attack, pet.pet.health))
+                .collect::<Vec<_>>()
+        };
+        let mut flamingo = fighter("Flamingo", 3, 0, None);
+        flamingo.effect_atk = 1;
+        flamingo.effect_health = 1;
+        let mut turtle = fighter("Turtle", 1, 0, None);
+        turtle.pet.level = 2;
+        let mut team = side(
+            "Friend",
+            vec![
+                flamingo,
+                turtle,
+                fighter("Dog", 3, 3, None),
+                fighter("Fish", 2, 2, None),
+            ],
+        );
+        team.resolve_faints(&mut Rng::new(0), 1);
+
+        // Fainted pets behind aren't buffed. Turtle gives one food per level.
+        assert_eq!(
+            pets(&team),
+            vec![("Dog".to_owned(), 4, 4), ("Fish".to_owned(), 3, 3)]
+        );
+        assert!(team
+            .pets
+            .iter()
+            .all(|pet| pet.pet.item.as_deref() == Some("Melon")));
+        assert_eq!(team.stats[0].stats_granted, 4);
+
+        let mut sheep = fighter("Sheep", 2, 0, None);
+        sheep.effect_atk = 2;
+        sheep.effect_health = 2;
+        let mut team = side("Friend", vec![sheep, fighter("Dog", 3, 3, None)]);
+        team.resolve_faints(&mut Rng::new(0), 1);
+
+        assert_eq!(
+            pets(&team),
+            vec![
+                ("Ram".to_owned(), 2, 2),
+                ("Ram".to_owned(), 2, 2),
+                ("Dog".to_owned(), 3, 3)
+            ]
+        );
+        assert_eq!(team.stats[0].summons, 2);
+    }
+
+    #[test]
+    fn test_faint_damage_all() {
+        let mut hedgehog = fighter("Hedgehog", 3, 0, None);
+        hedgehog.effect_atk = 2;
+        let mut friends = side(
+            "Friend",
+            vec![
+                hedgehog,
+                fighter("Dog", 3, 3, None),
+                fighter("Fish", 2, 2, None),
+            ],
+        );
+        let mut enemies = side(
+            "Enemy",
+            vec![fighter("Beaver", 3, 2, None), fighter("Pig", 4, 5, None)],
+        );
+        resolve_faints(&mut friends, &mut enemies, &mut Rng::new(0), 1);
+
+        let health = |side: &Side| {
+            side.pets
+                .iter()
+                .map(|pet| (pet.pet.name.clone(), pet.pet.health))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(health(&friends), vec![("Dog".to_owned(), 1)]);
+        assert_eq!(health(&enemies), vec![("Pig".to_owned(), 3)]);
+        // Only the enemy knocked out is credited.
+        let hedgehog = &friends.stats[0];
+        assert_eq!((hedgehog.kills, hedgehog.damage_dealt), (1, 4));
+    }
+
+    #[test]
+    fn test_level_clamped() {
+        let ant = |level| SimplePet {
+            name: "Ant".to_owned(),
+            level: Some(level),
+            ..Default::default()
+        };
+
+        assert_eq!(Fighter::try_from(&ant(0)).unwrap().pet.level, MIN_PET_LEVEL);
+        assert_eq!(Fighter::try_from(&ant(4)).unwrap().pet.level, MAX_PET_LEVEL);
+    }
+
+    #[test]
+    fn test_unmodeled() {
+        let pet = |name: &str| {
+            Some(SimplePet {
+                name: name.to_owned(),
+                ..Default::default()
+            })
+        };
+        let teams = Teams {
+            friend_team: SimpleTeam {
+                name: "Friend".to_owned(),
+                pets: vec![pet("Ant"), pet("Dodo"), None],
+            },
+            enemy_team: SimpleTeam {
+                name: "Enemy".to_owned(),
+                // Fish levels up in the shop.
+                pets: vec![pet("Fish"), pet("Dodo")],
+            },
+            seed: None,
+        };
+
+        assert_eq!(unmodeled(&teams), vec!["Dodo".to_owned()]);
+    }
+
+    #[test]
+    fn test_teams_json() {
+        let teams: Teams = serde_json::from_str(
+            r#"{"friend_team": {"name": "Friend", "pets": [{"name": "Slot", "attack": null, "health": null, "level": null, "item": null}, null]}, "enemy_team": {"name": "Enemy", "pets": []}}"#,
+        )
+        .unwrap();
+        let friends = Side::try_from(&teams.friend_team).unwrap();
+
+        // Empty slots are skipped.
+        assert!(friends.pets.is_empty());
+        assert_eq!(fight(&teams, 0).unwrap().outcome, Outcome::Draw);
+    }
+}
@@ -0,0 +1,115 @@
+//! Conversion of teams to `saptest` teams. Only built with the `saptest` feature as `saptest` doesn't compile to wasm.
+use saptest::{
+    effects::{
+        actions::{Action, StatChangeType},
+        effect::Effect,
+        state::{Position, Target},
+        trigger::TRIGGER_START_BATTLE,
+    },
+    error::SAPTestError,
+    pets::pet::{MAX_PET_LEVEL, MAX_PET_STATS, MIN_PET_LEVEL, MIN_PET_STATS},
+    Entity, Food, FoodName, Pet, PetName, Statistics, Team,
+};
+use std::str::FromStr;
+
+use crate::{
+    battle::MAX_TEAM_SIZE,
+    team::{SimplePet, SimpleTeam, EMPTY_SLOT_NAME},
+};
+
+/// Single use effect giving a pet its temporary stats at the start of battle.
+pub fn temp_stats_effect(stats: Statistics) -> Effect {
+    Effect {
+        owner: None,
+        entity: Entity::Pet,
+        trigger: TRIGGER_START_BATTLE,
+        target: Target::Friend,
+        position: Position::OnSelf,
+        action: Action::Add(StatChangeType::StaticValue(stats)),
+        uses: Some(1),
+        temp: true,
+    }
+}
+
+/// Build a `saptest` pet. Stats and levels are clamped to the allowed bounds.
+/// * Temporary stats are kept apart from the pet's stats as a [`temp_stats_effect`].
+pub fn build_pet(simple_pet: SimplePet) -> Result<Pet, SAPTestError> {
+    let item = simple_pet
+        .item
+        .map(|item_name| FoodName::from_str(&item_name).and_then(Food::try_from))
+        .transpose()?;
+
+    let pet_lvl = simple_pet
+        .level
+        .map_or(1, |lvl| lvl.clamp(MIN_PET_LEVEL, MAX_PET_LEVEL));
+
+    PetName::from_str(&simple_pet.name)
+        .and_then(|pet_name| Pet::new(pet_name, None, pet_lvl))
+        .map(|mut pet| {
+            //  Assign item.
+            pet.item = item;
+            // Assign stats if given.
+            if let Some(Ok(attack)) = simple_pet.attack.map(TryInto::<isize>::try_into) {
+                pet.stats.attack = attack.clamp(MIN_PET_STATS, MAX_PET_STATS)
+            }
+            if let Some(Ok(health)) = simple_pet.health.map(TryInto::<isize>::try_into) {
+                pet.stats.health = health.clamp(MIN_PET_STATS, MAX_PET_STATS)
+            }
+            // Add temporary stats as an effect.
+            let temp_stats = Statistics {
+                attack: simple_pet
+                    .temp_attack
+                    .and_then(|attack| isize::try_from(attack).ok())
+                    .map_or(0, |attack| attack.clamp(MIN_PET_STATS, MAX_PET_STATS)),
+                health: simple_pet
+                    .temp_health
+                    .and_then(|health| isize::try_from(health).ok())
+                    .map_or(0, |health| health.clamp(MIN_PET_STATS, MAX_PET_STATS)),
+            };
+            if temp_stats != Statistics::default() {
+                pet.effect.push(temp_stats_effect(temp_stats))
+            }
+            pet
+        })
+}
+
+/// Build a `saptest` team of `team_size` pets. Empty slots are kept.
+pub fn build_team(simple_team: SimpleTeam, team_size: usize) -> Result<Team, SAPTestError> {
+    let pets = simple_team
+        .slots(team_size)
+        .map_err(|reason| SAPTestError::InvalidTeamAction {
+            subject: "Pet Position".to_owned(),
+            reason,
+        })?
+        .into_iter()
+        // If pet in slot, generate pet. Otherwise, treat as empty slot.
+        .map(|slot| {
+            slot.filter(|pet| pet.name != EMPTY_SLOT_NAME)
+                .cloned()
+                .map(build_pet)
+                .transpose()
+        })
+        .collect::<Result<Vec<Option<Pet>>, SAPTestError>>()?;
+
+    Team::new(&pets, team_size).map(|mut team| {
+        let _ = team.set_name(&simple_team.name);
+        team
+    })
+}
+
+impl TryFrom<SimplePet> for Pet {
+    type Error = SAPTestError;
+
+    fn try_from(simple_pet: SimplePet) -> Result<Self, Self::Error> {
+        build_pet(simple_pet)
+    }
+}
+
+/// Builds a team of [`MAX_TEAM_SIZE`]. Use [`build_team`] for other team sizes.
+impl TryFrom<SimpleTeam> for Team {
+    type Error = SAPTestError;
+
+    fn try_from(simple_team: SimpleTeam) -> Result<Self, Self::Error> {
+        build_team(simple_team, MAX_TEAM_SIZE)
+    }
+}
@@ -0,0 +1,38 @@
+/// A pet at a single level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PetDef {
+    pub name: &'static str,
+    pub pack: &'static str,
+    pub tier: usize,
+    pub lvl: usize,
+    pub attack: usize,
+    pub health: usize,
+    pub effect_trigger: Option<&'static str>,
+    pub effect_atk: usize,
+    pub effect_health: usize,
+    pub is_token: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoodDef {
+    pub name: &'static str,
+    pub tier: usize,
+    /// If pets can hold the food.
+    pub holdable: bool,
+}
+
+include!(concat!(env!("OUT_DIR"), "/defs.rs"));
+
+/// Definition of pet `name` at level `lvl`. Names are compared ignoring case.
+/// * Teams don't have a pack so the pet's first row in the database is used.
+pub fn pet_def(name: &str, lvl: usize) -> Option<&'static PetDef> {
+    PETS.iter()
+        .find(|pet| pet.lvl == lvl && pet.name.eq_ignore_ascii_case(name))
+}
+
+/// Definition of food `name`. Names are compared ignoring case.
+pub fn food_def(name: &str) -> Option<&'static FoodDef> {
+    FOODS
+        .iter()
+        .find(|food| food.name.eq_ignore_ascii_case(name))
+}
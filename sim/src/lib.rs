@@ -0,0 +1,25 @@
+//! A simplified battle core that compiles to wasm.
+//!
+//! Pet and food definitions are baked in from the `saptest` database at build time. Only the parts of a battle below
+//! are modeled so results can differ from the backend:
+//! * Pets attack front to front until a team has no pets left or more than [`MAX_TURNS`] turns are fought.
+//! * Held foods. See [`battle::ItemEffect`].
+//! * The abilities of a few pets. See [`battle::Ability`].
+//!
+//! Pets and foods with effects that aren't modeled are listed by [`unmodeled`].
+//!
+//! Random targets are picked with a seeded RNG that doesn't match `saptest`'s so the same seed won't give the same
+//! battle as the backend.
+//!
+//! With the `saptest` feature, teams convert to `saptest` teams in the `convert` module.
+pub mod battle;
+#[cfg(feature = "saptest")]
+pub mod convert;
+pub mod defs;
+mod rng;
+pub mod stats;
+pub mod team;
+
+pub use battle::{fight, unmodeled, BattleResult, Outcome, PetState, TeamState, MAX_TURNS};
+pub use stats::{BattleStats, PetStats};
+pub use team::{SimplePet, SimpleTeam, Teams};
@@ -0,0 +1,23 @@
+/// SplitMix64. Small and dependency-free so it works in wasm.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random index below `len`. [`None`] if `len` is 0.
+    pub fn index(&mut self, len: usize) -> Option<usize> {
+        (len > 0).then(|| (self.next_u64() % len as u64) as usize)
+    }
+}
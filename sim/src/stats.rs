@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Performance of a pet in a single battle. Same fields as the backend's `pet_stats`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetStats {
+    pub id: Option<String>,
+    pub name: String,
+    pub damage_dealt: usize,
+    pub damage_taken: usize,
+    pub kills: usize,
+    /// Times the pet's ability triggered. Attacks aren't counted.
+    pub effect_triggers: usize,
+    /// Attack and health given to other pets on the team.
+    pub stats_granted: usize,
+    /// Pets summoned by the pet's ability or held food.
+    pub summons: usize,
+    pub fainted_turn: Option<usize>,
+}
+
+/// Pet statistics of both teams. Pets on a team before the battle come first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BattleStats {
+    pub friend_team: Vec<PetStats>,
+    pub enemy_team: Vec<PetStats>,
+}
+
+impl PetStats {
+    pub fn new(name: &str) -> Self {
+        PetStats {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Name of an empty slot.
+pub const EMPTY_SLOT_NAME: &str = "Slot";
+
+/// Teams in the same format as the backend's `/battle` request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Teams {
+    pub friend_team: SimpleTeam,
+    pub enemy_team: SimpleTeam,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A team. Position 0 is the front of the team.
+/// * Without explicit positions, pets are in slot order and [`None`] is an empty slot. See [`SimpleTeam::slots`].
+/// * Shared with the backend so both accept the same teams.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimpleTeam {
+    pub name: String,
+    pub pets: Vec<Option<SimplePet>>,
+}
+
+impl SimpleTeam {
+    /// Pets by slot, front first. Trailing empty slots are dropped if pets have positions.
+    /// * If any pet has a `position`, all pets must have one and each pet is put in its slot.
+    /// * Otherwise, pets are in list order.
+    /// * Returns an error message if a position is missing, repeated, or outside a team of `team_size`.
+    pub fn slots(&self, team_size: usize) -> Result<Vec<Option<&SimplePet>>, String> {
+        let mut pets = self.pets.iter().flatten();
+        if pets.clone().all(|pet| pet.position.is_none()) {
+            return Ok(self.pets.iter().map(Option::as_ref).collect());
+        }
+        let mut slots = vec![None; team_size];
+        pets.try_for_each(|pet| {
+            let Some(position) = pet.position else {
+                return Err(format!(
+                    "Team {}: {} has no position. Either all or no pets have a position.",
+                    self.name, pet.name
+                ));
+            };
+            match slots.get_mut(position) {
+                Some(slot @ None) => {
+                    *slot = Some(pet);
+                    Ok(())
+                }
+                Some(Some(_)) => Err(format!(
+                    "Team {}: More than one pet at position {position}.",
+                    self.name
+                )),
+                None => Err(format!(
+                    "Team {}: Position {position} is outside a team of {team_size}.",
+                    self.name
+                )),
+            }
+        })?;
+        while slots.last().is_some_and(Option::is_none) {
+            slots.pop();
+        }
+        Ok(slots)
+    }
+
+    /// Pets front first without empty slots. See [`SimpleTeam::slots`].
+    pub fn ordered_pets(&self, team_size: usize) -> Result<Vec<&SimplePet>, String> {
+        Ok(self.slots(team_size)?.into_iter().flatten().collect())
+    }
+}
+
+/// A pet. Unset stats are the pet's base stats at its level.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimplePet {
+    pub name: String,
+    pub attack: Option<usize>,
+    pub health: Option<usize>,
+    pub level: Option<usize>,
+    pub item: Option<String>,
+    /// Slot of the pet. 0 is the front of the team.
+    #[serde(default)]
+    pub position: Option<usize>,
+    /// Attack only kept for this battle. ex. From shop foods like Cupcake.
+    #[serde(default)]
+    pub temp_attack: Option<usize>,
+    /// Health only kept for this battle.
+    #[serde(default)]
+    pub temp_health: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_team_slots() {
+        let pet = |name: &str, position: Option<usize>| SimplePet {
+            name: name.to_owned(),
+            position,
+            ..Default::default()
+        };
+        let team = |pets: Vec<Option<SimplePet>>| SimpleTeam {
+            name: "Friend".to_owned(),
+            pets,
+        };
+        let names = |slots: Vec<Option<&SimplePet>>| {
+            slots
+                .into_iter()
+                .map(|pet| pet.map(|pet| pet.name.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // List order without positions.
+        let unpositioned = team(vec![Some(pet("Ant", None)), None, Some(pet("Dog", None))]);
+        assert_eq!(
+            names(unpositioned.slots(5).unwrap()),
+            vec![Some("Ant".to_owned()), None, Some("Dog".to_owned())]
+        );
+
+        // Positions override list order.
+        let positioned = team(vec![
+            Some(pet("Ant", Some(2))),
+            None,
+            Some(pet("Dog", Some(0))),
+        ]);
+        assert_eq!(
+            names(positioned.slots(5).unwrap()),
+            vec![Some("Dog".to_owned()), None, Some("Ant".to_owned())]
+        );
+        let ordered = positioned.ordered_pets(5).unwrap();
+        assert_eq!(
+            ordered.iter().map(|pet| &pet.name).collect::<Vec<_>>(),
+            ["Dog", "Ant"]
+        );
+
+        let repeated = team(vec![Some(pet("Ant", Some(1))), Some(pet("Dog", Some(1)))]);
+        let missing = team(vec![Some(pet("Ant", Some(1))), Some(pet("Dog", None))]);
+        let outside = team(vec![Some(pet("Ant", Some(5)))]);
+        assert!(repeated.slots(5).is_err());
+        assert!(missing.slots(5).is_err());
+        assert!(outside.slots(5).is_err());
+    }
+}